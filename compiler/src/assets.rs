@@ -0,0 +1,118 @@
+//! This module implements static assets. An asset is any file within a source directory which
+//! is not an md2 source file or a directory template, for instance an image, a stylesheet, or a
+//! download. Assets are copied into the output directory alongside the finished documents, at
+//! the same relative path as in the source directory.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use glob::{MatchOptions, Pattern};
+
+/// Selects which assets are copied into the output directory.
+///
+/// A pattern containing a slash is matched against the path of the asset relative to the
+/// source directory, for instance `images/*.png`, whereas any other pattern is matched against
+/// the name of the asset alone, for instance `*.png`.
+#[derive(Clone, Default)]
+pub struct AssetFilter {
+    /// If empty, then every asset is copied, except hidden files like `.DS_Store`, and the
+    /// contents of hidden directories like `.git`. Otherwise, only the assets matching at
+    /// least one of these patterns are copied.
+    pub include: Vec<Pattern>,
+    /// The assets matching any of these patterns are never copied.
+    pub exclude: Vec<Pattern>
+}
+
+impl AssetFilter {
+    /// Returns true if the asset at `path`, relative to the source directory, should be copied.
+    pub fn accepts(&self, path: &Path) -> bool {
+        let is_included = match self.include.len() {
+            0 => !is_hidden(path),
+            _ => self.include.iter().any(|pattern| matches(pattern, path))
+        };
+        return is_included && !self.exclude.iter().any(|pattern| matches(pattern, path));
+    }
+}
+
+fn matches(pattern: &Pattern, path: &Path) -> bool {
+    let options = MatchOptions { require_literal_separator: true, ..MatchOptions::new() };
+    if pattern.as_str().contains('/') {
+        return pattern.matches_path_with(path, options);
+    }
+    let Some(name) = path.file_name() else { return false; };
+    return pattern.matches_path_with(Path::new(name), options);
+}
+
+fn is_hidden(path: &Path) -> bool {
+    return path.components().any(|component| {
+        component.as_os_str().to_string_lossy().starts_with('.')
+    });
+}
+
+/// Copies the asset at `src` to `dest`, unless `dest` is already an up to date copy of it.
+/// Returns true if the asset was copied.
+pub fn copy_asset(src: &Path, dest: &Path) -> std::io::Result<bool> {
+    let src_metadata = std::fs::metadata(src)?;
+    if let Ok(dest_metadata) = std::fs::metadata(dest) {
+        let is_up_to_date = dest_metadata.len() == src_metadata.len()
+            && dest_metadata.modified()? >= src_metadata.modified()?;
+        if is_up_to_date { return Ok(false); }
+    }
+    std::fs::copy(src, dest)?;
+    return Ok(true);
+}
+
+/// The name of the file within the output directory which lists the outputs of the last build,
+/// one path per line, relative to the output directory.
+pub const MANIFEST_FILE_NAME: &str = ".merry-outputs";
+
+/// Removes every file listed in the manifest of the previous build of `dest_dir_path` which is
+/// not among `outputs`, the files produced by this build, and then every directory left empty by
+/// doing so. Files which no build produced, like `CNAME` or the contents of `.git`, are never
+/// touched. Returns the paths of the removed files.
+pub fn remove_stale_outputs(dest_dir_path: &Path, outputs: &HashSet<PathBuf>)
+-> std::io::Result<Vec<PathBuf>>
+{
+    let mut removed: Vec<PathBuf> = Vec::new();
+    let manifest = match std::fs::read_to_string(dest_dir_path.join(MANIFEST_FILE_NAME)) {
+        Ok(manifest) => manifest,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(removed),
+        Err(err) => return Err(err)
+    };
+    for line in manifest.lines() {
+        let relative_path = Path::new(line);
+        // A manifest which was edited by hand must not lead outside of the output directory.
+        let is_within = relative_path.components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if line.len() == 0 || !is_within { continue; }
+        let path = dest_dir_path.join(relative_path);
+        if outputs.contains(&path) || !path.is_file() { continue; }
+        std::fs::remove_file(&path)?;
+        remove_empty_parents(dest_dir_path, &path)?;
+        removed.push(path);
+    }
+    return Ok(removed);
+}
+
+/// Removes the directories containing `path` which are empty, from the innermost outwards,
+/// stopping at `dest_dir_path`.
+fn remove_empty_parents(dest_dir_path: &Path, path: &Path) -> std::io::Result<()> {
+    for dir_path in path.ancestors().skip(1) {
+        if dir_path == dest_dir_path || !dir_path.starts_with(dest_dir_path) { break; }
+        if std::fs::read_dir(dir_path)?.next().is_some() { break; }
+        std::fs::remove_dir(dir_path)?;
+    }
+    return Ok(());
+}
+
+/// Writes the manifest of `dest_dir_path`, listing `outputs`, the files produced by this build,
+/// so that the next build can remove those it no longer produces.
+pub fn write_manifest(dest_dir_path: &Path, outputs: &HashSet<PathBuf>) -> std::io::Result<()> {
+    let mut lines: Vec<String> = outputs.iter()
+        .filter_map(|path| path.strip_prefix(dest_dir_path).ok())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect();
+    lines.sort();
+    let mut manifest = lines.join("\n");
+    manifest.push('\n');
+    return std::fs::write(dest_dir_path.join(MANIFEST_FILE_NAME), manifest);
+}
@@ -1,58 +1,482 @@
 //! This module implements the builtin directive set. 
 //!
-//! - `href <tag> <url>` converts all spans marked with `<tag>` into hyperlinks to `<url>`.
+//! - `href <tag> <url> [title]` converts all spans marked with `<tag>` into hyperlinks to `<url>`.
+//! - `link <tag> <url> [title]` is like `href`, but acts on the whole document regardless of
+//!   where it appears.
 //! - `rewrite <tag> <command>` 
+//! - `class <tag> <css-class...>` adds the given CSS classes to all nodes marked with `<tag>`.
+//! - `element <tag> <html-element>` renders all nodes marked with `<tag>` as `<html-element>`.
+//! - `title <text>` declares the title of the document.
+//! - `lang <language>` declares the language of the document.
+//! - `template <path>` selects the template used to compile the document.
+//! - `meta <name> <value>` declares some other metadata about the document, for instance
+//!   its `description`, `author`, or `date`.
+//! - `include <path>` splices the content of another source file into the document.
+//! - `define <name> <value>` replaces every empty span marked with `<name>` with `<value>`.
+//! - `ref <tag> <target>` converts all spans marked with `<tag>` into hyperlinks to the section
+//!   or document `<target>`, for instance `install`, `guide.md2#install`, or `guide.md2`.
+//!
+//! An invocation of a builtin directive with too few arguments is reported and ignored.
 
 use std::process::{Command, Stdio};
-use std::io::{Write, read_to_string, Read};
+use std::io::{Write, Read};
 use crate::{assert_matches, req};
-use crate::misc::remove_first;
+use crate::misc::{relative_url_path, remove_first};
 use crate::mtree::ast::DirectiveInvocation;
-use crate::ctree::{AnyInline, BlockChild, Container, Context, HTML, HyperlinkText, InlineHTML, InlineRoot, Writable};
-use crate::report::{Issue, AnnotatedSourceSection, Severity, Elaboration, QuoteElaboration, SourceQuoteElaboration};
+use crate::ctree::{interpret_mtree_block, slugify, AnyInline, Attributes, Block, BlockChild, Container, Context, CrossReference, HTML, HyperlinkText, InlineHTML, InlineRoot, LinkDefinition, MetadataEntry, PlainText, Root, Writable};
+use crate::ltree::{make_ltree, plural, verify_ltree};
+use crate::mtree::{make_mtree, verify_mtree};
+use crate::report::{Issue, AnnotatedSourceSection, Severity, Elaboration, Fix, QuoteElaboration, SourceQuoteElaboration};
 use crate::rewrite::{rewrite_inline_root, rewrite_subtrees};
 use crate::scan::SourceSpan;
+use crate::source_map::SourceCache;
+
+/// Describes the usage of a builtin directive, as offered by the language server's completions.
+pub struct DirectiveUsage {
+    pub name: &'static str,
+    pub usage: &'static str,
+    /// True if the first argument of the directive is a tag.
+    pub is_tag_directive: bool,
+    /// The number of arguments without which the directive is ignored.
+    pub min_arg_count: usize
+}
+
+pub const BUILTIN_DIRECTIVES: &[DirectiveUsage] = &[
+    DirectiveUsage { name: "href", usage: "href <tag> <url> [title]", is_tag_directive: true,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "link", usage: "link <tag> <url> [title]", is_tag_directive: true,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "rewrite", usage: "rewrite <tag> <command> <args...>", is_tag_directive: true,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "embed", usage: "embed <command> <args...>", is_tag_directive: false,
+        min_arg_count: 1 },
+    DirectiveUsage { name: "class", usage: "class <tag> <css-class...>", is_tag_directive: true,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "element", usage: "element <tag> <html-element>", is_tag_directive: true,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "title", usage: "title <text>", is_tag_directive: false,
+        min_arg_count: 1 },
+    DirectiveUsage { name: "lang", usage: "lang <language>", is_tag_directive: false,
+        min_arg_count: 1 },
+    DirectiveUsage { name: "meta", usage: "meta <name> <value>", is_tag_directive: false,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "template", usage: "template <path>", is_tag_directive: false,
+        min_arg_count: 1 },
+    DirectiveUsage { name: "include", usage: "include <path>", is_tag_directive: false,
+        min_arg_count: 1 },
+    DirectiveUsage { name: "define", usage: "define <name> <value>", is_tag_directive: true,
+        min_arg_count: 2 },
+    DirectiveUsage { name: "ref", usage: "ref <tag> <target>", is_tag_directive: true,
+        min_arg_count: 2 },
+];
+
+/// The tags which are given meaning by the compiler itself, rather than by a directive.
+pub const BUILTIN_TAGS: &[&str] = &["m", "note", "warning", "tip", "danger"];
 
 pub fn builtin_directives<'a, 'b, C>(invocation: DirectiveInvocation<'a>, scope: &mut C, 
     ctx: &mut Context<'a, 'b>) where C: Container<'a>
 {
     if let Some(cmd) = invocation.cmd() {
+        let usage = BUILTIN_DIRECTIVES.iter().find(|usage| usage.name == cmd);
+        if let Some(usage) = usage.filter(|usage| invocation.args().len() < usage.min_arg_count) {
+            ctx.issues.push(missing_arguments_issue(&invocation, usage));
+            return;
+        }
         match cmd {
             "href" => apply_href(invocation.take_args(), scope, ctx),
             "rewrite" => apply_rewrite(invocation.take_args(), scope, ctx), 
             "embed" => apply_embed(invocation.take_args(), scope, ctx),
+            "class" => apply_class(invocation.take_args(), scope, ctx),
+            "element" => apply_element(invocation.take_args(), scope, ctx),
+            "title" => apply_title(invocation.take_args(), ctx),
+            "lang" => apply_lang(invocation.take_args(), ctx),
+            "meta" => apply_meta(invocation.take_args(), ctx),
+            "template" => apply_template(invocation.take_args(), ctx),
+            "include" => apply_include(invocation.take_args(), scope, ctx),
+            "define" => apply_define(invocation.take_args(), scope, ctx),
+            "link" => apply_link(invocation.take_args(), ctx),
+            "ref" => apply_ref(invocation.take_args(), scope, ctx),
             _ => ()
         }
     }
 }
 
+fn missing_arguments_issue<'a>(invocation: &DirectiveInvocation<'a>, usage: &DirectiveUsage) 
+-> Issue<'a> 
+{
+    let first = invocation.args.first().unwrap();
+    let last = invocation.args.last().unwrap();
+    let span = SourceSpan { source: first.source, file: first.file, begin: first.begin, end: last.end };
+    let mut quote = AnnotatedSourceSection::from_span(&span);
+    quote.highlight(span.begin.byte_pos, span.end.byte_pos);
+    let arg_count = invocation.args().len();
+    Issue {
+        quote,
+        title: format!("Directive `{}` expects at least {} argument{}, but was given {}", usage.name,
+            usage.min_arg_count, plural(usage.min_arg_count), arg_count).into(),
+        subtext: format!("The directive is written `| {}`. This invocation will be ignored.", 
+            usage.usage).into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
+
 fn apply_href<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
 where C: Container<'a> 
 {
-    let Some(tag) = args.get(0) else { /* TODO: Issue */ return; };
-    let Some(href) = args.get(1) else { /* TODO: Issue */ return; };
-    
+    let Some(link) = make_link_definition(&args, ctx) else { return; };
+    let scope_links = ctx.link_scopes.last_mut().expect("directives are invoked within a container");
+    if let Some(prev_link) = scope_links.iter().find(|prev| prev.tag.as_ref() == link.tag.as_ref()) {
+        ctx.issues.push(redefined_link_issue(&link, prev_link, 
+            "Spans preceding the earlier definition link to its URL, and the spans between the two\n\
+             definitions link to this one. Consider marking them with distinct tags instead."));
+    }
+    scope_links.push(link);
+    make_hyperlinks(&link, scope);
+}
+
+/// Declares a document-global link. Unlike `href`, a global link acts on every span marked with
+/// its tag, regardless of where the directive appears, unless a scoped `href` consumed it first.
+fn apply_link<'a, 'b>(args: Vec<SourceSpan<'a>>, ctx: &mut Context<'a, 'b>) {
+    let Some(link) = make_link_definition(&args, ctx) else { return; };
+    if let Some(prev_link) = ctx.links.iter().find(|prev| prev.tag.as_ref() == link.tag.as_ref()) {
+        ctx.issues.push(redefined_link_issue(&link, prev_link, 
+            "Only the first definition of a global link takes effect. This definition will be ignored."));
+        return;
+    }
+    ctx.links.push(link);
+}
+
+/// Interprets the arguments `<tag> <url> [title]` shared by the `href` and `link` directives,
+/// reporting the URL if it is malformed.
+fn make_link_definition<'a, 'b>(args: &[SourceSpan<'a>], ctx: &mut Context<'a, 'b>) 
+-> Option<LinkDefinition<'a>> 
+{
+    let tag = args.get(0).cloned()?;
+    let href = args.get(1).cloned()?;
+    let title = args.get(2..).and_then(join_args);
+    if let Some(problem) = find_url_problem(href.as_ref()) {
+        ctx.issues.push(malformed_url_issue(href, problem));
+    }
+    return Some(LinkDefinition { tag, href, title });
+}
+
+/// Converts every span within `scope` which is marked with the link's tag into a hyperlink.
+pub fn make_hyperlinks<'a, C>(link: &LinkDefinition<'a>, scope: &mut C) where C: Container<'a> {
     rewrite_subtrees(scope, &mut |node| {
-        let Some(inline_content) = node.inline_content_mut() else { return; };
-        rewrite_inline_root(inline_content, &mut |inline_node| {
-            let AnyInline::TaggedSpan(tagged_span) = inline_node else { return; };
-            if remove_first(&mut tagged_span.tags, |t| t.as_ref() == tag.as_ref()).is_none() { return; };
-            let mut tmp = AnyInline::Hyperlink(HyperlinkText {
-                href: href.clone(),
-                child_root: InlineRoot::default()
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                let AnyInline::TaggedSpan(tagged_span) = inline_node else { return; };
+                if remove_first(&mut tagged_span.tags, |t| t.as_ref() == link.tag.as_ref()).is_none() { return; };
+                let mut tmp = AnyInline::Hyperlink(HyperlinkText {
+                    href: link.href,
+                    title: link.title,
+                    child_root: InlineRoot::default()
+                });
+                std::mem::swap(&mut tmp, inline_node);
+                assert_matches!(inline_node, AnyInline::Hyperlink(hyperlink));
+                hyperlink.child_root.children.push(tmp);
             });
-            std::mem::swap(&mut tmp, inline_node);
-            assert_matches!(inline_node, AnyInline::Hyperlink(hyperlink));
-            hyperlink.child_root.children.push(tmp);
-        });
+        }
+    });
+}
+
+fn apply_ref<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
+where C: Container<'a>
+{
+    let Some(tag) = args.get(0) else { return; };
+    let Some(target) = join_args(&args[1..]) else { return; };
+    let (document, section_id) = match target.as_ref().split_once('#') {
+        Some((document, section_id)) => (Some(document), Some(section_id)),
+        None if target.as_ref().ends_with(".md2") => (Some(target.as_ref()), None),
+        None => (None, Some(target.as_ref()))
+    };
+    let document = document.filter(|document| document.len() > 0).map(String::from);
+    let document_path = document.as_ref().map(|document| ctx.cwd.join(document));
+    let section_id = section_id.map(String::from);
+
+    rewrite_subtrees(scope, &mut |node| {
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                let AnyInline::TaggedSpan(tagged_span) = inline_node else { return; };
+                if remove_first(&mut tagged_span.tags, |t| t.as_ref() == tag.as_ref()).is_none() { return; };
+                let mut tmp = AnyInline::CrossReference(CrossReference {
+                    target,
+                    document: document.clone(),
+                    document_path: document_path.clone(),
+                    section_id: section_id.clone(),
+                    text: None,
+                    child_root: InlineRoot::default()
+                });
+                std::mem::swap(&mut tmp, inline_node);
+                assert_matches!(inline_node, AnyInline::CrossReference(reference));
+                reference.child_root.children.push(tmp);
+            });
+        }
     });
 }
 
+/// The targets within a document which a cross-reference can resolve to.
+struct DocumentIndex {
+    title: Option<String>,
+    /// The identifier and heading text of every section, in order of appearance.
+    sections: Vec<(String, String)>
+}
+
+fn index_sections<'a>(block: &mut Block<'a>) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    rewrite_subtrees(block, &mut |node| {
+        let BlockChild::Section(section) = node else { return; };
+        let heading = section.heading.content.plain_text().trim().to_string();
+        sections.push((section.id.clone(), heading));
+    });
+    return sections;
+}
+
+/// Interprets the document at `path`, so that references into it can be resolved. Problems
+/// within that document are not reported here, since they are reported when it is compiled.
+fn index_document<'a>(path: &std::path::Path, sources: &SourceCache<'a>) 
+-> std::io::Result<DocumentIndex>
+{
+    let (file, source) = sources.read(path)?;
+    let ltree = make_ltree(source, file);
+    let mtree = make_mtree(&ltree);
+    let mut issues: Vec<Issue<'a>> = Vec::new();
+    let cwd = path.parent().map(|parent| parent.to_path_buf()).unwrap_or_default();
+    let mut ctx = Context::new(&mut issues, cwd, sources);
+    let mut block = interpret_mtree_block(mtree.block, &mut ctx);
+    let sections = index_sections(&mut block);
+    let title = Root { block, metadata: ctx.metadata }.title();
+    return Ok(DocumentIndex { title, sections });
+}
+
+/// Returns the title of the document at `path`, as given by [`Root::title`]. Like a document
+/// referenced by a cross-reference, it is interpreted without reporting its problems.
+pub fn document_title(path: &std::path::Path, sources: &SourceCache)
+-> std::io::Result<Option<String>>
+{
+    return Ok(index_document(path, sources)?.title);
+}
+
+/// Resolves the target of every cross-reference within `block`, which is the interpreted
+/// document whose directory is `cwd`. References to other documents are resolved by
+/// interpreting those documents too. Targets which do not exist are reported.
+pub fn resolve_cross_references<'a>(block: &mut Block<'a>, cwd: &std::path::Path, 
+    sources: &SourceCache<'a>, issues: &mut Vec<Issue<'a>>) 
+{
+    let own_index = DocumentIndex { title: None, sections: index_sections(block) };
+    let mut documents: std::collections::HashMap<std::path::PathBuf, Result<DocumentIndex, String>> = 
+        std::collections::HashMap::new();
+    let canonical_cwd = cwd.canonicalize().ok();
+
+    rewrite_subtrees(block, &mut |node| {
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                let AnyInline::CrossReference(reference) = inline_node else { return; };
+                let index = match &reference.document_path {
+                    None => &own_index,
+                    Some(path) => {
+                        let index = documents.entry(path.clone())
+                            .or_insert_with(|| index_document(path, sources).map_err(|err| err.to_string()));
+                        match index {
+                            Ok(index) => index,
+                            Err(err) => {
+                                issues.push(unreadable_reference_issue(reference.target, err.clone()));
+                                return;
+                            }
+                        }
+                    }
+                };
+                // The reference may have been included from a file in another directory, but
+                // its URL must be relative to the finished document.
+                if let (Some(cwd), Some(path)) = (&canonical_cwd, &reference.document_path) {
+                    if let Ok(path) = path.canonicalize() {
+                        reference.document = Some(relative_url_path(cwd, &path));
+                    }
+                }
+                let text = match &reference.section_id {
+                    None => index.title.clone(),
+                    Some(section_id) => {
+                        let section = index.sections.iter().find(|(id, _)| id == section_id);
+                        let Some((_, heading)) = section else {
+                            issues.push(dangling_reference_issue(reference, &index.sections));
+                            // The span keeps its own text, or if it has none, shows the target,
+                            // but it does not link anywhere.
+                            let target = reference.target;
+                            let mut child_root = std::mem::take(&mut reference.child_root);
+                            *inline_node = match child_root.plain_text().trim().len() {
+                                0 => AnyInline::Plain(PlainText { span: target }),
+                                _ => child_root.children.pop().unwrap_or(AnyInline::None)
+                            };
+                            return;
+                        };
+                        Some(heading.clone())
+                    }
+                };
+                if reference.child_root.plain_text().trim().len() == 0 { reference.text = text; }
+            });
+        }
+    });
+}
+
+fn unreadable_reference_issue<'a>(target: SourceSpan<'a>, err: String) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&target);
+    quote.highlight(target.begin.byte_pos, target.end.byte_pos);
+    Issue {
+        quote,
+        title: "Referenced document could not be read".into(),
+        subtext: "The spans marked with this tag will link to the document anyway.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: vec![
+            Elaboration::Quote(QuoteElaboration {
+                caption: "The following error occurred while reading the document...",
+                content: err
+            })
+        ]
+    }
+}
+
+fn dangling_reference_issue<'a>(reference: &CrossReference<'a>, sections: &[(String, String)]) 
+-> Issue<'a> 
+{
+    let target = reference.target;
+    let mut quote = AnnotatedSourceSection::from_span(&target);
+    quote.highlight(target.begin.byte_pos, target.end.byte_pos);
+    let section_id = reference.section_id.clone().unwrap_or_default();
+    // The identifier may have been written as the heading text itself, like `Getting Started`.
+    let slug = slugify(&section_id);
+    let fix = sections.iter().any(|(id, _)| *id == slug).then(|| Fix {
+        description: format!("refer to `{}`", slug),
+        range: (target.end.byte_pos - section_id.len())..target.end.byte_pos,
+        replacement: slug,
+        safe: false
+    });
+    let title = match &reference.document {
+        Some(document) => format!("Section `{}` does not exist in `{}`", section_id, document),
+        None => format!("Section `{}` does not exist", section_id),
+    };
+    Issue {
+        quote,
+        title: title.into(),
+        subtext: "The identifier of a section is derived from its heading, for instance `getting-started` \
+                  for\na section titled \"Getting Started\". The spans marked with this tag will not be \
+                  linked.".into(),
+        severity: Severity::Error,
+        fix,
+        elaborations: Vec::new()
+    }
+}
+
+/// Describes the problem with `url`, if it is malformed. A well-formed URL is either absolute,
+/// beginning with a scheme like `https:`, or a relative path. Either may be followed by a query
+/// and a fragment.
+fn find_url_problem(url: &str) -> Option<&'static str> {
+    if url.len() == 0 { return Some("The URL is empty."); }
+    if url.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"')) {
+        return Some("The URL contains a space, a quote, or an angle bracket. These characters must \
+                     be percent-encoded.");
+    }
+    if url.contains('\\') {
+        return Some("The URL contains a backslash. The segments of a path are separated by forward \
+                     slashes.");
+    }
+    if let Some((before_fragment, fragment)) = url.split_once('#') {
+        if fragment.contains('#') {
+            return Some("The URL contains more than one `#`. Only the first begins the fragment.");
+        }
+        if before_fragment.len() == 0 && fragment.len() == 0 {
+            return Some("The fragment is empty, so the link leads nowhere.");
+        }
+    }
+    // A colon preceding the first slash, question mark, or number sign ends the scheme.
+    let path_begin = url.find(['/', '?', '#']).unwrap_or(url.len());
+    let Some(colon) = url[..path_begin].find(':') else {
+        if url.starts_with("www.") {
+            return Some("The URL has no scheme, so it will be treated as a relative path.");
+        }
+        return None;
+    };
+    let scheme = &url[..colon];
+    let mut scheme_chars = scheme.chars();
+    let is_scheme = scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme_chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !is_scheme {
+        return Some("The text preceding the colon is not a scheme. A scheme begins with a letter and \
+                     contains only\nletters, digits, `+`, `-`, and `.`. A relative path containing a \
+                     colon must begin with `./`.");
+    }
+    let after_scheme = &url[colon + 1..];
+    let is_hierarchical = matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https" | "ftp");
+    if is_hierarchical && !after_scheme.starts_with("//") {
+        return Some("The scheme must be followed by `//` and a host, for instance `https://example.com`.");
+    }
+    if let Some(authority) = after_scheme.strip_prefix("//") {
+        if is_hierarchical && authority.split(['/', '?', '#']).next().unwrap_or("").len() == 0 {
+            return Some("The URL has no host.");
+        }
+    }
+    return None;
+}
+
+fn malformed_url_issue<'a>(href: SourceSpan<'a>, problem: &'static str) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&href);
+    quote.highlight(href.begin.byte_pos, href.end.byte_pos);
+    let fix = href.as_ref().starts_with("www.").then(|| Fix {
+        description: String::from("prefix with `https://`"),
+        range: href.begin.byte_pos..href.begin.byte_pos,
+        replacement: String::from("https://"),
+        safe: false
+    });
+    Issue {
+        quote,
+        title: "Malformed URL".into(),
+        subtext: format!("{}\nThe spans marked with this tag will link to it anyway.", problem).into(),
+        severity: Severity::Warning,
+        fix,
+        elaborations: Vec::new()
+    }
+}
+
+fn redefined_link_issue<'a>(link: &LinkDefinition<'a>, prev_link: &LinkDefinition<'a>, 
+    consequence: &'static str) -> Issue<'a> 
+{
+    let mut quote = AnnotatedSourceSection::from_span(&link.tag);
+    quote.highlight(link.tag.begin.byte_pos, link.tag.end.byte_pos);
+    let mut prev_quote = AnnotatedSourceSection::from_span(&prev_link.tag);
+    prev_quote.highlight(prev_link.tag.begin.byte_pos, prev_link.tag.end.byte_pos);
+    let is_conflict = link.href.as_ref() != prev_link.href.as_ref();
+    let (title, subtext) = match is_conflict {
+        true => (format!("Link `{}` is defined more than once with different URLs", link.tag.as_ref()),
+            consequence),
+        false => (format!("Link `{}` is defined more than once", link.tag.as_ref()),
+            "Both definitions link to the same URL, so this one is redundant."),
+    };
+    Issue {
+        quote,
+        title: title.into(),
+        subtext: subtext.into(),
+        severity: Severity::Warning,
+        fix: None,
+        elaborations: vec![
+            Elaboration::SourceQuote(SourceQuoteElaboration {
+                caption: "It was previously defined here...",
+                content: prev_quote
+            })
+        ]
+    }
+}
+
 fn apply_rewrite<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
 where C: Container<'a>
 {
-    let Some(tag) = args.get(0) else { /* TODO: Issue */ return; };
-    let Some(external_cmd) = args.get(1) else { /* TODO: Issue */ return; };
+    let Some(tag) = args.get(0) else { return; };
+    let Some(external_cmd) = args.get(1) else { return; };
+    if let Some(issue) = disallowed_command_issue(external_cmd, ctx) {
+        ctx.issues.push(issue);
+        return;
+    }
 
     rewrite_subtrees(scope, &mut |node| {
         let BlockChild::Verbatim(verbatim) = node else { return; };
@@ -70,24 +494,364 @@ where C: Container<'a>
     });
 
     rewrite_subtrees(scope, &mut |node| {
-        let Some(inline_content) = node.inline_content_mut() else { return; };
-        rewrite_inline_root(inline_content, &mut |inline_node| {
-            let AnyInline::Verbatim(verbatim) = inline_node else { return; };
-            req!(Some(verbatim_tag), 
-                remove_first(&mut verbatim.tags, |t| t.as_ref() == tag.as_ref()));            
-            let mut tmp: AnyInline<'a> = AnyInline::None;
-            std::mem::swap(&mut tmp, inline_node);
-            assert_matches!(tmp, AnyInline::Verbatim(verbatim));
-            let src: Vec<SourceSpan<'a>> = verbatim.content;
-            let external_args: Vec<SourceSpan<'a>> = Vec::from(&args[2..]);
-            let external_cmd = external_cmd.clone();
-            let rewriter: Box<dyn Writable<'a> + 'a> = Box::new(ExternalRewriter { 
-                src, external_cmd, external_args, verbatim_tag, cwd: ctx.cwd.clone() });
-            *inline_node = AnyInline::HTML(InlineHTML { value: rewriter });
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                let AnyInline::Verbatim(verbatim) = inline_node else { return; };
+                req!(Some(verbatim_tag), 
+                    remove_first(&mut verbatim.tags, |t| t.as_ref() == tag.as_ref()));            
+                let mut tmp: AnyInline<'a> = AnyInline::None;
+                std::mem::swap(&mut tmp, inline_node);
+                assert_matches!(tmp, AnyInline::Verbatim(verbatim));
+                let src: Vec<SourceSpan<'a>> = verbatim.content;
+                let external_args: Vec<SourceSpan<'a>> = Vec::from(&args[2..]);
+                let external_cmd = external_cmd.clone();
+                let rewriter: Box<dyn Writable<'a> + 'a> = Box::new(ExternalRewriter { 
+                    src, external_cmd, external_args, verbatim_tag, cwd: ctx.cwd.clone() });
+                *inline_node = AnyInline::HTML(InlineHTML { value: rewriter });
+            });
+        }
+    });
+}
+
+/// Returns an issue if the project configuration does not allow the external command `command`
+/// to be run by the `rewrite` and `embed` directives.
+fn disallowed_command_issue<'a, 'b>(command: &SourceSpan<'a>, ctx: &Context<'a, 'b>) 
+-> Option<Issue<'a>>
+{
+    let allowed_commands = ctx.allowed_commands.as_ref()?;
+    if allowed_commands.iter().any(|allowed| allowed == command.as_ref()) { return None; }
+    let mut quote = AnnotatedSourceSection::from_span(command);
+    quote.highlight(command.begin.byte_pos, command.end.byte_pos);
+    let allowed_list = match allowed_commands.len() {
+        0 => String::from("The project configuration allows no commands to be run."),
+        _ => format!("The project configuration allows only {} to be run.", allowed_commands.iter()
+            .map(|allowed| format!("`{}`", allowed)).collect::<Vec<_>>().join(", ")),
+    };
+    return Some(Issue {
+        quote,
+        title: format!("Command `{}` is not allowed", command.as_ref()).into(),
+        subtext: format!("{}\nThis directive will have no effect.", allowed_list).into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: Vec::new()
+    });
+}
+
+fn apply_class<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
+where C: Container<'a>
+{
+    let Some(tag) = args.get(0) else { return; };
+    let mut classes: Vec<SourceSpan<'a>> = Vec::new();
+    for class in &args[1..] {
+        if is_class_name(class.as_ref()) { classes.push(*class); continue; }
+        let mut quote = AnnotatedSourceSection::from_span(class);
+        quote.highlight(class.begin.byte_pos, class.end.byte_pos);
+        ctx.issues.push(Issue {
+            quote,
+            title: "Invalid CSS class name".into(),
+            subtext: "A class name must begin with a letter, hyphen or underscore and contain only \n\
+                      letters, digits, hyphens and underscores. This class will not be added.".into(),
+            severity: Severity::Error,
+            fix: None,
+            elaborations: Vec::new()
+        });
+    }
+    if classes.len() == 0 { return; }
+
+    rewrite_attributes(tag, scope, &mut |attrs| {
+        attrs.classes.extend_from_slice(&classes);
+    });
+}
+
+fn apply_element<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
+where C: Container<'a>
+{
+    let Some(tag) = args.get(0) else { return; };
+    let Some(element) = args.get(1) else { return; };
+
+    if !is_element_name(element.as_ref()) {
+        let mut quote = AnnotatedSourceSection::from_span(element);
+        quote.highlight(element.begin.byte_pos, element.end.byte_pos);
+        ctx.issues.push(Issue {
+            quote,
+            title: "Invalid HTML element name".into(),
+            subtext: "An element name must begin with a letter and contain only letters, digits \n\
+                      and hyphens. The nodes marked with this tag will keep their default element.".into(),
+            severity: Severity::Error,
+            fix: None,
+            elaborations: Vec::new()
         });
+        return;
+    }
+
+    rewrite_attributes(tag, scope, &mut |attrs| {
+        attrs.element = Some(*element);
+    });
+}
+
+fn apply_define<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, _ctx: &mut Context<'a, 'b>) 
+where C: Container<'a>
+{
+    let Some(name) = args.get(0) else { return; };
+    let Some(value) = join_args(&args[1..]) else { return; };
+    substitute_definition(name.as_ref(), value, scope);
+}
+
+/// Fills every empty span within `scope` which is marked with `name` with the text `value`.
+/// An empty tagged span is a reference to a defined value. Like `href`, the tag is consumed,
+/// so each reference is filled by the innermost definition only.
+pub fn substitute_definition<'a, C>(name: &str, value: SourceSpan<'a>, scope: &mut C) 
+where C: Container<'a>
+{
+    rewrite_subtrees(scope, &mut |node| {
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                let AnyInline::TaggedSpan(tagged_span) = inline_node else { return; };
+                if tagged_span.child_root.children.len() > 0 { return; }
+                if remove_first(&mut tagged_span.tags, |t| t.as_ref() == name).is_none() { return; }
+                tagged_span.child_root.children.push(AnyInline::Plain(PlainText { span: value }));
+            });
+        }
+    });
+}
+
+/// Reports every empty tagged span within `scope` which was never filled by a definition.
+/// Spans given a presentation by the `class` or `element` directives are exempt, since an 
+/// empty element can be meaningful on its own.
+pub fn report_undefined_references<'a, C>(scope: &mut C, issues: &mut Vec<Issue<'a>>) 
+where C: Container<'a>
+{
+    rewrite_subtrees(scope, &mut |node| {
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                let AnyInline::TaggedSpan(tagged_span) = inline_node else { return; };
+                if tagged_span.child_root.children.len() > 0 { return; }
+                if !tagged_span.attrs.is_empty() { return; }
+                let Some(tag) = tagged_span.tags.first() else { return; };
+                let mut quote = AnnotatedSourceSection::from_span(tag);
+                quote.highlight(tag.begin.byte_pos, tag.end.byte_pos);
+                issues.push(Issue {
+                    quote,
+                    title: format!("Value `{}` is never defined", tag.as_ref()).into(),
+                    subtext: "An empty span marked with a tag refers to the value of the same name. \
+                              The value is\ndefined by a `define` directive following the span \
+                              within the same block or section,\nor an enclosing one, or by the \
+                              `-D` command line option. The span will be empty.".into(),
+                    severity: Severity::Error,
+                    fix: None,
+                    elaborations: Vec::new()
+                });
+            });
+        }
+    });
+}
+
+/// Calls `rewrite_attrs` on the [`Attributes`] of every node within `scope` which is marked
+/// with `tag`. Unlike `href` and `rewrite`, the tag is not consumed, so multiple presentational
+/// directives can act on the same node.
+fn rewrite_attributes<'a, C>(tag: &SourceSpan<'a>, scope: &mut C, 
+    rewrite_attrs: &mut impl FnMut(&mut Attributes<'a>)) where C: Container<'a>
+{
+    let has_tag = |tags: &Vec<SourceSpan<'a>>| tags.iter().any(|t| t.as_ref() == tag.as_ref());
+    
+    rewrite_subtrees(scope, &mut |node| {
+        if let BlockChild::Verbatim(verbatim) = node {
+            if has_tag(&verbatim.tags) { (rewrite_attrs)(&mut verbatim.attrs); }
+            return;
+        }
+        if let BlockChild::TaggedBlock(tagged) = node {
+            if has_tag(&tagged.tags) { (rewrite_attrs)(&mut tagged.attrs); }
+            return;
+        }
+        if let BlockChild::Admonition(admonition) = node {
+            if has_tag(&admonition.tags) { (rewrite_attrs)(&mut admonition.attrs); }
+            return;
+        }
+        for inline_content in node.inline_content_mut() {
+            rewrite_inline_root(inline_content, &mut |inline_node| {
+                match inline_node {
+                    AnyInline::TaggedSpan(span) if has_tag(&span.tags) => 
+                        (rewrite_attrs)(&mut span.attrs),
+                    AnyInline::Verbatim(verbatim) if has_tag(&verbatim.tags) => 
+                        (rewrite_attrs)(&mut verbatim.attrs),
+                    _ => ()
+                }
+            });
+        }
     });
 }
 
+fn is_element_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else { return false; };
+    if !first.is_ascii_alphabetic() { return false; }
+    return chars.all(|c| c.is_ascii_alphanumeric() || c == '-');
+}
+
+fn is_class_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else { return false; };
+    if !(first.is_alphabetic() || first == '-' || first == '_') { return false; }
+    return chars.all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+}
+
+fn apply_title<'a, 'b>(args: Vec<SourceSpan<'a>>, ctx: &mut Context<'a, 'b>) {
+    let Some(title) = join_args(&args) else { return; };
+    if let Some(prev_title) = ctx.metadata.title {
+        ctx.issues.push(redeclared_metadata_issue(title, prev_title, 
+            "Document title is declared more than once"));
+        return;
+    }
+    ctx.metadata.title = Some(title);
+}
+
+fn apply_lang<'a, 'b>(args: Vec<SourceSpan<'a>>, ctx: &mut Context<'a, 'b>) {
+    let Some(lang) = args.get(0).cloned() else { return; };
+    if let Some(prev_lang) = ctx.metadata.lang {
+        ctx.issues.push(redeclared_metadata_issue(lang, prev_lang, 
+            "Document language is declared more than once"));
+        return;
+    }
+    ctx.metadata.lang = Some(lang);
+}
+
+fn apply_template<'a, 'b>(args: Vec<SourceSpan<'a>>, ctx: &mut Context<'a, 'b>) {
+    let Some(path) = args.get(0).cloned() else { return; };
+    if let Some(prev_path) = ctx.metadata.template {
+        ctx.issues.push(redeclared_metadata_issue(path, prev_path, 
+            "Document template is selected more than once"));
+        return;
+    }
+    ctx.metadata.template = Some(path);
+}
+
+fn apply_meta<'a, 'b>(args: Vec<SourceSpan<'a>>, ctx: &mut Context<'a, 'b>) {
+    let Some(name) = args.get(0).cloned() else { return; };
+    let Some(value) = join_args(&args[1..]) else { return; };
+    if let Some(prev_value) = ctx.metadata.get(name.as_ref()) {
+        ctx.issues.push(redeclared_metadata_issue(value, prev_value, 
+            "Document metadata is declared more than once"));
+        return;
+    }
+    ctx.metadata.entries.push(MetadataEntry { name, value });
+}
+
+fn redeclared_metadata_issue<'a>(value: SourceSpan<'a>, prev_value: SourceSpan<'a>, 
+    title: &'static str) -> Issue<'a> 
+{
+    let mut quote = AnnotatedSourceSection::from_span(&value);
+    quote.highlight(value.begin.byte_pos, value.end.byte_pos);
+    let mut prev_quote = AnnotatedSourceSection::from_span(&prev_value);
+    prev_quote.highlight(prev_value.begin.byte_pos, prev_value.end.byte_pos);
+    Issue {
+        quote,
+        title: title.into(),
+        subtext: "Only the first declaration takes effect. This declaration will be ignored.".into(),
+        severity: Severity::Warning,
+        fix: None,
+        elaborations: vec![
+            Elaboration::SourceQuote(SourceQuoteElaboration {
+                caption: "It was previously declared here...",
+                content: prev_quote
+            })
+        ]
+    }
+}
+
+/// Interprets the source file at the given path, relative to the directory of the including
+/// file, and appends its content to `scope`. The headings of the included file are re-levelled
+/// so that its top-level sections nest beneath the section it is included into.
+fn apply_include<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
+where C: Container<'a>
+{
+    let Some(path) = args.get(0).cloned() else { return; };
+    let read_result = ctx.cwd.join(path.as_ref()).canonicalize()
+        .and_then(|canonical_path| Ok((ctx.sources.read(&canonical_path)?, canonical_path)));
+    let ((file, source), canonical_path) = match read_result {
+        Ok(result) => result,
+        Err(err) => {
+            ctx.issues.push(unreadable_include_issue(path, err));
+            return;
+        }
+    };
+
+    let root_file = ctx.includes.first().map(|(_, invocation)| invocation.file).unwrap_or(path.file);
+    let root_path = ctx.sources.map().path(root_file).and_then(|root_path| root_path.canonicalize().ok());
+    let is_cycle = root_path.as_ref() == Some(&canonical_path) 
+        || ctx.includes.iter().any(|(included_path, _)| *included_path == canonical_path);
+    if is_cycle {
+        ctx.issues.push(include_cycle_issue(path, &ctx.includes));
+        return;
+    }
+
+    let ltree = make_ltree(source, file);
+    let mtree = make_mtree(&ltree);
+    for issue in verify_ltree(&ltree) { ctx.issues.push(issue.into()) }
+    for issue in verify_mtree(&mtree) { ctx.issues.push(issue.into()) }
+
+    let included_cwd = canonical_path.parent().unwrap().to_path_buf();
+    let parent_cwd = std::mem::replace(&mut ctx.cwd, included_cwd);
+    let parent_hlevel_offset = std::mem::replace(&mut ctx.hlevel_offset, ctx.hlevel);
+    ctx.includes.push((canonical_path, path));
+    let block = interpret_mtree_block(mtree.block, ctx);
+    ctx.includes.pop();
+    ctx.hlevel_offset = parent_hlevel_offset;
+    ctx.cwd = parent_cwd;
+
+    scope.children_mut().extend(block.children);
+}
+
+fn unreadable_include_issue<'a>(path: SourceSpan<'a>, err: std::io::Error) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&path);
+    quote.highlight(path.begin.byte_pos, path.end.byte_pos);
+    Issue {
+        quote,
+        title: "Included file could not be read".into(),
+        subtext: "The document will be compiled as if this file were never included.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: vec![
+            Elaboration::Quote(QuoteElaboration {
+                caption: "The following error occurred while reading the file...",
+                content: err.to_string()
+            })
+        ]
+    }
+}
+
+fn include_cycle_issue<'a>(path: SourceSpan<'a>, includes: &[(std::path::PathBuf, SourceSpan<'a>)]) 
+-> Issue<'a> 
+{
+    let mut quote = AnnotatedSourceSection::from_span(&path);
+    quote.highlight(path.begin.byte_pos, path.end.byte_pos);
+    let mut elaborations: Vec<Elaboration<'a>> = Vec::new();
+    for (_, invocation) in includes.iter().rev() {
+        let mut content = AnnotatedSourceSection::from_span(invocation);
+        content.highlight(invocation.begin.byte_pos, invocation.end.byte_pos);
+        elaborations.push(Elaboration::SourceQuote(SourceQuoteElaboration {
+            caption: "The file containing this invocation was included here...",
+            content
+        }));
+    }
+    Issue {
+        quote,
+        title: "File includes itself".into(),
+        subtext: "This file is already being included, so including it again would never end.\n\
+                  This invocation will be ignored.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations
+    }
+}
+
+/// Returns a span beginning at the first argument and ending at the last argument, so that
+/// unquoted text containing spaces can be given as a single directive argument. Returns `None`
+/// if there are no arguments.
+fn join_args<'a>(args: &[SourceSpan<'a>]) -> Option<SourceSpan<'a>> {
+    let first = args.first()?;
+    let last = args.last()?;
+    return Some(SourceSpan { source: first.source, file: first.file, begin: first.begin, end: last.end });
+}
 
 #[derive(Debug)]
 struct ExternalRewriter<'a> {
@@ -115,11 +879,12 @@ impl<'a> Writable<'a> for ExternalRewriter<'a> {
             quote.highlight(self.verbatim_tag.begin.byte_pos, self.verbatim_tag.end.byte_pos);
             issues.push(Issue { 
                 quote, 
-                title: "Failed to start external rewriter process", 
+                title: "Failed to start external rewriter process".into(), 
                 subtext: "The verbatim tagged here will not be represented in the finished document\n\
                           because the external command given in the rewrite directive invocation could\n\
-                          not be executed.", 
+                          not be executed.".into(), 
                 severity: Severity::Error, 
+                fix: None,
                 elaborations: vec![
                     Elaboration::SourceQuote(SourceQuoteElaboration { 
                         caption: "This directive invocation acted on the verbatim.",
@@ -140,11 +905,12 @@ impl<'a> Writable<'a> for ExternalRewriter<'a> {
                     quote.highlight(self.verbatim_tag.begin.byte_pos, self.verbatim_tag.end.byte_pos);
                     issues.push(Issue { 
                         quote,
-                        title: "External rewriter is not accepting input",
+                        title: "External rewriter is not accepting input".into(),
                         subtext: "The verbatim tagged here will not be represented in the finished document\n\
                                   because the external command given in the rewrite directive invocation is not\n\
-                                  reading from stdin.", 
+                                  reading from stdin.".into(), 
                         severity: Severity::Error,
+                        fix: None,
                         elaborations: vec![
                             Elaboration::SourceQuote(SourceQuoteElaboration { 
                                 caption: "The following directive invocation acted on this verbatim...",
@@ -166,11 +932,12 @@ impl<'a> Writable<'a> for ExternalRewriter<'a> {
                 quote.highlight(self.verbatim_tag.begin.byte_pos, self.verbatim_tag.end.byte_pos);
                 issues.push(Issue { 
                     quote,
-                    title: "Failed to pipe external process output into finished document.",
+                    title: "Failed to pipe external process output into finished document.".into(),
                     subtext: "The verbatim tagged here could not be rewritten because an error\n\
                               ocurred while piping the process' stdout into the finished document.\n\
-                              The document is now likely malformed, or at least incomplete.", 
+                              The document is now likely malformed, or at least incomplete.".into(), 
                     severity: Severity::Error,
+                    fix: None,
                     elaborations: vec![
                         Elaboration::SourceQuote(SourceQuoteElaboration { 
                             caption: "This directive invocation was applied to the verbatim.",
@@ -187,15 +954,16 @@ impl<'a> Writable<'a> for ExternalRewriter<'a> {
         
         if let Some(mut stderr) = process.stderr.take() {
             let mut error_text = String::new();
-            stderr.read_to_string(&mut error_text); // TODO
+            let _ = stderr.read_to_string(&mut error_text); // TODO
             if error_text.len() > 0 {
                 let mut quote = AnnotatedSourceSection::from_span(&self.verbatim_tag);
                 quote.highlight(self.verbatim_tag.begin.byte_pos, self.verbatim_tag.end.byte_pos);
                 issues.push(Issue { 
                     quote,
-                    title: "An external error ocurred while rewriting a verbatim.",
-                    subtext: "An external process logged an error while rewriting the verbatim tagged here.",
+                    title: "An external error ocurred while rewriting a verbatim.".into(),
+                    subtext: "An external process logged an error while rewriting the verbatim tagged here.".into(),
                     severity: Severity::Warning,
+                    fix: None,
                     elaborations: vec![
                         Elaboration::SourceQuote(SourceQuoteElaboration { 
                             caption: "The following directive invocation acted on this verbatim...",
@@ -215,11 +983,12 @@ impl<'a> Writable<'a> for ExternalRewriter<'a> {
             quote.highlight(self.verbatim_tag.begin.byte_pos, self.verbatim_tag.end.byte_pos);
             issues.push(Issue { 
                 quote,
-                title: "Cannot confirm completion of external process.",
+                title: "Cannot confirm completion of external process.".into(),
                 subtext: "The verbatim tagged here could not be rewritten because an error\n\
                           ocurred while piping the process' stdout into the finished document.\n\
-                          The document is now likely malformed, or at least incomplete.", 
+                          The document is now likely malformed, or at least incomplete.".into(), 
                 severity: Severity::Error,
+                fix: None,
                 elaborations: vec![
                     Elaboration::SourceQuote(SourceQuoteElaboration { 
                         caption: "The following directive invocation acted on this verbatim...",
@@ -238,7 +1007,11 @@ impl<'a> Writable<'a> for ExternalRewriter<'a> {
 fn apply_embed<'a, 'b, C>(args: Vec<SourceSpan<'a>>, scope: &mut C, ctx: &mut Context<'a, 'b>) 
 where C: Container<'a>
 {
-    let Some(external_cmd) = args.get(0).cloned() else { /* TODO: Issue */ return; };
+    let Some(external_cmd) = args.get(0).cloned() else { return; };
+    if let Some(issue) = disallowed_command_issue(&external_cmd, ctx) {
+        ctx.issues.push(issue);
+        return;
+    }
     let external_args = Vec::from(&args[1..]);
 
     let value: Box<dyn Writable<'a> + 'a> = Box::new(Synthesizer { 
@@ -268,10 +1041,11 @@ impl<'a> Writable<'a> for Synthesizer<'a> {
             let quote = AnnotatedSourceSection::from_span(&self.external_cmd);
             issues.push(Issue { 
                 quote, 
-                title: "Failed to start external process", 
+                title: "Failed to start external process".into(), 
                 subtext: "The content represented by this directive invocation will not be included\n\
-                          in the finished document becuase the external process could not be executed.", 
+                          in the finished document becuase the external process could not be executed.".into(), 
                 severity: Severity::Error, 
+                fix: None,
                 elaborations: vec![
                     Elaboration::Quote(QuoteElaboration {
                         caption: "The following error occurred after invoking the external command",
@@ -286,9 +1060,10 @@ impl<'a> Writable<'a> for Synthesizer<'a> {
                 let quote = AnnotatedSourceSection::from_span(&self.external_cmd);
                  issues.push(Issue { 
                     quote,
-                    title: "Failed to pipe external process output into finished document.",
-                    subtext: "The finished document will likely be malformed, or at least incomplete.", 
+                    title: "Failed to pipe external process output into finished document.".into(),
+                    subtext: "The finished document will likely be malformed, or at least incomplete.".into(), 
                     severity: Severity::Error,
+                    fix: None,
                     elaborations: vec![
                         Elaboration::Quote(QuoteElaboration {
                             caption: "The following error occurred while piping from the external process...",
@@ -301,16 +1076,17 @@ impl<'a> Writable<'a> for Synthesizer<'a> {
 
         if let Some(mut stderr) = process.stderr.take() {
             let mut error_text = String::new();
-            stderr.read_to_string(&mut error_text); // TODO
+            let _ = stderr.read_to_string(&mut error_text); // TODO
             if error_text.len() > 0 {
                 let quote = AnnotatedSourceSection::from_span(&self.external_cmd);
                 issues.push(Issue { 
                     quote,
-                    title: "An external error occurred during synthesis",
+                    title: "An external error occurred during synthesis".into(),
                     subtext: "The external process logged an error while synthesizing the content\n\
                               represented by this \"embed\". The finished document will likely by\n\
-                              incomplete or malformed.",
+                              incomplete or malformed.".into(),
                     severity: Severity::Warning,
+                    fix: None,
                     elaborations: vec![
                         Elaboration::Quote(QuoteElaboration {
                             caption: "The process logged the following error...",
@@ -325,10 +1101,11 @@ impl<'a> Writable<'a> for Synthesizer<'a> {
             let quote = AnnotatedSourceSection::from_span(&self.external_cmd);
             issues.push(Issue { 
                 quote,
-                title: "Cannot confirm successful completion of external process.",
+                title: "Cannot confirm successful completion of external process.".into(),
                 subtext: "This \"make\" invocation might not have been expanded correctly.\n\
-                          The finished document might be incomplete or malformed.", 
+                          The finished document might be incomplete or malformed.".into(), 
                 severity: Severity::Error,
+                fix: None,
                 elaborations: vec![
                     Elaboration::Quote(QuoteElaboration {
                         caption: "The following error occurred while waiting for the process...",
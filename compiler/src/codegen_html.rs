@@ -1,18 +1,30 @@
-use std::path::PathBuf;
-
 use crate::{ctree, report::Issue};
+use crate::highlight::{has_highlighted_code, highlight, THEME_CSS};
+use crate::site::{NavLink, Navigation};
 
-pub fn codegen<'a, W>(root: &ctree::Root<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>, head: &Option<PathBuf>)
+/// Writes the finished document `root` as a complete HTML page. This is the page structure used
+/// when no template is selected. See [`crate::template`] otherwise. The `head` is inserted into
+/// the `<head>` of the page verbatim, and so is the default highlighting theme if the document
+/// contains highlighted code.
+pub fn codegen<'a, W>(root: &ctree::Root<'a>, head: &str, out: &mut W, issues: &mut Vec<Issue<'a>>)
 -> std::io::Result<()>
 where W: std::io::Write
 {
     write!(out, "<!DOCTYPE html>")?;
-    write!(out, "<html>")?;
-    if let Some(head_path) = head {
-        let mut head_file = std::fs::File::open(head_path)?;
-        std::io::copy(&mut head_file, out)?;
+    match root.metadata.lang {
+        Some(lang) => {
+            write!(out, "<html lang=\"")?;
+            write_escaped(lang.as_ref(), out)?;
+            write!(out, "\">")?;
+        },
+        None => write!(out, "<html>")?,
     }
     write!(out, "<head>")?;
+    codegen_metadata(root, out)?;
+    if has_highlighted_code(&root.block.children) {
+        write!(out, "<style>{}</style>", THEME_CSS)?;
+    }
+    out.write_all(head.as_bytes())?;
     write!(out, "</head>")?;
     write!(out, "<body>")?;
     codegen_block(&root.block, out, issues)?;
@@ -21,6 +33,132 @@ where W: std::io::Write
     return Ok(());
 }
 
+pub fn codegen_metadata<'a, W>(root: &ctree::Root<'a>, out: &mut W) -> std::io::Result<()> 
+where W: std::io::Write
+{
+    write!(out, "<meta charset=\"utf-8\">")?;
+    if let Some(title) = root.title() {
+        write!(out, "<title>")?;
+        write_escaped(&title, out)?;
+        write!(out, "</title>")?;
+    }
+    for entry in &root.metadata.entries {
+        write!(out, "<meta name=\"")?;
+        write_escaped(entry.name.as_ref(), out)?;
+        write!(out, "\" content=\"")?;
+        write_escaped(entry.value.as_ref(), out)?;
+        write!(out, "\">")?;
+    }
+    return Ok(());
+}
+
+/// Writes `text` to `out`, replacing all characters which have special meaning in HTML 
+/// with character references. The result is suitable for use in both text and attribute values.
+pub fn write_escaped<W>(text: &str, out: &mut W) -> std::io::Result<()> where W: std::io::Write {
+    let mut unwritten_begin: usize = 0;
+    for (i, c) in text.char_indices() {
+        let reference = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' => "&quot;",
+            '\'' => "&#39;",
+            _ => continue
+        };
+        out.write_all(&text.as_bytes()[unwritten_begin..i])?;
+        out.write_all(reference.as_bytes())?;
+        unwritten_begin = i + 1;
+    }
+    out.write_all(&text.as_bytes()[unwritten_begin..])?;
+    return Ok(());
+}
+
+/// Writes a link to a neighbouring page, for instance `<a href="intro.html" rel="prev">Intro</a>`.
+/// If there is no such page, nothing is written.
+pub fn codegen_nav_link<W>(link: Option<&NavLink>, rel: &str, out: &mut W) -> std::io::Result<()> 
+where W: std::io::Write
+{
+    let Some(link) = link else { return Ok(()); };
+    write!(out, "<a href=\"")?;
+    write_escaped(&link.url, out)?;
+    write!(out, "\" rel=\"{}\">", rel)?;
+    write_escaped(&link.title, out)?;
+    write!(out, "</a>")?;
+    return Ok(());
+}
+
+/// Writes the trail of index pages leading to the document, followed by the title of the
+/// document itself. If the document is not a part of a site, nothing is written.
+pub fn codegen_breadcrumbs<W>(navigation: &Navigation, out: &mut W) -> std::io::Result<()> 
+where W: std::io::Write
+{
+    let Some((current, ancestors)) = navigation.breadcrumbs.split_last() else { return Ok(()); };
+    write!(out, "<nav class=\"breadcrumbs\"><ol>")?;
+    for ancestor in ancestors {
+        write!(out, "<li><a href=\"")?;
+        write_escaped(&ancestor.url, out)?;
+        write!(out, "\">")?;
+        write_escaped(&ancestor.title, out)?;
+        write!(out, "</a></li>")?;
+    }
+    write!(out, "<li aria-current=\"page\">")?;
+    write_escaped(&current.title, out)?;
+    write!(out, "</li></ol></nav>")?;
+    return Ok(());
+}
+
+/// Writes a table of contents for the document. The table is a nested list linking to 
+/// every section in the document. If the document has no sections, nothing is written. 
+pub fn codegen_toc<'a, W>(root: &ctree::Root<'a>, out: &mut W) -> std::io::Result<()> 
+where W: std::io::Write
+{
+    if !has_sections(&root.block.children) { return Ok(()); }
+    write!(out, "<nav class=\"toc\">")?;
+    codegen_toc_list(&root.block.children, out)?;
+    write!(out, "</nav>")?;
+    return Ok(());
+}
+
+fn codegen_toc_list<'a, W>(children: &[ctree::BlockChild<'a>], out: &mut W) -> std::io::Result<()> 
+where W: std::io::Write
+{
+    write!(out, "<ul>")?;
+    codegen_toc_entries(children, out)?;
+    write!(out, "</ul>")?;
+    return Ok(());
+}
+
+fn codegen_toc_entries<'a, W>(children: &[ctree::BlockChild<'a>], out: &mut W) 
+-> std::io::Result<()> where W: std::io::Write
+{
+    for child in children {
+        match child {
+            ctree::BlockChild::Section(section) => {
+                write!(out, "<li><a href=\"#")?;
+                write_escaped(&section.id, out)?;
+                write!(out, "\">")?;
+                write_escaped(section.heading.content.plain_text().trim(), out)?;
+                write!(out, "</a>")?;
+                if has_sections(&section.children) { codegen_toc_list(&section.children, out)?; }
+                write!(out, "</li>")?;
+            },
+            // Consecutive sections are sometimes wrapped in a block for disambiguation.
+            // See `ctree::mark_section_ambiguities`.
+            ctree::BlockChild::Block(block) => codegen_toc_entries(&block.children, out)?,
+            _ => ()
+        }
+    }
+    return Ok(());
+}
+
+fn has_sections<'a>(children: &[ctree::BlockChild<'a>]) -> bool {
+    children.iter().any(|child| match child {
+        ctree::BlockChild::Section(_) => true,
+        ctree::BlockChild::Block(block) => has_sections(&block.children),
+        _ => false
+    })
+}
+
 pub fn codegen_node<'a, W>(any_node: &ctree::BlockChild<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
 -> std::io::Result<()> where W: std::io::Write
 {
@@ -34,6 +172,9 @@ pub fn codegen_node<'a, W>(any_node: &ctree::BlockChild<'a>, out: &mut W, issues
         ctree::BlockChild::Heading      (node) => codegen_heading(node, out, issues),
         ctree::BlockChild::CodeSnippet  (node) => codegen_code_snippet(node, out),
         ctree::BlockChild::ThematicBreak(node) => codegen_thematic_break(node, out),
+        ctree::BlockChild::TaggedBlock  (node) => codegen_tagged_block(node, out, issues),
+        ctree::BlockChild::Admonition   (node) => codegen_admonition(node, out, issues),
+        ctree::BlockChild::DefinitionList(node) => codegen_definition_list(node, out, issues),
         ctree::BlockChild::None                => Ok(()),
     }
 }
@@ -44,34 +185,81 @@ pub fn codegen_thematic_break<'a, W>(_node: &ctree::ThematicBreak, out: &mut W)
     write!(out, "<hr/>")   
 }
 
+pub fn codegen_tagged_block<'a, W>(node: &ctree::TaggedBlock<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
+-> std::io::Result<()> where W: std::io::Write
+{
+    let element = codegen_open_tag(&node.attrs, "div", out)?;
+    codegen_block(&node.content, out, issues)?;
+    write!(out, "</{}>", element)?;
+    return Ok(());
+}
+
+pub fn codegen_admonition<'a, W>(node: &ctree::Admonition<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
+-> std::io::Result<()> where W: std::io::Write
+{
+    let element = node.attrs.element.as_ref().map(|e| e.as_ref()).unwrap_or("aside");
+    write!(out, "<{} class=\"admonition {}", element, node.kind.tag())?;
+    for class in &node.attrs.classes {
+        write!(out, " ")?;
+        write_escaped(class.as_ref(), out)?;
+    }
+    write!(out, "\">")?;
+    write!(out, "<p class=\"admonition-title\">{}</p>", node.kind.title())?;
+    codegen_block(&node.content, out, issues)?;
+    write!(out, "</{}>", element)?;
+    return Ok(());
+}
+
 pub fn codegen_code_snippet<'a, W>(snippet: &ctree::CodeSnippet<'a>, out: &mut W)
 -> std::io::Result<()> where W: std::io::Write 
 {
-    write!(out, "<pre>")?;
+    let Some(language) = snippet.language else {
+        write!(out, "<pre>")?;
+        for line in &snippet.lines {
+            out.write_all(line.as_ref().as_bytes())?;
+            write!(out, "\n")?;
+        }
+        write!(out, "</pre>")?;
+        return Ok(())
+    };
+    let mut code = String::new();
     for line in &snippet.lines {
-        out.write_all(line.as_ref().as_bytes())?;
-        write!(out, "\n")?;
+        code.push_str(line.as_ref());
+        code.push('\n');
+    }
+    write!(out, "<pre class=\"highlight lang-{}\"><code>", language.name())?;
+    for token in highlight(language, &code) {
+        match token.kind {
+            Some(kind) => {
+                write!(out, "<span class=\"{}\">", kind.class())?;
+                write_escaped(token.text, out)?;
+                write!(out, "</span>")?;
+            },
+            None => write_escaped(token.text, out)?,
+        }
     }
-    write!(out, "</pre>")?;
+    write!(out, "</code></pre>")?;
     return Ok(())
 }
 
 pub fn codegen_verbatim_block<W>(verbatim: &ctree::VerbatimBlock, out: &mut W) 
 -> std::io::Result<()> where W: std::io::Write 
 {
-    write!(out, "<div>")?;
+    let element = codegen_open_tag(&verbatim.attrs, "div", out)?;
     for line in &verbatim.lines {
         out.write_all(line.as_ref().as_bytes())?;
         write!(out, "\n")?;
     }
-    write!(out, "</div>")?;
+    write!(out, "</{}>", element)?;
     return Ok(());
 }
 
 pub fn codegen_section<'a, W>(section: &ctree::Section<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>) 
 -> std::io::Result<()> where W: std::io::Write
 {
-    write!(out, "<section>")?;
+    write!(out, "<section id=\"")?;
+    write_escaped(&section.id, out)?;
+    write!(out, "\">")?;
     write!(out, "<h1>")?;
     codegen_inline_root(&section.heading.content, out, issues)?;
     write!(out, "</h1>")?;
@@ -85,7 +273,14 @@ pub fn codegen_list<'a, W>(list: &ctree::List<'a>, out: &mut W, issues: &mut Vec
 {
     write!(out, "<ul>")?;
     for element in &list.elements {
-        write!(out, "<li>")?;
+        match element.task {
+            Some(task) => {
+                write!(out, "<li class=\"task\">")?;
+                let checked = if task.checked { " checked" } else { "" };
+                write!(out, "<input type=\"checkbox\" disabled{}/>", checked)?;
+            },
+            None => write!(out, "<li>")?,
+        }
         write!(out, "<div>")?;
         codegen_block(&element.content, out, issues)?;
         write!(out, "</div>")?;
@@ -95,6 +290,22 @@ pub fn codegen_list<'a, W>(list: &ctree::List<'a>, out: &mut W, issues: &mut Vec
     return Ok(())
 }
 
+pub fn codegen_definition_list<'a, W>(list: &ctree::DefinitionList<'a>, out: &mut W, 
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    write!(out, "<dl>")?;
+    for definition in &list.definitions {
+        write!(out, "<dt>")?;
+        codegen_inline_root(&definition.term, out, issues)?;
+        write!(out, "</dt>")?;
+        write!(out, "<dd>")?;
+        codegen_block(&definition.content, out, issues)?;
+        write!(out, "</dd>")?;
+    }
+    write!(out, "</dl>")?;
+    return Ok(())
+}
+
 pub fn codegen_block<'a, W>(block: &ctree::Block<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>) 
 -> std::io::Result<()> where W: std::io::Write
 {
@@ -146,6 +357,7 @@ pub fn codegen_inline_node<'a, W>(node: &ctree::AnyInline<'a>, out: &mut W, issu
     match node {
         ctree::AnyInline::Plain            (node) => codegen_plain_text(node, out),
         ctree::AnyInline::Hyperlink        (node) => codegen_hyperlink(node, out, issues),
+        ctree::AnyInline::CrossReference   (node) => codegen_cross_reference(node, out, issues),
         ctree::AnyInline::Emboldened       (node) => codegen_bold_text(node, out, issues),
         ctree::AnyInline::Italicized       (node) => codegen_italicized_text(node, out, issues),
         ctree::AnyInline::Underlined       (node) => codegen_underlined_text(node, out, issues),
@@ -206,20 +418,40 @@ pub fn codegen_underlined_text<'a, W>(node: &ctree::UnderlinedText<'a>, out: &mu
 pub fn codegen_inline_verbatim<W>(node: &ctree::InlineVerbatim, out: &mut W)
 -> std::io::Result<()> where W: std::io::Write
 {
-    write!(out, "<span>")?;
+    let element = codegen_open_tag(&node.attrs, "span", out)?;
     for span in &node.content {
         out.write_all(span.as_ref().as_bytes())?;
         write!(out, " ")?;
     }
-    write!(out, "</span>")?;
+    write!(out, "</{}>", element)?;
     return Ok(())
 }
 
 pub fn codegen_hyperlink<'a, W>(node: &ctree::HyperlinkText<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
 -> std::io::Result<()> where W: std::io::Write
 {
-    write!(out, "<a href={}>", node.href.as_ref())?;
+    write!(out, "<a href=\"")?;
+    write_escaped(&node.url("html"), out)?;
+    write!(out, "\"")?;
+    if let Some(title) = &node.title {
+        write!(out, " title=\"")?;
+        write_escaped(title.as_ref(), out)?;
+        write!(out, "\"")?;
+    }
+    write!(out, ">")?;
+    codegen_inline_root(&node.child_root, out, issues)?;
+    write!(out, "</a>")?;
+    return Ok(());
+}
+
+pub fn codegen_cross_reference<'a, W>(node: &ctree::CrossReference<'a>, out: &mut W, 
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    write!(out, "<a href=\"")?;
+    write_escaped(&node.href("html"), out)?;
+    write!(out, "\">")?;
     codegen_inline_root(&node.child_root, out, issues)?;
+    if let Some(text) = &node.text { write_escaped(text, out)?; }
     write!(out, "</a>")?;
     return Ok(());
 }
@@ -242,5 +474,30 @@ pub fn codegen_implicit_space<W>(_node: &ctree::ImplicitSpace, out: &mut W)
 pub fn codegen_tagged_text<'a, W>(node: &ctree::TaggedSpan<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
 -> std::io::Result<()> where W: std::io::Write
 {
-    codegen_inline_root(&node.child_root, out, issues)
+    // A tagged span which was not acted upon by any presentational directive is transparent.
+    if node.attrs.is_empty() { return codegen_inline_root(&node.child_root, out, issues); }
+    let element = codegen_open_tag(&node.attrs, "span", out)?;
+    codegen_inline_root(&node.child_root, out, issues)?;
+    write!(out, "</{}>", element)?;
+    return Ok(());
+}
+
+/// Writes the opening tag for a node with the given [`ctree::Attributes`] and returns the
+/// name of the element which was opened, so that the caller can close it. 
+/// `default_element` is used when no `element` directive acted upon the node.
+pub fn codegen_open_tag<'a, 'b, W>(attrs: &'b ctree::Attributes<'a>, default_element: &'b str, 
+    out: &mut W) -> std::io::Result<&'b str> where W: std::io::Write
+{
+    let element: &'b str = attrs.element.as_ref().map(|e| e.as_ref()).unwrap_or(default_element);
+    write!(out, "<{}", element)?;
+    if attrs.classes.len() > 0 {
+        write!(out, " class=\"")?;
+        for (i, class) in attrs.classes.iter().enumerate() {
+            if i > 0 { write!(out, " ")?; }
+            write_escaped(class.as_ref(), out)?;
+        }
+        write!(out, "\"")?;
+    }
+    write!(out, ">")?;
+    return Ok(element);
 }
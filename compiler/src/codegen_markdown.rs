@@ -0,0 +1,329 @@
+//! This module renders the *CTree* as CommonMark, using the GitHub Flavored Markdown extensions
+//! for task lists and alerts. It is an alternative to [`crate::codegen_html`] for publishing
+//! documents to platforms which accept only Markdown.
+//!
+//! Not every construct has a Markdown equivalent. Underlined text and definition lists are
+//! written as raw HTML, which CommonMark passes through verbatim. The indentation used to
+//! disambiguate nested sections has no equivalent and is dropped.
+
+use std::borrow::Cow;
+use crate::{ctree, report::Issue};
+use crate::codegen_html::codegen_definition_list;
+use crate::misc::longest_run_of;
+
+pub fn codegen<'a, W>(root: &ctree::Root<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
+-> std::io::Result<()> where W: std::io::Write
+{
+    codegen_children(&root.block.children, 0, out, issues)
+}
+
+/// Writes each of the given block nodes, separating them with blank lines.
+/// The `depth` is the number of sections enclosing these nodes.
+pub fn codegen_children<'a, W>(children: &[ctree::BlockChild<'a>], depth: usize, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    let mut is_first = true;
+    for child in children {
+        let mut buffer: Vec<u8> = Vec::new();
+        codegen_node(child, depth, &mut buffer, issues)?;
+        if buffer.len() == 0 { continue; }
+        if !is_first { write!(out, "\n")?; }
+        out.write_all(&buffer)?;
+        if !buffer.ends_with(b"\n") { write!(out, "\n")?; }
+        is_first = false;
+    }
+    return Ok(());
+}
+
+pub fn codegen_node<'a, W>(any_node: &ctree::BlockChild<'a>, depth: usize, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    match any_node {
+        ctree::BlockChild::Verbatim      (node) => codegen_verbatim_block(node, out),
+        ctree::BlockChild::Section       (node) => codegen_section(node, depth, out, issues),
+        ctree::BlockChild::List          (node) => codegen_list(node, depth, out, issues),
+        ctree::BlockChild::Block         (node) => codegen_children(&node.children, depth, out, issues),
+        ctree::BlockChild::Paragraph     (node) => codegen_paragraph(node, out, issues),
+        ctree::BlockChild::HTML          (node) => codegen_html_block(node, out, issues),
+        ctree::BlockChild::Heading       (node) => codegen_heading(node, out, issues),
+        ctree::BlockChild::CodeSnippet   (node) => codegen_code_snippet(node, out),
+        ctree::BlockChild::ThematicBreak (_)    => write!(out, "---\n"),
+        ctree::BlockChild::TaggedBlock   (node) => codegen_children(&node.content.children, depth, out, issues),
+        ctree::BlockChild::Admonition    (node) => codegen_admonition(node, depth, out, issues),
+        ctree::BlockChild::DefinitionList(node) => codegen_definition_list(node, out, issues),
+        ctree::BlockChild::None                 => Ok(()),
+    }
+}
+
+pub fn codegen_section<'a, W>(section: &ctree::Section<'a>, depth: usize, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    let hlevel = std::cmp::min(depth + 1, 6);
+    for _ in 0..hlevel { write!(out, "#")?; }
+    write!(out, " ")?;
+    codegen_inline_root(&section.heading.content, out, issues)?;
+    write!(out, "\n")?;
+    if section.children.len() > 0 {
+        write!(out, "\n")?;
+        codegen_children(&section.children, depth + 1, out, issues)?;
+    }
+    return Ok(());
+}
+
+pub fn codegen_heading<'a, W>(heading: &ctree::Heading<'a>, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    for _ in 0..heading.hlevel.clamp(1, 6) { write!(out, "#")?; }
+    write!(out, " ")?;
+    codegen_inline_root(&heading.content, out, issues)?;
+    write!(out, "\n")?;
+    return Ok(());
+}
+
+pub fn codegen_list<'a, W>(list: &ctree::List<'a>, depth: usize, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    for element in &list.elements {
+        let mut marker = String::from("- ");
+        if let Some(task) = &element.task {
+            marker.push_str(if task.checked { "[x] " } else { "[ ] " });
+        }
+        let mut content: Vec<u8> = Vec::new();
+        codegen_children(&element.content.children, depth, &mut content, issues)?;
+        write_prefixed(&content, &marker, "  ", out)?;
+    }
+    return Ok(());
+}
+
+pub fn codegen_admonition<'a, W>(node: &ctree::Admonition<'a>, depth: usize, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    let alert = match node.kind {
+        ctree::AdmonitionKind::Note => "NOTE",
+        ctree::AdmonitionKind::Warning => "WARNING",
+        ctree::AdmonitionKind::Tip => "TIP",
+        ctree::AdmonitionKind::Danger => "CAUTION",
+    };
+    write!(out, "> [!{}]\n", alert)?;
+    let mut content: Vec<u8> = Vec::new();
+    codegen_children(&node.content.children, depth, &mut content, issues)?;
+    write_prefixed(&content, "> ", "> ", out)?;
+    return Ok(());
+}
+
+pub fn codegen_paragraph<'a, W>(paragraph: &ctree::Paragraph<'a>, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    let mut buffer: Vec<u8> = Vec::new();
+    codegen_inline_root(&paragraph.content, &mut buffer, issues)?;
+    let text = String::from_utf8_lossy(&buffer);
+    let text = text.trim();
+    if text.len() == 0 { return Ok(()); }
+    out.write_all(escape_block_start(text).as_bytes())?;
+    write!(out, "\n")?;
+    return Ok(());
+}
+
+pub fn codegen_verbatim_block<W>(verbatim: &ctree::VerbatimBlock, out: &mut W)
+-> std::io::Result<()> where W: std::io::Write
+{
+    for (i, line) in verbatim.lines.iter().enumerate() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_escaped_markdown(line.as_ref(), &mut buffer)?;
+        let text = String::from_utf8_lossy(&buffer);
+        out.write_all(escape_block_start(text.trim_start()).as_bytes())?;
+        // A trailing backslash is a hard line break in CommonMark.
+        if i + 1 < verbatim.lines.len() { write!(out, "\\")?; }
+        write!(out, "\n")?;
+    }
+    return Ok(());
+}
+
+pub fn codegen_code_snippet<'a, W>(snippet: &ctree::CodeSnippet<'a>, out: &mut W)
+-> std::io::Result<()> where W: std::io::Write
+{
+    let longest_run = snippet.lines.iter().map(|line| longest_run_of(line.as_ref(), '`')).max();
+    let fence = "`".repeat(std::cmp::max(3, longest_run.unwrap_or(0) + 1));
+    let info = snippet.language.map(|language| language.name()).unwrap_or("");
+    write!(out, "{}{}\n", fence, info)?;
+    for line in &snippet.lines {
+        out.write_all(line.as_ref().as_bytes())?;
+        write!(out, "\n")?;
+    }
+    write!(out, "{}\n", fence)?;
+    return Ok(());
+}
+
+pub fn codegen_html_block<'a, W>(node: &ctree::HTML<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
+-> std::io::Result<()> where W: std::io::Write
+{
+    node.value.write(out, issues);
+    return Ok(());
+}
+
+pub fn codegen_inline_root<'a, W>(node: &ctree::InlineRoot<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
+-> std::io::Result<()> where W: std::io::Write
+{
+    for child in &node.children {
+        codegen_inline_node(child, out, issues)?;
+    }
+    return Ok(());
+}
+
+pub fn codegen_inline_node<'a, W>(node: &ctree::AnyInline<'a>, out: &mut W, issues: &mut Vec<Issue<'a>>)
+-> std::io::Result<()> where W: std::io::Write
+{
+    match node {
+        ctree::AnyInline::Plain            (node) => write_escaped_markdown(node.span.as_ref(), out),
+        ctree::AnyInline::Hyperlink        (node) => codegen_hyperlink(node, out, issues),
+        ctree::AnyInline::CrossReference   (node) => codegen_cross_reference(node, out, issues),
+        ctree::AnyInline::Emboldened       (node) => codegen_delimited(&node.child_root, "**", out, issues),
+        ctree::AnyInline::Italicized       (node) => codegen_delimited(&node.child_root, "*", out, issues),
+        ctree::AnyInline::Underlined       (node) => codegen_underlined_text(node, out, issues),
+        ctree::AnyInline::TaggedSpan       (node) => codegen_inline_root(&node.child_root, out, issues),
+        ctree::AnyInline::ImplicitSpace    (_)    => write!(out, " "),
+        ctree::AnyInline::Verbatim         (node) => codegen_inline_verbatim(node, out),
+        ctree::AnyInline::InlineCodeSnippet(node) => codegen_inline_code_snippet(node, out),
+        ctree::AnyInline::HTML             (node) => codegen_inline_html(node, out, issues),
+        ctree::AnyInline::None                    => Ok(()),
+    }
+}
+
+pub fn codegen_inline_html<'a, W>(inline_html: &ctree::InlineHTML<'a>, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    inline_html.value.write(out, issues);
+    return Ok(());
+}
+
+pub fn codegen_delimited<'a, W>(child_root: &ctree::InlineRoot<'a>, delim: &str, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    write!(out, "{}", delim)?;
+    codegen_inline_root(child_root, out, issues)?;
+    write!(out, "{}", delim)?;
+    return Ok(());
+}
+
+pub fn codegen_underlined_text<'a, W>(node: &ctree::UnderlinedText<'a>, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    // Markdown has no syntax for underlined text.
+    write!(out, "<u>")?;
+    codegen_inline_root(&node.child_root, out, issues)?;
+    write!(out, "</u>")?;
+    return Ok(());
+}
+
+pub fn codegen_hyperlink<'a, W>(node: &ctree::HyperlinkText<'a>, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    write!(out, "[")?;
+    codegen_inline_root(&node.child_root, out, issues)?;
+    write!(out, "](")?;
+    let href = node.url("md");
+    if href.contains([' ', '(', ')']) {
+        write!(out, "<{}>", href)?;
+    } else {
+        write!(out, "{}", href)?;
+    }
+    if let Some(title) = &node.title {
+        write!(out, " \"{}\"", title.as_ref().replace('"', "\\\""))?;
+    }
+    write!(out, ")")?;
+    return Ok(());
+}
+
+pub fn codegen_cross_reference<'a, W>(node: &ctree::CrossReference<'a>, out: &mut W,
+    issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+{
+    write!(out, "[")?;
+    codegen_inline_root(&node.child_root, out, issues)?;
+    if let Some(text) = &node.text { write_escaped_markdown(text, out)?; }
+    let href = node.href("md");
+    if href.contains([' ', '(', ')']) {
+        write!(out, "](<{}>)", href)?;
+    } else {
+        write!(out, "]({})", href)?;
+    }
+    return Ok(());
+}
+
+pub fn codegen_inline_verbatim<W>(node: &ctree::InlineVerbatim, out: &mut W)
+-> std::io::Result<()> where W: std::io::Write
+{
+    for (i, span) in node.content.iter().enumerate() {
+        if i > 0 { write!(out, " ")?; }
+        write_escaped_markdown(span.as_ref(), out)?;
+    }
+    return Ok(());
+}
+
+pub fn codegen_inline_code_snippet<W>(node: &ctree::InlineCodeSnippet, out: &mut W)
+-> std::io::Result<()> where W: std::io::Write
+{
+    let code: Vec<&str> = node.inner_spans.iter().map(|span| span.as_ref()).collect();
+    let code = code.join("");
+    let fence = "`".repeat(longest_run_of(&code, '`') + 1);
+    // A code span which begins or ends with a backtick must be padded with spaces.
+    let pad = if code.starts_with('`') || code.ends_with('`') { " " } else { "" };
+    write!(out, "{}{}{}{}{}", fence, pad, code, pad, fence)?;
+    return Ok(());
+}
+
+/// Writes `text` to `out`, escaping every character which could otherwise be interpreted as
+/// inline Markdown syntax. The result is also safe from interpretation as inline HTML.
+pub fn write_escaped_markdown<W>(text: &str, out: &mut W) -> std::io::Result<()>
+where W: std::io::Write
+{
+    for (i, c) in text.char_indices() {
+        let is_special = match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' => true,
+            '&' => is_character_reference(&text[i + 1..]),
+            _ => false
+        };
+        if is_special { write!(out, "\\")?; }
+        write!(out, "{}", c)?;
+    }
+    return Ok(());
+}
+
+/// Returns `text` with a backslash inserted if it begins with a character which, at the
+/// beginning of a line, would otherwise begin a list item, heading, block quote, or thematic break.
+fn escape_block_start(text: &str) -> Cow<'_, str> {
+    if text.starts_with(['-', '+', '=', '#', '>']) {
+        return Cow::Owned(format!("\\{}", text));
+    }
+    // An ordered list item, for instance `1. `, is escaped by escaping the period.
+    let digit_count = text.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digit_count > 0 && text[digit_count..].starts_with(['.', ')']) {
+        return Cow::Owned(format!("{}\\{}", &text[..digit_count], &text[digit_count..]));
+    }
+    return Cow::Borrowed(text);
+}
+
+/// Writes `content` to `out`, prefixing its first line with `first_prefix` and every
+/// subsequent non-blank line with `rest_prefix`.
+fn write_prefixed<W>(content: &[u8], first_prefix: &str, rest_prefix: &str, out: &mut W)
+-> std::io::Result<()> where W: std::io::Write
+{
+    let content = String::from_utf8_lossy(content);
+    let content = content.trim_end_matches('\n');
+    if content.len() == 0 { return write!(out, "{}\n", first_prefix.trim_end()); }
+    for (i, line) in content.split('\n').enumerate() {
+        let prefix = if i == 0 { first_prefix } else { rest_prefix };
+        if line.len() == 0 {
+            write!(out, "{}\n", prefix.trim_end())?;
+            continue;
+        }
+        write!(out, "{}{}\n", prefix, line)?;
+    }
+    return Ok(());
+}
+
+/// Determines if the text following an ampersand would form a character reference,
+/// for instance `&amp;` or `&#38;`.
+fn is_character_reference(text: &str) -> bool {
+    let name_len = text.chars().take_while(|c| c.is_ascii_alphanumeric() || *c == '#').count();
+    return name_len > 0 && text[name_len..].starts_with(';');
+}
@@ -1,15 +1,191 @@
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::ctree::make_ctree;
-use crate::report::{Issue, print_issue};
+use crate::assets::{copy_asset, remove_stale_outputs, write_manifest, AssetFilter};
+use crate::config::{apply_lint_level, load_config, Config, LintLevel, CONFIG_FILE_NAME};
+use crate::ctree::{self, make_ctree, Environment};
+use crate::feed::{Feed, FeedOptions};
+use crate::highlight::{has_highlighted_code, THEME_CSS, THEME_FILE_NAME};
+use crate::linkcheck::LinkChecker;
+use crate::report::{Issue, AnnotatedSourceSection, Severity, Diagnostic, print_issue, 
+    print_file_error, apply_safe_fixes};
 use crate::mtree::{make_mtree, verify_mtree};
 use crate::ltree::{make_ltree, verify_ltree};
-use crate::misc::ansi;
-use crate::{codegen_html, assert_matches};
+use crate::misc::{ansi, today};
+use crate::search::SearchIndex;
+use crate::site::{Navigation, Site};
+use crate::source_map::{FileId, SourceCache, SourceMap};
+use crate::template::{PageContext, Template};
+use crate::{codegen_html, codegen_markdown, assert_matches};
 
-pub fn compile_dir(src_dir_path: PathBuf, dest_dir_path: PathBuf, head: &Option<PathBuf>) -> std::io::Result<()> {
+/// The name of the file which, when present in a source directory, is used as the template
+/// for every document within that directory and its subdirectories.
+pub const DIR_TEMPLATE_FILE_NAME: &str = "_template.html";
+
+/// The language of the finished documents.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    Html,
+    /// CommonMark with the GitHub Flavored Markdown extensions. Templates are not applied
+    /// to documents of this format.
+    Markdown
+}
+
+impl OutputFormat {
+    /// The file extension given to finished documents of this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Html => "html",
+            OutputFormat::Markdown => "md",
+        }
+    }
+}
+
+/// The maximum number of times the safe fixes are reapplied to a source file. Applying one fix
+/// can reveal another, and fixes which overlap are deferred to the next pass.
+const MAX_FIX_PASSES: usize = 8;
+
+/// Compiles every md2 source file within `src_dir_path` (recursively) into `dest_dir_path`.
+/// The `cwd`, `root_path` and `navigation` of the given `options` are replaced for each document,
+/// and its `template` is overridden by any directory template. Once every document has been
+/// compiled, the local links within them are checked and those which lead nowhere are printed.
+///
+/// Besides the finished documents, the output directory receives...
+///
+/// - every other file selected by the `assets` of the `options`, at the same relative path.
+/// - a generated index page for every directory which contains documents but no `index.md2`.
+/// - the search index and its script, if enabled by the `options`. See [`crate::search`].
+/// - the sitemap and the feed, if the `options` give a base URL and the output format is HTML.
+///   See [`crate::feed`].
+/// - the default highlighting theme, if any document contains highlighted code and the source
+///   directory does not provide its own theme.
+/// - the manifest of the build, listing all of the above. See [`crate::assets`].
+pub fn compile_dir<'s>(src_dir_path: PathBuf, dest_dir_path: PathBuf, options: &Options, fix: bool,
+    sources: &SourceCache<'s>) -> std::io::Result<()> 
+{
     std::fs::create_dir_all(&dest_dir_path)?;
+    let dest_root = dest_dir_path.canonicalize()?;
+    let site = Site::index(&src_dir_path, &dest_root, options.format.extension(), sources)?;
+    let mut build = DirBuild {
+        src_root: src_dir_path.clone(),
+        dest_root,
+        links: LinkChecker::new(options.format.extension()),
+        site,
+        search: options.search_index.then(|| SearchIndex::new(options.search_html)),
+        feed: options.base_url.as_deref()
+            .filter(|_| options.format == OutputFormat::Html)
+            .map(Feed::new),
+        outputs: HashSet::new(),
+        copied_count: 0,
+        generated_count: 0,
+        has_highlighted_code: false
+    };
+    compile_dir_at_depth(src_dir_path.clone(), dest_dir_path.clone(), options, fix, sources, 
+        &mut build, 0)?;
+    println!("{}##{} generated {}{}{} index pages.", ansi::BOLD, ansi::STOP_BOLD,
+        ansi::FG_GREY, build.generated_count, ansi::FG_DEFAULT);
+    println!("{}##{} copied {}{}{} static files.", ansi::BOLD, ansi::STOP_BOLD,
+        ansi::FG_GREY, build.copied_count, ansi::FG_DEFAULT);
+    let theme_path = dest_dir_path.join(THEME_FILE_NAME);
+    if build.has_highlighted_code && options.format == OutputFormat::Html 
+        && !build.outputs.contains(&theme_path) 
+    {
+        std::fs::write(&theme_path, THEME_CSS)?;
+        build.outputs.insert(theme_path);
+    }
+    if let Some(search) = &build.search {
+        build.outputs.extend(search.write(&dest_dir_path)?);
+        println!("{}##{} indexed {}{}{} sections for search.", ansi::BOLD, ansi::STOP_BOLD,
+            ansi::FG_GREY, search.entry_count(), ansi::FG_DEFAULT);
+    }
+    if let Some(feed) = &mut build.feed {
+        build.outputs.extend(feed.write(&dest_dir_path, build.site.pages(), build.site.title(),
+            &options.feed)?);
+        match feed.entry_count() {
+            0 => println!("{}##{} wrote the sitemap.", ansi::BOLD, ansi::STOP_BOLD),
+            count => println!("{}##{} wrote the sitemap, and a feed of {}{}{} entries.", ansi::BOLD,
+                ansi::STOP_BOLD, ansi::FG_GREY, count, ansi::FG_DEFAULT)
+        }
+        for (i, issue) in feed.issues().iter().enumerate() {
+            print!("{}. ", i + 1);
+            print_issue(issue, sources.map());
+        }
+    }
+
+    if options.clean {
+        // Were the source directory within the output directory, it would be emptied.
+        if src_dir_path.canonicalize()?.starts_with(&build.dest_root) {
+            println!("{}##{} the output directory contains the source directory, so it was not \
+                cleaned.", ansi::BOLD, ansi::STOP_BOLD);
+        } else {
+            let removed = remove_stale_outputs(&dest_dir_path, &build.outputs)?;
+            println!("{}##{} removed {}{}{} stale files.", ansi::BOLD, ansi::STOP_BOLD,
+                ansi::FG_GREY, removed.len(), ansi::FG_DEFAULT);
+            for path in removed { println!("{}{}{}", ansi::FG_GREY, path.display(), ansi::FG_DEFAULT); }
+        }
+    }
+    write_manifest(&dest_dir_path, &build.outputs)?;
+    println!();
+
+    let mut issues = build.links.check();
+    println!("{}#{} merryc is checking {}{}{} local links...", ansi::BOLD, ansi::STOP_BOLD,
+        ansi::FG_GREY, build.links.link_count(), ansi::FG_DEFAULT);
+    println!("{}##{} link check finished with {}{}{} issues.", ansi::BOLD, ansi::STOP_BOLD,
+        ansi::FG_GREY, issues.len(), ansi::FG_DEFAULT);
+    println!();
+
+    issues.sort_by_key(|issue| (issue.quote.file, issue.quote.first_line_no));
+    for (i, issue) in issues.iter().enumerate() { 
+        print!("{}. ", i + 1);
+        print_issue(issue, sources.map()); 
+    }
+    return Ok(());
+}
+
+/// The state shared by every directory compiled by [`compile_dir`].
+struct DirBuild<'s> {
+    src_root: PathBuf,
+    /// The canonical path of the output directory. If it lies within the source directory,
+    /// then it is not compiled itself.
+    dest_root: PathBuf,
+    links: LinkChecker<'s>,
+    site: Site,
+    search: Option<SearchIndex>,
+    feed: Option<Feed<'s>>,
+    /// Every file within the output directory which belongs to the build, whether it was
+    /// written by the build or was found to be up to date.
+    outputs: HashSet<PathBuf>,
+    copied_count: usize,
+    generated_count: usize,
+    has_highlighted_code: bool
+}
+
+fn compile_dir_at_depth<'s>(src_dir_path: PathBuf, dest_dir_path: PathBuf, options: &Options,
+    fix: bool, sources: &SourceCache<'s>, build: &mut DirBuild<'s>, depth: usize) 
+-> std::io::Result<()> 
+{
+    let dir_template_path = src_dir_path.join(DIR_TEMPLATE_FILE_NAME);
+    let dir_template = match dir_template_path.is_file() {
+        true => load_template(&dir_template_path, sources.map()),
+        false => None
+    };
+    // The configuration of the root directory was found before the build began.
+    let dir_config_path = src_dir_path.join(CONFIG_FILE_NAME);
+    let dir_config = match depth > 0 && dir_config_path.is_file() {
+        true => load_config(&dir_config_path, sources.map()),
+        false => None
+    };
+    let config_template = dir_config.as_ref()
+        .and_then(|config| config.template.as_ref())
+        .and_then(|template_path| load_template(template_path, sources.map()));
+    let mut options = Options { 
+        template: dir_template.as_ref().or(config_template.as_ref()).or(options.template),
+        root_path: relative_root_path(depth),
+        ..options.clone()
+    };
+    if let Some(config) = &dir_config { options.configure(config); }
+    let relative_dir_path = src_dir_path.strip_prefix(&build.src_root).unwrap().to_path_buf();
 
     let contents = std::fs::read_dir(&src_dir_path)?;
 
@@ -18,57 +194,330 @@ pub fn compile_dir(src_dir_path: PathBuf, dest_dir_path: PathBuf, head: &Option<
         let mut nested_dest_dir_path = dest_dir_path.clone();
         nested_dest_dir_path.push(entry.file_name());
         if entry.file_type()?.is_dir() {
-            compile_dir(entry.path(), nested_dest_dir_path, head)?;
+            if entry.path().canonicalize()? == build.dest_root { continue; }
+            compile_dir_at_depth(entry.path(), nested_dest_dir_path, &options, fix, sources, 
+                build, depth + 1)?;
             continue;
         }
         if entry.file_type()?.is_file() {
-            if let Some(extension) = entry.path().extension() {
-                if extension == "md2" {
-                    let mut dest_file_path = nested_dest_dir_path.clone();
-                    dest_file_path.set_extension(OsStr::new("html"));
-                    compile_file(entry.path(), dest_file_path, head)?;
-                }
+            if entry.path().extension().is_some_and(|extension| extension == "md2") {
+                let mut dest_file_path = nested_dest_dir_path.clone();
+                dest_file_path.set_extension(OsStr::new(options.format.extension()));
+                let mut page_path = relative_dir_path.join(entry.file_name());
+                page_path.set_extension(OsStr::new(options.format.extension()));
+                let page_options = Options { navigation: build.site.navigation(&page_path), 
+                    ..options.clone() };
+                std::fs::create_dir_all(&dest_dir_path)?;
+                let mut ctree = compile_file(entry.path(), dest_file_path.clone(), &page_options,
+                    fix, sources)?;
+                build.links.add_page(&dest_file_path, &mut ctree);
+                if let Some(search) = &mut build.search { search.add_page(&page_path, &ctree); }
+                if let Some(feed) = &mut build.feed { feed.add_page(&page_path, &ctree); }
+                build.has_highlighted_code |= has_highlighted_code(&ctree.block.children);
+                build.outputs.insert(dest_file_path);
+                continue;
             }
+            if entry.file_name() == DIR_TEMPLATE_FILE_NAME { continue; }
+            if entry.file_name() == CONFIG_FILE_NAME { continue; }
+            let relative_path = entry.path().strip_prefix(&build.src_root).unwrap().to_path_buf();
+            if !options.assets.accepts(&relative_path) { continue; }
+            std::fs::create_dir_all(&dest_dir_path)?;
+            if copy_asset(&entry.path(), &nested_dest_dir_path)? { build.copied_count += 1; }
+            build.outputs.insert(nested_dest_dir_path);
         }
     }
+    generate_index_page(&relative_dir_path, &dest_dir_path, &options, sources, build)?;
     return Ok(())
 }
 
-pub fn compile_file(input_file: std::path::PathBuf, output_file: std::path::PathBuf, head: &Option<PathBuf>)
--> std::io::Result<()> 
+/// Writes the generated index page of the directory at `relative_dir_path` into `dest_dir_path`,
+/// if the directory has one.
+fn generate_index_page<'s>(relative_dir_path: &Path, dest_dir_path: &Path, options: &Options,
+    sources: &SourceCache<'s>, build: &mut DirBuild<'s>) -> std::io::Result<()>
+{
+    let Some(page) = build.site.generated_index(relative_dir_path) else { return Ok(()); };
+    let dest_file_path = dest_dir_path.join(page.path.file_name().unwrap());
+    let mut ctree = build.site.make_index_page(page, dest_file_path.clone(), sources.map());
+    let options = Options { navigation: build.site.navigation(&page.path), ..options.clone() };
+    std::fs::create_dir_all(dest_dir_path)?;
+    let mut output = std::fs::File::create(&dest_file_path)?;
+    // The page consists only of text and links, so its code-generation cannot raise issues.
+    let mut issues: Vec<Issue> = Vec::new();
+    render_page(&ctree, options.template, &options, &mut output, &mut issues)?;
+    build.links.add_page(&dest_file_path, &mut ctree);
+    build.outputs.insert(dest_file_path);
+    build.generated_count += 1;
+    return Ok(());
+}
+
+/// Compiles the md2 source file `input_file` into `output_file` and prints the issues found
+/// in it. The `cwd` of the given `options` is replaced by the directory containing the file.
+/// Returns the *CTree* of the finished document, so that its sections and local links can be
+/// recorded once the whole directory has been compiled.
+pub fn compile_file<'s>(input_file: std::path::PathBuf, output_file: std::path::PathBuf, 
+    options: &Options, fix: bool, sources: &SourceCache<'s>) 
+-> std::io::Result<ctree::Root<'s>> 
 {
     let input_file_name = input_file.file_name().unwrap().to_str().unwrap();
     println!("{}#{} merryc {}v{}{} is compiling {}\"{}\"{}...", ansi::BOLD, ansi::STOP_BOLD,
         ansi::FG_GREY, env!("CARGO_PKG_VERSION"), ansi::FG_DEFAULT,
         ansi::FG_GREY, input_file_name, ansi::FG_DEFAULT);
     
-    let source_text = std::fs::read_to_string(&input_file)?;
-    let ltree = make_ltree(&source_text);
-    let mtree = make_mtree(&ltree);
-    
-    let mut issues: Vec<Issue> = Vec::new();
-    for issue in verify_ltree(&ltree) { issues.push(issue.into()) }
-    for issue in verify_mtree(&mtree) { issues.push(issue.into()) }
-
-    let ctree = make_ctree(mtree, &mut issues, input_file.parent().unwrap().to_path_buf());
-    
+    // The file may have been read already, to find its title or the sections referenced by
+    // another document.
+    let (mut file, mut source) = sources.read(&input_file)?;
+    if fix {
+        let (fixed_source_text, fix_count) = fix_source(source);
+        if fix_count > 0 {
+            std::fs::write(&input_file, &fixed_source_text)?;
+            println!("{}##{} applied {}{}{} fixes.", ansi::BOLD, ansi::STOP_BOLD,
+                ansi::FG_GREY, fix_count, ansi::FG_DEFAULT);
+            (file, source) = sources.update(&input_file, fixed_source_text);
+        }
+    }
+    let options = Options { cwd: input_file.parent().unwrap().to_path_buf(), ..options.clone() };
     let mut output = std::fs::OpenOptions::new()
         .write(true)
         .create(true)
         .truncate(true)
         .open(&output_file)?;
-    codegen_html::codegen(&ctree, &mut output, &mut issues, head)?; 
+    let mut issues: Vec<Issue> = Vec::new();
+    let (ctree, doc_template) = compile_source(source, file, &options, sources, &mut output,
+        &mut issues)?;
+    if let Some((template, _)) = doc_template {
+        print_template_issues(&template, sources.map());
+    }
     println!("{}##{} compilation finished with {}{}{} issues.", ansi::BOLD, ansi::STOP_BOLD,
         ansi::FG_GREY, issues.len(), ansi::FG_DEFAULT);
     println!();
     
-    issues.sort_by_key(|issue| issue.quote.first_line_no);
+    issues.sort_by_key(|issue| (issue.quote.file, issue.quote.first_line_no));
     for (i, issue) in issues.iter().enumerate() { 
         print!("{}. ", i + 1);
-        assert_matches!(input_file.as_path().to_str(), Some(in_file_path_str));
-        print_issue(issue, in_file_path_str); 
+        print_issue(issue, sources.map()); 
+    }
+    
+    return Ok(ctree);
+}
+
+/// Describes how a document is compiled.
+#[derive(Clone)]
+pub struct Options<'t> {
+    pub format: OutputFormat,
+    /// The template used unless the document selects its own. Ignored for Markdown output.
+    pub template: Option<&'t Template>,
+    /// The directory which relative paths in the document are resolved against. External
+    /// commands invoked by the `rewrite` and `embed` directives are run in this directory.
+    pub cwd: PathBuf,
+    /// The relative path from the finished document to the root of the output directory.
+    pub root_path: String,
+    /// The date of compilation formatted as `YYYY-MM-DD`.
+    pub build_date: String,
+    /// The settings which affect the interpretation of the document, but which are given
+    /// outside of it.
+    pub environment: Environment,
+    /// The level of each lint whose severity is configured.
+    pub lints: HashMap<String, LintLevel>,
+    /// HTML inserted into the `<head>` of the finished document.
+    pub head: Option<String>,
+    /// Selects the files, other than md2 source files, which are copied into the output
+    /// directory by [`compile_dir`].
+    pub assets: AssetFilter,
+    /// Whether [`compile_dir`] removes the files in the output directory which belonged to the
+    /// previous build but not to this one, for instance the finished document of a source file
+    /// since deleted. See [`crate::assets::remove_stale_outputs`].
+    pub clean: bool,
+    /// Whether [`compile_dir`] writes a search index of every document. See [`crate::search`].
+    pub search_index: bool,
+    /// Whether the text of embedded HTML, such as rewritten math, is included in the search index.
+    pub search_html: bool,
+    /// The absolute URL at which the output directory is published. If given, [`compile_dir`]
+    /// writes a sitemap and a feed. See [`crate::feed`].
+    pub base_url: Option<String>,
+    pub feed: FeedOptions,
+    /// The position of the document within the site compiled by [`compile_dir`].
+    pub navigation: Navigation
+}
+
+impl<'t> Options<'t> {
+    /// Applies the settings given by `config` on top of these options. The template is not
+    /// applied, since it must be loaded first.
+    pub fn configure(&mut self, config: &Config) {
+        if let Some(head) = &config.head { self.head = Some(head.clone()); }
+        for (lint_name, level) in &config.lints { self.lints.insert(lint_name.clone(), *level); }
+        if let Some(directives) = &config.directives {
+            self.environment.directives.push(directives.clone());
+        }
+        if let Some(allowed_commands) = &config.allowed_commands {
+            self.environment.allowed_commands = Some(allowed_commands.clone());
+        }
+        if let Some(include) = &config.include { self.assets.include = include.clone(); }
+        if let Some(exclude) = &config.exclude { self.assets.exclude = exclude.clone(); }
+        if let Some(search_index) = config.search_index { self.search_index = search_index; }
+        if let Some(search_html) = config.search_html { self.search_html = search_html; }
+        if let Some(base_url) = &config.base_url { self.base_url = Some(base_url.clone()); }
+        if let Some(title) = &config.feed.title { self.feed.title = Some(title.clone()); }
+        if let Some(author) = &config.feed.author { self.feed.author = Some(author.clone()); }
+    }
+}
+
+impl<'t> Default for Options<'t> {
+    fn default() -> Self {
+        return Options {
+            format: OutputFormat::Html,
+            template: None,
+            cwd: PathBuf::from("."),
+            root_path: String::from("."),
+            build_date: today(),
+            environment: Environment::default(),
+            lints: HashMap::new(),
+            head: None,
+            assets: AssetFilter::default(),
+            clean: false,
+            search_index: false,
+            search_html: false,
+            base_url: None,
+            feed: FeedOptions::default(),
+            navigation: Navigation::default()
+        };
     }
+}
+
+/// Compiles the document `source` and returns the finished document along with every
+/// issue found in it. Nothing is printed, and no files are read or written, except the files
+/// which the document includes and the template which it selects for itself, if any. Problems
+/// within that template are not reported, since they do not belong to `source`.
+pub fn compile_str(source: &str, options: &Options) -> (String, Vec<Diagnostic>) {
+    let sources = SourceMap::new();
+    let mut output: Vec<u8> = Vec::new();
+    let mut issues: Vec<Issue> = Vec::new();
+    let cache = SourceCache::new(&sources);
+    compile_source(source, FileId::ANONYMOUS, options, &cache, &mut output, &mut issues)
+        .expect("writing to memory cannot fail");
+    issues.sort_by_key(|issue| (issue.quote.file, issue.quote.first_line_no));
+    let diagnostics = issues.iter()
+        .map(|issue| Diagnostic::from_issue(issue, &sources))
+        .collect();
+    return (String::from_utf8_lossy(&output).into_owned(), diagnostics);
+}
+
+/// Compiles the document `source`, which was read from `file`, and writes the finished document
+/// to `out`. The issues found in the document are appended to `issues`. Returns the *CTree* of
+/// the document. If the document selects its own template, then that template is registered in
+/// `sources` and returned too, along with its path, so that its problems can be reported.
+fn compile_source<'a, W>(source: &'a str, file: FileId, options: &Options, sources: &SourceCache<'a>,
+    out: &mut W, issues: &mut Vec<Issue<'a>>) 
+-> std::io::Result<(ctree::Root<'a>, Option<(Template, PathBuf)>)>
+where W: std::io::Write
+{
+    let ltree = make_ltree(source, file);
+    let mtree = make_mtree(&ltree);
     
+    for issue in verify_ltree(&ltree) { 
+        issues.extend(apply_lint_level(issue.lint_name(), issue.into(), &options.lints));
+    }
+    for issue in verify_mtree(&mtree) { 
+        issues.extend(apply_lint_level(issue.lint_name(), issue.into(), &options.lints));
+    }
+
+    let ctree = make_ctree(mtree, issues, options.cwd.clone(), sources, &options.environment);
+
+    // A template selected by the document itself takes precedence over the directory's.
+    let mut doc_template: Option<(Template, PathBuf)> = None;
+    if let Some(template_path) = ctree.metadata.template {
+        let path = options.cwd.join(template_path.as_ref());
+        match Template::load(&path, sources.map()) {
+            Ok(loaded) => doc_template = Some((loaded, path)),
+            Err(err) => issues.push(missing_template_issue(template_path, err)),
+        }
+    }
+    let template = doc_template.as_ref().map(|(template, _)| template).or(options.template);
+    render_page(&ctree, template, options, out, issues)?;
+    return Ok((ctree, doc_template));
+}
+
+/// Writes the finished document `ctree` to `out` in the format given by `options`, applying
+/// `template` if the format is HTML.
+fn render_page<'a, W>(ctree: &ctree::Root<'a>, template: Option<&Template>, options: &Options,
+    out: &mut W, issues: &mut Vec<Issue<'a>>) -> std::io::Result<()>
+where W: std::io::Write
+{
+    let page = PageContext { root_path: options.root_path.clone(), 
+        build_date: options.build_date.clone(), head: options.head.clone().unwrap_or_default(),
+        navigation: options.navigation.clone() };
+    match (options.format, template) {
+        (OutputFormat::Markdown, _) => codegen_markdown::codegen(ctree, out, issues)?,
+        (OutputFormat::Html, Some(template)) => template.render(ctree, &page, out, issues)?,
+        (OutputFormat::Html, None) => codegen_html::codegen(ctree, &page.head, out, issues)?,
+    }
     return Ok(());
 }
 
+/// Repeatedly applies the safe fixes attached to the syntactic issues in `source` until
+/// there are none left. Returns the fixed source text and the total number of fixes applied.
+pub fn fix_source(source: &str) -> (String, usize) {
+    let mut source = source.to_string();
+    let mut total_fix_count: usize = 0;
+    for _ in 0..MAX_FIX_PASSES {
+        let ltree = make_ltree(&source, FileId::ANONYMOUS);
+        let mtree = make_mtree(&ltree);
+        let mut issues: Vec<Issue> = Vec::new();
+        for issue in verify_ltree(&ltree) { issues.push(issue.into()) }
+        for issue in verify_mtree(&mtree) { issues.push(issue.into()) }
+        let fixes = issues.iter().filter_map(|issue| issue.fix.as_ref());
+        let (fixed, fix_count) = apply_safe_fixes(&source, fixes);
+        if fix_count == 0 { break; }
+        total_fix_count += fix_count;
+        source = fixed;
+    }
+    return (source, total_fix_count);
+}
+
+/// Reads and parses the template at `path`, registering it in `sources` and printing any
+/// problems with it. If the template cannot be read then the error is printed and `None`
+/// is returned.
+pub fn load_template(path: &Path, sources: &SourceMap) -> Option<Template> {
+    let template = match Template::load(path, sources) {
+        Ok(template) => template,
+        Err(err) => {
+            print_file_error("Template file could not be read", 
+                "Documents will be compiled without a template.", path, &err);
+            return None;
+        }
+    };
+    print_template_issues(&template, sources);
+    return Some(template);
+}
+
+fn print_template_issues(template: &Template, sources: &SourceMap) {
+    for issue in template.issues() {
+        print_issue(&issue, sources);
+    }
+}
+
+fn missing_template_issue<'a>(template_path: crate::scan::SourceSpan<'a>, err: std::io::Error) 
+-> Issue<'a> 
+{
+    let mut quote = AnnotatedSourceSection::from_span(&template_path);
+    quote.highlight(template_path.begin.byte_pos, template_path.end.byte_pos);
+    Issue {
+        quote,
+        title: "Template file could not be read".into(),
+        subtext: "The document will be compiled as if this template were never selected.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: vec![
+            crate::report::Elaboration::Quote(crate::report::QuoteElaboration {
+                caption: "The following error occurred while reading the template...",
+                content: err.to_string()
+            })
+        ]
+    }
+}
+
+/// Returns the relative path from a file `depth` directories beneath the output root,
+/// to the output root itself.
+fn relative_root_path(depth: usize) -> String {
+    if depth == 0 { return String::from("."); }
+    return vec![".."; depth].join("/");
+}
@@ -0,0 +1,450 @@
+//! This module implements the project configuration file, `merry.toml`. The configuration which
+//! applies to a source file, or to a source directory, is the nearest `merry.toml` in the
+//! directory containing it or in any of that directory's ancestors. When a directory is compiled,
+//! a `merry.toml` within one of its subdirectories overrides the settings of its parent for the
+//! documents within that subdirectory.
+//!
+//! ```toml
+//! template = "_page.html"
+//! head = "head.html"
+//! output = "site"
+//! base-url = "https://merry.dev/"
+//! directives = '''
+//! | link home https://merry.dev
+//! | rewrite math katex
+//! '''
+//!
+//! [lints]
+//! excessive-vertical-space = "allow"
+//!
+//! [rewrite]
+//! allow = ["katex"]
+//!
+//! [assets]
+//! exclude = ["drafts/*"]
+//!
+//! [search]
+//! enabled = true
+//!
+//! [feed]
+//! title = "The Merry Blog"
+//! ```
+//!
+//! - `template` is the template used for every document, unless overridden by a directory
+//!   template or by the document itself.
+//! - `head` is a file whose contents are inserted into the `<head>` of every finished HTML
+//!   document, in place of the `{{head}}` placeholder when a template is used.
+//! - `output` is the output directory, used when none is given on the command line.
+//! - `base-url` is the absolute URL at which the output directory is published. When it is given,
+//!   a sitemap and an Atom feed are written into the output directory. See [`crate::feed`].
+//! - `directives` contains directives which act on the whole of every document, as if they were
+//!   invoked at its end.
+//! - `lints` sets the severity of each lint to `allow`, `warning`, or `error`.
+//! - `rewrite.allow` lists the only commands which the `rewrite` and `embed` directives may run.
+//! - `assets.include` and `assets.exclude` select the static assets copied to the output directory.
+//! - `search.enabled` writes a search index of every document into the output directory, and
+//!   `search.include-html` includes the text of embedded HTML in it. These are ignored within
+//!   the configuration of a subdirectory.
+//! - `feed.title` is the title of the feed, and `feed.author` the author of every entry which
+//!   does not declare its own, by default the title of the feed. These and `base-url` are
+//!   ignored within the configuration of a subdirectory too.
+//!
+//! Relative paths are resolved against the directory containing the configuration file.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use glob::Pattern;
+use serde::Deserialize;
+use toml::Spanned;
+use crate::builtins::{builtin_directives, BUILTIN_DIRECTIVES};
+use crate::ctree::{Block, Context};
+use crate::feed::FeedOptions;
+use crate::ltree::{self, make_ltree};
+use crate::mtree::{self, make_mtree};
+use crate::report::{Issue, AnnotatedSourceSection, Severity, Elaboration, QuoteElaboration,
+    print_file_error, print_issue};
+use crate::scan::SourceSpan;
+use crate::source_map::{FileId, SourceCache, SourceMap};
+
+pub const CONFIG_FILE_NAME: &str = "merry.toml";
+
+/// The names of the lints, the issues whose severity can be configured. Every other issue is
+/// an error, since it means the source text is likely to be misinterpreted.
+pub const LINT_NAMES: &[&str] = &["insufficient-indent", "excessive-indent",
+    "excessive-vertical-space", "abrupt-child-block", "verbatim-underindented",
+    "long-verbatim-close", "unstructured-document"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LintLevel {
+    /// The lint is never reported.
+    Allow,
+    Warning,
+    Error
+}
+
+impl LintLevel {
+    /// Applies this level to `issue`. Returns `None` if the issue should not be reported.
+    pub fn apply<'a>(&self, mut issue: Issue<'a>) -> Option<Issue<'a>> {
+        match self {
+            LintLevel::Allow => return None,
+            LintLevel::Warning => issue.severity = Severity::Warning,
+            LintLevel::Error => issue.severity = Severity::Error,
+        }
+        return Some(issue);
+    }
+}
+
+/// Applies the level configured in `lints` for the lint `lint_name` to `issue`, if any. Returns
+/// `None` if the issue should not be reported.
+pub fn apply_lint_level<'a>(lint_name: Option<&str>, issue: Issue<'a>, 
+    lints: &HashMap<String, LintLevel>) -> Option<Issue<'a>> 
+{
+    match lint_name.and_then(|lint_name| lints.get(lint_name)) {
+        Some(level) => return level.apply(issue),
+        None => return Some(issue)
+    }
+}
+
+/// The settings given by a configuration file. A setting which the file does not give is `None`.
+#[derive(Clone, Default)]
+pub struct Config {
+    pub template: Option<PathBuf>,
+    /// The contents of the head file.
+    pub head: Option<String>,
+    /// This is ignored within the configuration of a subdirectory.
+    pub output: Option<PathBuf>,
+    /// The text of the `directives` setting, paired with the path of the configuration file.
+    /// Every other character of the file is replaced by a space, so that the directives appear
+    /// at the same lines and columns as in the file.
+    pub directives: Option<(PathBuf, String)>,
+    pub lints: HashMap<String, LintLevel>,
+    pub allowed_commands: Option<Vec<String>>,
+    pub include: Option<Vec<Pattern>>,
+    pub exclude: Option<Vec<Pattern>>,
+    pub search_index: Option<bool>,
+    pub search_html: Option<bool>,
+    pub base_url: Option<String>,
+    pub feed: FeedOptions
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    template: Option<Spanned<String>>,
+    head: Option<Spanned<String>>,
+    output: Option<String>,
+    #[serde(rename = "base-url")]
+    base_url: Option<Spanned<String>>,
+    directives: Option<Spanned<String>>,
+    #[serde(default)]
+    lints: BTreeMap<Spanned<String>, Spanned<String>>,
+    rewrite: Option<RawRewriteConfig>,
+    assets: Option<RawAssetsConfig>,
+    search: Option<RawSearchConfig>,
+    feed: Option<RawFeedConfig>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRewriteConfig {
+    allow: Option<Vec<String>>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAssetsConfig {
+    include: Option<Vec<Spanned<String>>>,
+    exclude: Option<Vec<Spanned<String>>>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct RawSearchConfig {
+    enabled: Option<bool>,
+    include_html: Option<bool>
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFeedConfig {
+    title: Option<String>,
+    author: Option<String>
+}
+
+/// Returns the path of the nearest configuration file in `path` or in any of its ancestors.
+pub fn discover_config(path: &Path) -> Option<PathBuf> {
+    for dir in path.ancestors() {
+        let config_path = dir.join(CONFIG_FILE_NAME);
+        if config_path.is_file() { return Some(config_path); }
+    }
+    return None;
+}
+
+/// Reads and parses the configuration file at `path`, registering it in `sources` and printing
+/// any problems with it. Invalid settings are ignored. If the file cannot be read, or is not a
+/// valid configuration file, then the problem is printed and `None` is returned.
+pub fn load_config(path: &Path, sources: &SourceMap) -> Option<Config> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            print_file_error("Configuration file could not be read",
+                "Documents will be compiled as if it did not exist.", path, &err);
+            return None;
+        }
+    };
+    let (file, text) = sources.add(path.to_path_buf(), text);
+    let mut issues: Vec<Issue> = Vec::new();
+    let config = parse_config(text, file, path, sources, &mut issues);
+    for issue in &issues { print_issue(issue, sources); }
+    return config;
+}
+
+/// Parses the configuration file `text`, which was read from `path`, and appends the problems
+/// found in it to `issues`.
+pub fn parse_config<'a>(text: &'a str, file: FileId, path: &Path, sources: &'a SourceMap,
+    issues: &mut Vec<Issue<'a>>) -> Option<Config>
+{
+    let raw: RawConfig = match toml::from_str(text) {
+        Ok(raw) => raw,
+        Err(err) => {
+            let range = err.span().unwrap_or(0..0);
+            issues.push(invalid_config_issue(SourceSpan::from_range(text, file, range),
+                err.message(), "The configuration file will be ignored."));
+            return None;
+        }
+    };
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut config = Config { output: raw.output.map(|output| dir.join(output)),
+        ..Config::default() };
+
+    if let Some(template) = raw.template {
+        let template_path = dir.join(template.get_ref());
+        match template_path.is_file() {
+            true => config.template = Some(template_path),
+            false => issues.push(missing_file_issue(SourceSpan::from_range(text, file,
+                template.span()), "Template file does not exist", None)),
+        }
+    }
+    if let Some(head) = raw.head {
+        match std::fs::read_to_string(dir.join(head.get_ref())) {
+            Ok(head) => config.head = Some(head),
+            Err(err) => issues.push(missing_file_issue(SourceSpan::from_range(text, file,
+                head.span()), "Head file could not be read", Some(err))),
+        }
+    }
+    if let Some(directives) = raw.directives {
+        let directives_text = isolate_string(text, directives.span(), directives.get_ref());
+        let (directives_file, directives_source) = sources.add(path.to_path_buf(),
+            directives_text.clone());
+        if verify_directives(directives_source, directives_file, dir, sources, issues) {
+            config.directives = Some((path.to_path_buf(), directives_text));
+        }
+    }
+    for (name, level) in raw.lints {
+        if !LINT_NAMES.contains(&name.get_ref().as_str()) {
+            issues.push(unknown_lint_issue(SourceSpan::from_range(text, file, name.span())));
+            continue;
+        }
+        let level = match level.get_ref().as_str() {
+            "allow" => LintLevel::Allow,
+            "warning" => LintLevel::Warning,
+            "error" => LintLevel::Error,
+            _ => {
+                issues.push(invalid_config_issue(SourceSpan::from_range(text, file, level.span()),
+                    "The level of a lint must be `allow`, `warning`, or `error`.",
+                    "This setting will be ignored."));
+                continue;
+            }
+        };
+        config.lints.insert(name.into_inner(), level);
+    }
+    if let Some(rewrite) = raw.rewrite {
+        config.allowed_commands = rewrite.allow;
+    }
+    if let Some(assets) = raw.assets {
+        config.include = assets.include.map(|globs| parse_globs(globs, text, file, issues));
+        config.exclude = assets.exclude.map(|globs| parse_globs(globs, text, file, issues));
+    }
+    if let Some(base_url) = raw.base_url {
+        match is_absolute_url(base_url.get_ref()) {
+            true => config.base_url = Some(base_url.into_inner()),
+            false => issues.push(invalid_config_issue(SourceSpan::from_range(text, file,
+                base_url.span()), "The base URL must be an absolute URL beginning with \
+                `http://` or `https://`.", "This setting will be ignored.")),
+        }
+    }
+    if let Some(feed) = raw.feed {
+        config.feed = FeedOptions { title: feed.title, author: feed.author };
+    }
+    if let Some(search) = raw.search {
+        config.search_index = search.enabled;
+        config.search_html = search.include_html;
+    }
+    return Some(config);
+}
+
+/// Returns true if `url` is an absolute HTTP or HTTPS URL, and so could be the base URL of a site.
+pub fn is_absolute_url(url: &str) -> bool {
+    let Some((_, rest)) = url.split_once("://") else { return false; };
+    return (url.starts_with("http://") || url.starts_with("https://")) && rest.len() > 0;
+}
+
+fn parse_globs<'a>(globs: Vec<Spanned<String>>, text: &'a str, file: FileId,
+    issues: &mut Vec<Issue<'a>>) -> Vec<Pattern>
+{
+    let mut patterns: Vec<Pattern> = Vec::new();
+    for glob in globs {
+        match Pattern::new(glob.get_ref()) {
+            Ok(pattern) => patterns.push(pattern),
+            Err(err) => issues.push(invalid_config_issue(SourceSpan::from_range(text, file,
+                glob.span()), &format!("The glob is invalid: {}.", err.msg),
+                "This glob will be ignored.")),
+        }
+    }
+    return patterns;
+}
+
+/// Returns `text`, the configuration file, with every character outside of `value`, the TOML
+/// string at `range`, replaced by a space. If the string contains escape sequences, so that its
+/// value does not appear in the file, then the value alone is returned.
+fn isolate_string(text: &str, range: Range<usize>, value: &str) -> String {
+    let literal = &text[range.clone()];
+    let quote_len = match literal.starts_with("'''") || literal.starts_with("\"\"\"") {
+        true => 3,
+        false => 1
+    };
+    let mut begin = range.start + quote_len;
+    // A newline immediately following the opening quotes of a multi-line string is trimmed.
+    if quote_len == 3 {
+        if text[begin..].starts_with("\r\n") { begin += 2; }
+        else if text[begin..].starts_with('\n') { begin += 1; }
+    }
+    if !text[begin..].starts_with(value) { return value.to_string(); }
+    let end = begin + value.len();
+    let mut isolated = String::with_capacity(text.len());
+    for (i, c) in text.char_indices() {
+        if (begin..end).contains(&i) || c == '\n' { isolated.push(c); } else { isolated.push(' '); }
+    }
+    return isolated;
+}
+
+/// Interprets the directives in `source` on an empty document, so that the problems with them
+/// are reported once, rather than once for every document they act on. Returns false if
+/// `source` contains anything but directives, in which case it should not be used.
+fn verify_directives<'a>(source: &'a str, file: FileId, dir: &Path, sources: &'a SourceMap,
+    issues: &mut Vec<Issue<'a>>) -> bool
+{
+    let ltree = make_ltree(source, file);
+    let mut is_valid = true;
+    for child in &ltree.block.children {
+        let content_span = match child {
+            ltree::ast::BlockChild::VerticalSpace(_) => continue,
+            ltree::ast::BlockChild::Line(line) => {
+                if line.line_content.as_ref().starts_with('|') { continue; }
+                line.line_content
+            },
+            ltree::ast::BlockChild::Block(block) => block.span,
+            ltree::ast::BlockChild::Verbatim(verbatim) => verbatim.span,
+            ltree::ast::BlockChild::FencedBlock(fenced) => fenced.span,
+            ltree::ast::BlockChild::List(list) => list.children[0].content.span,
+            ltree::ast::BlockChild::DefinitionList(list) => list.children[0].term,
+        };
+        issues.push(not_a_directive_issue(content_span));
+        is_valid = false;
+    }
+    if !is_valid { return false; }
+
+    let mtree = make_mtree(&ltree);
+    let cache = SourceCache::new(sources);
+    let mut ctx = Context::new(issues, dir.to_path_buf(), &cache);
+    let mut block = Block::default();
+    for child in mtree.block.children {
+        let mtree::ast::BlockChild::Invoke(invocation) = child else { continue; };
+        if let Some(name) = invocation.args.first() {
+            if !BUILTIN_DIRECTIVES.iter().any(|usage| usage.name == name.as_ref()) {
+                ctx.issues.push(unknown_directive_issue(*name));
+                continue;
+            }
+        }
+        builtin_directives(invocation, &mut block, &mut ctx);
+    }
+    return true;
+}
+
+fn invalid_config_issue<'a>(span: SourceSpan<'a>, message: &str, consequence: &str) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&span);
+    quote.highlight(span.begin.byte_pos, span.end.byte_pos);
+    Issue {
+        quote,
+        title: "Configuration is invalid".into(),
+        subtext: format!("{}\n{}", message, consequence).into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
+
+fn unknown_lint_issue<'a>(name: SourceSpan<'a>) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&name);
+    quote.highlight(name.begin.byte_pos, name.end.byte_pos);
+    Issue {
+        quote,
+        title: format!("Unknown lint `{}`", name.as_ref()).into(),
+        subtext: format!("The lints are {}.\nThis setting will be ignored.", LINT_NAMES.join(", "))
+            .into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
+
+fn missing_file_issue<'a>(path: SourceSpan<'a>, title: &'static str, err: Option<std::io::Error>)
+-> Issue<'a>
+{
+    let mut quote = AnnotatedSourceSection::from_span(&path);
+    quote.highlight(path.begin.byte_pos, path.end.byte_pos);
+    let elaborations = match err {
+        Some(err) => vec![Elaboration::Quote(QuoteElaboration {
+            caption: "The following error occurred while reading the file...",
+            content: err.to_string()
+        })],
+        None => Vec::new()
+    };
+    Issue {
+        quote,
+        title: title.into(),
+        subtext: "The path is relative to the directory containing the configuration file.\n\
+                  This setting will be ignored.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations
+    }
+}
+
+fn unknown_directive_issue<'a>(name: SourceSpan<'a>) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&name);
+    quote.highlight(name.begin.byte_pos, name.end.byte_pos);
+    Issue {
+        quote,
+        title: format!("Unknown directive `{}`", name.as_ref()).into(),
+        subtext: "This directive will have no effect.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
+
+fn not_a_directive_issue<'a>(span: SourceSpan<'a>) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&span);
+    quote.highlight(span.begin.byte_pos, span.end.byte_pos);
+    Issue {
+        quote,
+        title: "Only directives may be given in the configuration".into(),
+        subtext: "Every line of the `directives` setting must be a directive invocation, like \
+                  `| link home https://merry.dev`.\nThis setting will be ignored.".into(),
+        severity: Severity::Error,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
@@ -13,10 +13,14 @@
 //! delimiters of spans for instance. Note that the CTree does contain references into the source 
 //! text as there are many cases where the source text can be copied verbatim into the finished document.
 
-use crate::builtins::builtin_directives;
+use crate::builtins::{builtin_directives, make_hyperlinks, report_undefined_references, 
+    resolve_cross_references, substitute_definition};
 use crate::misc::remove_first;
-use crate::report::Issue;
+use crate::highlight::Language;
+use crate::report::{Issue, AnnotatedSourceSection, Severity};
 use crate::scan::SourceSpan;
+use crate::source_map::{FileId, SourceCache};
+use crate::ltree;
 use crate::ttree;
 use crate::mtree;
 
@@ -25,8 +29,51 @@ use crate::mtree;
 #[derive(Default, Debug)] 
 pub struct InlineRoot<'a> { pub children: Vec<AnyInline<'a>> }
 
+impl<'a> InlineRoot<'a> {
+    /// Appends the text of this inline content to `out`, excluding all markup. 
+    /// Embedded HTML is excluded as well since its text is not known until code-generation.
+    pub fn write_plain_text(&self, out: &mut String) { self.write_text(false, out); }
+
+    /// Appends the text of this inline content to `out`, excluding all markup. If `include_html`
+    /// is true, then embedded HTML is written too, and the text between its tags is kept.
+    /// See [`write_html_text`].
+    pub fn write_text(&self, include_html: bool, out: &mut String) {
+        for child in &self.children {
+            match child {
+                AnyInline::Plain(node) => out.push_str(node.span.as_ref()),
+                AnyInline::Hyperlink(node) => node.child_root.write_text(include_html, out),
+                AnyInline::CrossReference(node) => {
+                    node.child_root.write_text(include_html, out);
+                    if let Some(text) = &node.text { out.push_str(text); }
+                },
+                AnyInline::Emboldened(node) => node.child_root.write_text(include_html, out),
+                AnyInline::Italicized(node) => node.child_root.write_text(include_html, out),
+                AnyInline::Underlined(node) => node.child_root.write_text(include_html, out),
+                AnyInline::TaggedSpan(node) => node.child_root.write_text(include_html, out),
+                AnyInline::ImplicitSpace(_) => out.push(' '),
+                AnyInline::Verbatim(node) => 
+                    for span in &node.content { out.push_str(span.as_ref()); },
+                AnyInline::InlineCodeSnippet(node) => 
+                    for span in &node.inner_spans { out.push_str(span.as_ref()); },
+                AnyInline::HTML(node) => if include_html { write_html_text(node.value.as_ref(), out); },
+                AnyInline::None => (),
+            }
+        }
+    }
+
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        self.write_plain_text(&mut text);
+        return text;
+    }
+}
+
 #[derive(Debug)]
-pub struct InlineVerbatim<'a> { pub content: Vec<SourceSpan<'a>>, pub tags: Vec<SourceSpan<'a>> }
+pub struct InlineVerbatim<'a> { 
+    pub content: Vec<SourceSpan<'a>>,
+    pub tags: Vec<SourceSpan<'a>>,
+    pub attrs: Attributes<'a>
+}
 
 #[derive(Debug)]
 pub struct ImplicitSpace;
@@ -35,6 +82,7 @@ pub struct ImplicitSpace;
 pub enum AnyInline<'a> {
     Plain(PlainText<'a>),
     Hyperlink(HyperlinkText<'a>),
+    CrossReference(CrossReference<'a>),
     Emboldened(EmboldenedText<'a>),
     Italicized(ItalicizedText<'a>),
     Underlined(UnderlinedText<'a>),
@@ -55,7 +103,78 @@ pub enum AnyInline<'a> {
 pub struct PlainText<'a> { pub span: SourceSpan<'a> }
 
 #[derive(Debug)]
-pub struct HyperlinkText<'a> { pub href: SourceSpan<'a>, pub child_root: InlineRoot<'a> }
+pub struct HyperlinkText<'a> { 
+    pub href: SourceSpan<'a>,
+    /// The advisory text shown when hovering over the link, given by the `href` directive.
+    pub title: Option<SourceSpan<'a>>,
+    pub child_root: InlineRoot<'a>
+}
+
+impl<'a> HyperlinkText<'a> {
+    /// Returns the URL written to the finished document. A local link to an md2 source file is
+    /// rewritten to link to the finished document compiled from it, whose extension is `extension`.
+    pub fn url(&self, extension: &str) -> String {
+        return rewrite_source_url(self.href.as_ref(), extension);
+    }
+}
+
+/// Returns true if `url` refers to a place within the output directory, that is, it is a relative
+/// path or a fragment, rather than an absolute URL like `https://example.com` or `//example.com`.
+pub fn is_local_url(url: &str) -> bool {
+    if url.starts_with('/') { return false; }
+    // A colon preceding the first slash, question mark, or number sign ends the scheme.
+    let path_end = url.find(['/', '?', '#']).unwrap_or(url.len());
+    return !url[..path_end].contains(':');
+}
+
+/// Replaces the `.md2` extension of the path in the local URL `url` with `extension`.
+/// Any other URL is returned unchanged.
+pub fn rewrite_source_url(url: &str, extension: &str) -> String {
+    if !is_local_url(url) { return url.to_string(); }
+    let path_end = url.find(['?', '#']).unwrap_or(url.len());
+    let (path, rest) = url.split_at(path_end);
+    match path.strip_suffix(".md2") {
+        Some(stem) => return format!("{}.{}{}", stem, extension, rest),
+        None => return url.to_string()
+    }
+}
+
+/// A hyperlink to a section, or to a whole document, created by the `ref` directive. The target 
+/// is resolved once the whole document has been interpreted.
+#[derive(Debug)]
+pub struct CrossReference<'a> {
+    /// The directive argument naming the target, for instance `install` or `guide.md2#install`.
+    pub target: SourceSpan<'a>,
+    /// The path of the document containing the target as written in the directive, or `None`
+    /// if the target is within this document.
+    pub document: Option<String>,
+    /// The path of the document containing the target, resolved against the directory of the
+    /// file which invoked the directive.
+    pub document_path: Option<std::path::PathBuf>,
+    /// The identifier of the target section, or `None` if the target is a whole document.
+    pub section_id: Option<String>,
+    /// The heading of the target section, or the title of the target document. This is written
+    /// after `child_root` when the span marked by the user was empty.
+    pub text: Option<String>,
+    pub child_root: InlineRoot<'a>
+}
+
+impl<'a> CrossReference<'a> {
+    /// Returns the URL of the target. The extension of the target document is replaced with
+    /// `extension`, the extension of the finished documents.
+    pub fn href(&self, extension: &str) -> String {
+        let mut href = String::new();
+        if let Some(document) = &self.document {
+            let stem = document.strip_suffix(".md2").unwrap_or(document);
+            href.push_str(&format!("{}.{}", stem, extension));
+        }
+        if let Some(section_id) = &self.section_id {
+            href.push('#');
+            href.push_str(section_id);
+        }
+        return href;
+    }
+}
 
 #[derive(Debug)]
 pub struct EmboldenedText<'a> { pub child_root: InlineRoot<'a> }
@@ -67,7 +186,11 @@ pub struct ItalicizedText<'a> { pub child_root: InlineRoot<'a> }
 pub struct UnderlinedText<'a> { pub child_root: InlineRoot<'a> }
 
 #[derive(Debug)]
-pub struct TaggedSpan<'a> { pub child_root: InlineRoot<'a>, pub tags: Vec<SourceSpan<'a>> }
+pub struct TaggedSpan<'a> { 
+    pub child_root: InlineRoot<'a>,
+    pub tags: Vec<SourceSpan<'a>>,
+    pub attrs: Attributes<'a>
+}
 
 #[derive(Debug)]
 pub struct InlineCodeSnippet<'a> { pub inner_spans: Vec<SourceSpan<'a>> }
@@ -75,16 +198,85 @@ pub struct InlineCodeSnippet<'a> { pub inner_spans: Vec<SourceSpan<'a>> }
 #[derive(Debug)]
 pub struct InlineHTML<'a> { pub value: Box<dyn Writable<'a> + 'a> }
 
+/// The presentational attributes of a tagged node. These are assigned by the `element` and
+/// `class` directives and are otherwise empty. A node with empty attributes is rendered using
+/// its default HTML element.
+#[derive(Default, Debug)]
+pub struct Attributes<'a> {
+    pub element: Option<SourceSpan<'a>>,
+    pub classes: Vec<SourceSpan<'a>>
+}
+
+impl<'a> Attributes<'a> {
+    pub fn is_empty(&self) -> bool { self.element.is_none() && self.classes.is_empty() }
+}
+
+// # Document Metadata
+
+/// Information describing the document as a whole. This is declared by the `title`, `lang`,
+/// and `meta` directives, which may appear anywhere in the document.
+#[derive(Default, Debug)]
+pub struct Metadata<'a> {
+    pub title: Option<SourceSpan<'a>>,
+    pub lang: Option<SourceSpan<'a>>,
+    /// The path of the template selected by the `template` directive, relative to the
+    /// directory containing the document.
+    pub template: Option<SourceSpan<'a>>,
+    /// Every `meta` declaration in order of appearance in the source text. For instance
+    /// `description`, `author`, and `date`.
+    pub entries: Vec<MetadataEntry<'a>>
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MetadataEntry<'a> { pub name: SourceSpan<'a>, pub value: SourceSpan<'a> }
+
+impl<'a> Metadata<'a> {
+    /// Returns the value of the first `meta` declaration with the given name.
+    pub fn get(&self, name: &str) -> Option<SourceSpan<'a>> {
+        self.entries.iter().find(|e| e.name.as_ref() == name).map(|e| e.value)
+    }
+
+    pub fn description(&self) -> Option<SourceSpan<'a>> { self.get("description") }
+
+    pub fn author(&self) -> Option<SourceSpan<'a>> { self.get("author") }
+
+    pub fn date(&self) -> Option<SourceSpan<'a>> { self.get("date") }
+}
+
 // # Block Elements
 
 #[derive(Debug)]
-pub struct Root<'a> { pub block: Block<'a> }
+pub struct Root<'a> { pub block: Block<'a>, pub metadata: Metadata<'a> }
+
+impl<'a> Root<'a> {
+    /// Returns the title of the document. That is the title declared by the `title` directive,
+    /// or if there is no such declaration, the heading of the first top-level section.
+    pub fn title(&self) -> Option<String> {
+        if let Some(title) = self.metadata.title { return Some(title.as_ref().to_string()); }
+        for child in &self.block.children {
+            if let BlockChild::Section(section) = child {
+                return Some(section.heading.content.plain_text().trim().to_string());
+            }
+        }
+        return None;
+    }
+}
 
 #[derive(Debug)]
-pub struct VerbatimBlock<'a> { pub lines: Vec<SourceSpan<'a>>, pub tags: Vec<SourceSpan<'a>> }
+pub struct VerbatimBlock<'a> { 
+    pub lines: Vec<SourceSpan<'a>>,
+    pub tags: Vec<SourceSpan<'a>>,
+    pub attrs: Attributes<'a>
+}
 
 #[derive(Debug)]
-pub struct Section<'a> { pub heading: Heading<'a>, pub children: Vec<BlockChild<'a>> }
+pub struct Section<'a> { 
+    pub heading: Heading<'a>,
+    pub children: Vec<BlockChild<'a>>,
+    /// An identifier derived from the heading text which is unique within the document.
+    /// This is the anchor that links to the section.
+    pub id: String
+}
 
 #[derive(Debug)]
 pub struct List<'a> { pub elements: Vec<ListElement<'a>> }
@@ -96,7 +288,11 @@ pub struct Heading<'a> { pub hlevel: usize, pub content: InlineRoot<'a> }
 pub struct Paragraph<'a> { pub content: InlineRoot<'a> }
 
 #[derive(Debug)]
-pub struct CodeSnippet<'a> { pub lines: Vec<SourceSpan<'a>> }
+pub struct CodeSnippet<'a> { 
+    pub lines: Vec<SourceSpan<'a>>,
+    /// The language of the code, given by a `lang:<name>` tag. If given, the code is highlighted.
+    pub language: Option<Language>
+}
 
 #[derive(Default, Debug)]
 pub struct Block<'a> { 
@@ -107,6 +303,60 @@ pub struct Block<'a> {
 #[derive(Debug)]
 pub struct ThematicBreak;
 
+/// A block which was fenced in the source text and marked with tags that no builtin
+/// interprets. It is rendered like an ordinary block unless a directive acts on its tags.
+#[derive(Debug)]
+pub struct TaggedBlock<'a> { 
+    pub content: Block<'a>,
+    pub tags: Vec<SourceSpan<'a>>,
+    pub attrs: Attributes<'a>
+}
+
+/// A fenced block marked with one of the builtin `note`, `warning`, `tip`, or `danger` tags.
+/// Admonitions are set apart from the surrounding text in the finished document.
+#[derive(Debug)]
+pub struct Admonition<'a> { 
+    pub kind: AdmonitionKind, 
+    pub content: Block<'a>,
+    /// The other tags on the fenced block, which presentational directives can act upon.
+    pub tags: Vec<SourceSpan<'a>>,
+    pub attrs: Attributes<'a>
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AdmonitionKind { Note, Warning, Tip, Danger }
+
+impl AdmonitionKind {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "note" => Some(AdmonitionKind::Note),
+            "warning" => Some(AdmonitionKind::Warning),
+            "tip" => Some(AdmonitionKind::Tip),
+            "danger" => Some(AdmonitionKind::Danger),
+            _ => None
+        }
+    }
+
+    /// The tag which marks an admonition of this kind. This is also used as its CSS class.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Warning => "warning",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Danger => "danger",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Warning => "Warning",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Danger => "Danger",
+        }
+    }
+}
+
 /// An arbitrary piece of HTML which will be embedded into the finished document during the 
 /// code-generation phase.
 #[derive(Debug)]
@@ -123,6 +373,9 @@ pub enum BlockChild<'a> {
     Heading(Heading<'a>),
     CodeSnippet(CodeSnippet<'a>),
     ThematicBreak(ThematicBreak),
+    TaggedBlock(TaggedBlock<'a>),
+    Admonition(Admonition<'a>),
+    DefinitionList(DefinitionList<'a>),
     None
 }
 
@@ -144,19 +397,22 @@ impl<'a> Container<'a> for Block<'a> {
 }
 
 impl<'a> BlockChild<'a> {
-    /// Returns an exclusive reference to the inline content of this node (if it has some
-    /// inline content), otherwise returns `None`. 
+    /// Returns exclusive references to the inline content of this node (if it has some
+    /// inline content), otherwise returns an empty `Vec`. 
     ///
     /// Currently, the following node types have inline content....
     /// - Heading
     /// - Section (its heading's inline content)
     /// - Paragraph
-    pub fn inline_content_mut(&mut self) -> Option<&mut InlineRoot<'a>> {
+    /// - DefinitionList (its terms' inline content)
+    pub fn inline_content_mut(&mut self) -> Vec<&mut InlineRoot<'a>> {
         match self {
-            BlockChild::Section(node) => Some(&mut node.heading.content),
-            BlockChild::Paragraph(node) => Some(&mut node.content),
-            BlockChild::Heading(node) => Some(&mut node.content),
-            _ => None
+            BlockChild::Section(node) => vec![&mut node.heading.content],
+            BlockChild::Paragraph(node) => vec![&mut node.content],
+            BlockChild::Heading(node) => vec![&mut node.content],
+            BlockChild::DefinitionList(node) => 
+                node.definitions.iter_mut().map(|d| &mut d.term).collect(),
+            _ => Vec::new()
         }
     }
 }
@@ -164,7 +420,18 @@ impl<'a> BlockChild<'a> {
 // # List Elements
 
 #[derive(Debug)]
-pub struct ListElement<'a> { pub content: Block<'a> }
+pub struct ListElement<'a> { pub content: Block<'a>, pub task: Option<Task<'a>> }
+
+/// The completion state of a task list element. The `marker` refers to the `[ ]` or `[x]`
+/// in the source text, so that tasks can be located later.
+#[derive(Debug, Clone, Copy)]
+pub struct Task<'a> { pub checked: bool, pub marker: SourceSpan<'a> }
+
+#[derive(Debug)]
+pub struct DefinitionList<'a> { pub definitions: Vec<Definition<'a>> }
+
+#[derive(Debug)]
+pub struct Definition<'a> { pub term: InlineRoot<'a>, pub content: Block<'a> }
 
 pub trait Writable<'a>: std::fmt::Debug {
     /// Writes this entire `Writable` to `out`. 
@@ -177,17 +444,136 @@ pub trait Writable<'a>: std::fmt::Debug {
     fn write(&self, out: &mut dyn std::io::Write, issues: &mut Vec<Issue<'a>>);
 }
 
+/// Writes `value` and appends the text between its tags to `out`, with the character references
+/// for markup characters decoded. Writing some HTML, for instance that of the `rewrite` directive,
+/// runs an external command. Problems writing it are not reported, since they are reported when
+/// the document is generated.
+pub fn write_html_text<'a>(value: &(dyn Writable<'a> + 'a), out: &mut String) {
+    let mut html: Vec<u8> = Vec::new();
+    value.write(&mut html, &mut Vec::new());
+    let html = String::from_utf8_lossy(&html);
+    let mut text = String::new();
+    let mut is_within_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => is_within_tag = true,
+            '>' if is_within_tag => { is_within_tag = false; text.push(' '); },
+            _ if is_within_tag => (),
+            _ => text.push(c)
+        }
+    }
+    out.push_str(&text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"")
+        .replace("&#39;", "'").replace("&nbsp;", " ").replace("&amp;", "&"));
+}
+
 // # Interpret *MTree*
 
-pub struct Context<'a, 'b> { pub issues: &'b mut Vec<Issue<'a>>, pub cwd: std::path::PathBuf }
+pub struct Context<'a, 'b> { 
+    pub issues: &'b mut Vec<Issue<'a>>,
+    pub cwd: std::path::PathBuf,
+    pub metadata: Metadata<'a>,
+    pub section_ids: std::collections::HashSet<String>,
+    /// The registry which the files read by the `include` and `ref` directives are added to.
+    pub sources: &'b SourceCache<'a>,
+    /// The `include` invocations which led to the file being interpreted, outermost first.
+    /// Each is paired with the canonical path of the file it included.
+    pub includes: Vec<(std::path::PathBuf, SourceSpan<'a>)>,
+    /// The heading level of the innermost enclosing section, or zero outside of any section.
+    pub hlevel: usize,
+    /// The number of levels added to every heading in the file being interpreted. This is 
+    /// non-zero within an included file, so that its headings nest beneath the section
+    /// it was included into.
+    pub hlevel_offset: usize,
+    /// The links defined by `href` directives in each container being interpreted, innermost
+    /// last. This is used to detect redefinitions within the same container.
+    pub link_scopes: Vec<Vec<LinkDefinition<'a>>>,
+    /// The document-global links defined by `link` directives. These are applied to the whole
+    /// document once it has been interpreted.
+    pub links: Vec<LinkDefinition<'a>>,
+    /// The commands which the `rewrite` and `embed` directives may run, or `None` if they may
+    /// run any command.
+    pub allowed_commands: Option<Vec<String>>
+}
 
+impl<'a, 'b> Context<'a, 'b> {
+    pub fn new(issues: &'b mut Vec<Issue<'a>>, cwd: std::path::PathBuf, sources: &'b SourceCache<'a>) 
+    -> Self 
+    {
+        return Context { issues, cwd, metadata: Metadata::default(), 
+            section_ids: std::collections::HashSet::new(), sources, includes: Vec::new(),
+            hlevel: 0, hlevel_offset: 0, link_scopes: Vec::new(), links: Vec::new(),
+            allowed_commands: None };
+    }
+}
+
+/// A URL which the spans marked with `tag` are converted into hyperlinks to.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkDefinition<'a> {
+    pub tag: SourceSpan<'a>,
+    pub href: SourceSpan<'a>,
+    pub title: Option<SourceSpan<'a>>
+}
+
+/// The path under which values defined on the command line are registered in the source map.
+pub const COMMAND_LINE_PATH: &str = "<command line>";
+
+/// The settings which affect the interpretation of a document, but which are given outside of
+/// it, on the command line or in the project configuration.
+#[derive(Clone, Default)]
+pub struct Environment {
+    /// Values given on the command line with `-D name=value`. These act as if they were defined
+    /// at the end of the document, so they are substituted only for the references which no
+    /// `define` directive in the document gives a value to.
+    pub defines: Vec<(String, String)>,
+    /// Source text containing directives which act on the whole document, as if they were
+    /// invoked at its end, paired with the path of the configuration file which gave them.
+    /// The directives of the innermost configuration are last, but are applied first, so that
+    /// they take precedence over those of its parent.
+    pub directives: Vec<(std::path::PathBuf, String)>,
+    /// The commands which the `rewrite` and `embed` directives may run, or `None` if they may
+    /// run any command.
+    pub allowed_commands: Option<Vec<String>>
+}
+
+/// Interprets the given *MTree* within the given `env`.
 pub fn make_ctree<'a, 'b>(mtree: mtree::ast::Root<'a>, issues: &'b mut Vec<Issue<'a>>,
-     cwd: std::path::PathBuf)
+     cwd: std::path::PathBuf, sources: &SourceCache<'a>, env: &Environment)
 -> Root<'a> 
 {
-    let mut ctx = Context { issues, cwd };
-    let block = interpret_mtree_block(mtree.block, &mut ctx);
-    return Root { block }
+    let mut ctx = Context::new(issues, cwd.clone(), sources);
+    ctx.allowed_commands = env.allowed_commands.clone();
+    let mut block = interpret_mtree_block(mtree.block, &mut ctx);
+    for (path, text) in env.directives.iter().rev() {
+        let (file, text) = sources.add(path, text);
+        apply_default_directives(text, file, &mut block, &mut ctx);
+    }
+    for (name, value) in &env.defines {
+        let (file, value) = sources.add(std::path::Path::new(COMMAND_LINE_PATH), value);
+        substitute_definition(name, SourceSpan::whole(value, file), &mut block);
+    }
+    for link in &ctx.links { make_hyperlinks(link, &mut block); }
+    resolve_cross_references(&mut block, &cwd, sources, ctx.issues);
+    report_undefined_references(&mut block, ctx.issues);
+    return Root { block, metadata: ctx.metadata }
+}
+
+/// Applies the directives in `source` to `block`, the whole document. Only the issues in the
+/// document itself are reported. Those in `source` are reported once, when the configuration
+/// which gave them is loaded, rather than once for every document.
+pub fn apply_default_directives<'a, 'b>(source: &'a str, file: FileId, block: &mut Block<'a>,
+    ctx: &mut Context<'a, 'b>)
+{
+    let ltree = ltree::make_ltree(source, file);
+    let mtree = mtree::make_mtree(&ltree);
+    let issue_count = ctx.issues.len();
+    for child in mtree.block.children {
+        let mtree::ast::BlockChild::Invoke(invocation) = child else { continue; };
+        interpret_invocation(block, invocation, ctx);
+    }
+    let mut i = issue_count;
+    while i < ctx.issues.len() {
+        if ctx.issues[i].quote.file == file { ctx.issues.remove(i); } else { i += 1; }
+    }
 }
 
 fn interpret_mtree_node<'a, 'b>(ctree_parent: &mut impl Container<'a>, 
@@ -199,7 +585,7 @@ fn interpret_mtree_node<'a, 'b>(ctree_parent: &mut impl Container<'a>,
             ctree_parent.children_mut().push(BlockChild::Paragraph(ctree_p));
         },
         mtree::ast::BlockChild::Heading(mtree_h) => {
-            let ctree_h = interpret_mtree_heading(mtree_h);
+            let ctree_h = interpret_mtree_heading(mtree_h, ctx.hlevel_offset);
             ctree_parent.children_mut().push(BlockChild::Heading(ctree_h));
         },
         mtree::ast::BlockChild::Block(mtree_b) => {
@@ -211,7 +597,7 @@ fn interpret_mtree_node<'a, 'b>(ctree_parent: &mut impl Container<'a>,
             ctree_parent.children_mut().push(BlockChild::List(ctree_l));
         },
         mtree::ast::BlockChild::VerbatimBlock(mtree_v) => {
-            let ctree_v = interpret_mtree_verbatim(mtree_v);
+            let ctree_v = interpret_mtree_verbatim(mtree_v, ctx);
             ctree_parent.children_mut().push(ctree_v);
         },
         mtree::ast::BlockChild::Section(mtree_s) => {
@@ -221,6 +607,14 @@ fn interpret_mtree_node<'a, 'b>(ctree_parent: &mut impl Container<'a>,
         mtree::ast::BlockChild::Invoke(mtree_i) => {
             interpret_invocation(ctree_parent, mtree_i, ctx);
         }
+        mtree::ast::BlockChild::DefinitionList(mtree_l) => {
+            let ctree_l = interpret_mtree_definition_list(mtree_l, ctx);
+            ctree_parent.children_mut().push(BlockChild::DefinitionList(ctree_l));
+        },
+        mtree::ast::BlockChild::FencedBlock(mtree_f) => {
+            let ctree_f = interpret_mtree_fenced_block(mtree_f, ctx);
+            ctree_parent.children_mut().push(ctree_f);
+        },
         mtree::ast::BlockChild::ExplicitSectionClose(_) => {},
     }
 }
@@ -230,19 +624,25 @@ fn interpret_mtree_paragraph<'a>(ast_p: mtree::ast::Paragraph<'a>) -> Paragraph<
     return Paragraph { content };
 }
 
-fn interpret_mtree_heading<'a>(ast_h: mtree::ast::Heading<'a>) -> Heading<'a> {
+fn interpret_mtree_heading<'a>(ast_h: mtree::ast::Heading<'a>, hlevel_offset: usize) -> Heading<'a> {
     let content = interpret_ttree_root(ast_h.content);
-    let hlevel = ast_h.hlevel;
+    // HTML has only six levels of heading, so re-levelled headings are capped at the sixth.
+    let hlevel = match hlevel_offset {
+        0 => ast_h.hlevel,
+        _ => std::cmp::min(ast_h.hlevel + hlevel_offset, 6)
+    };
     return Heading { hlevel, content };
 }
 
-fn interpret_mtree_block<'a, 'b>(ast_block: mtree::ast::Block<'a>, ctx: &mut Context<'a, 'b>)
+pub fn interpret_mtree_block<'a, 'b>(ast_block: mtree::ast::Block<'a>, ctx: &mut Context<'a, 'b>)
  -> Block<'a> 
 {
     let mut ctree_block = Block::default();
+    ctx.link_scopes.push(Vec::new());
     for ast_child in ast_block.children {
         interpret_mtree_node(&mut ctree_block, ast_child, ctx);
     }
+    ctx.link_scopes.pop();
     return ctree_block;
 }
 
@@ -252,40 +652,129 @@ fn interpret_mtree_list<'a, 'b>(ast_l: mtree::ast::List<'a>, ctx: &mut Context<'
     let mut ctree_elements: Vec<ListElement<'a>> = Vec::new();
     for ast_element in ast_l.elements {
         let element_block = interpret_mtree_block(ast_element.content, ctx);
-        ctree_elements.push(ListElement { content: element_block });
+        let task = ast_element.task.map(|t| Task { checked: t.checked, marker: t.span });
+        ctree_elements.push(ListElement { content: element_block, task });
     }
     return List { elements: ctree_elements };
 }
 
-fn interpret_mtree_verbatim<'a>(ast_v: mtree::ast::VerbatimBlock<'a>) -> BlockChild<'a> {
+fn interpret_mtree_definition_list<'a, 'b>(ast_l: mtree::ast::DefinitionList<'a>, 
+    ctx: &mut Context<'a, 'b>) -> DefinitionList<'a> 
+{
+    let mut ctree_definitions: Vec<Definition<'a>> = Vec::new();
+    for ast_definition in ast_l.definitions {
+        let term = interpret_ttree_root(ast_definition.term);
+        let content = interpret_mtree_block(ast_definition.content, ctx);
+        ctree_definitions.push(Definition { term, content });
+    }
+    return DefinitionList { definitions: ctree_definitions };
+}
+
+fn interpret_mtree_verbatim<'a, 'b>(ast_v: mtree::ast::VerbatimBlock<'a>, 
+    ctx: &mut Context<'a, 'b>) -> BlockChild<'a> 
+{
     let mut tags = make_tags(ast_v.trailing_qualifier);
+    let is_code = remove_first(&mut tags, |t| t.as_ref() == "m").is_some();
+    // A `lang` tag implies the `m` tag, since only code is highlighted.
+    let lang_tag = remove_first(&mut tags, |t| t.as_ref().starts_with("lang:"));
 
-    if remove_first(&mut tags, |t| t.as_ref() == "m").is_some() {
+    if is_code || lang_tag.is_some() {
         // TODO: Report issue if there are other tags on this verbatim block.
         //       The m tag cannot coexist with other tags on a verbatim block.
-        return BlockChild::CodeSnippet(CodeSnippet { lines: ast_v.lines });
+        let language = lang_tag.and_then(|tag| {
+            let name = &tag.as_ref()["lang:".len()..];
+            let language = Language::from_name(name);
+            if language.is_none() { ctx.issues.push(unknown_language_issue(tag)); }
+            return language;
+        });
+        return BlockChild::CodeSnippet(CodeSnippet { lines: ast_v.lines, language });
     }
     
-    return BlockChild::Verbatim(VerbatimBlock { lines: ast_v.lines, tags });
+    return BlockChild::Verbatim(VerbatimBlock { lines: ast_v.lines, tags, 
+        attrs: Attributes::default() });
+}
+
+fn unknown_language_issue<'a>(tag: SourceSpan<'a>) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&tag);
+    quote.highlight(tag.begin.byte_pos, tag.end.byte_pos);
+    Issue {
+        quote,
+        title: format!("Unknown language `{}`", &tag.as_ref()["lang:".len()..]).into(),
+        subtext: format!("The languages which can be highlighted are {}.\n\
+            The code will not be highlighted.", Language::names().join(", ")).into(),
+        severity: Severity::Warning,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
+
+fn interpret_mtree_fenced_block<'a, 'b>(ast_f: mtree::ast::FencedBlock<'a>, 
+    ctx: &mut Context<'a, 'b>) -> BlockChild<'a> 
+{
+    let mut tags = make_tags(ast_f.trailing_qualifier);
+    let content = interpret_mtree_block(ast_f.content, ctx);
+
+    let admonition_tag = remove_first(&mut tags, |t| AdmonitionKind::from_tag(t.as_ref()).is_some());
+    if let Some(tag) = admonition_tag {
+        let kind = AdmonitionKind::from_tag(tag.as_ref()).unwrap();
+        return BlockChild::Admonition(Admonition { kind, content, tags, 
+            attrs: Attributes::default() });
+    }
+
+    return BlockChild::TaggedBlock(TaggedBlock { content, tags, attrs: Attributes::default() });
 }
 
 fn interpret_mtree_section<'a, 'b>(ast_s: mtree::ast::Section<'a>, ctx: &mut Context<'a, 'b>) 
 -> Section<'a> 
 {
-    let mut ctree_section: Section<'a> = Section {
-        heading: interpret_mtree_heading(ast_s.heading),
-        children: Vec::new(),
-    };
+    let heading = interpret_mtree_heading(ast_s.heading, ctx.hlevel_offset);
+    let id = make_section_id(&heading, ctx);
+    let parent_hlevel = std::mem::replace(&mut ctx.hlevel, heading.hlevel);
+    let mut ctree_section: Section<'a> = Section { heading, children: Vec::new(), id };
 
+    ctx.link_scopes.push(Vec::new());
     for ast_child in ast_s.children {
         interpret_mtree_node(&mut ctree_section, ast_child, ctx);
     }
+    ctx.link_scopes.pop();
+    ctx.hlevel = parent_hlevel;
 
     mark_section_ambiguities(&mut ctree_section);
 
     return ctree_section;
 }
 
+/// Derives an identifier for the section with the given heading. Lowercase letters and digits
+/// are kept and all other runs of characters are replaced with a single hyphen. If the 
+/// identifier is already in use within the document then a numeric suffix is appended.
+fn make_section_id<'a, 'b>(heading: &Heading<'a>, ctx: &mut Context<'a, 'b>) -> String {
+    let slug = slugify(&heading.content.plain_text());
+    let mut id = slug.clone();
+    let mut suffix: usize = 2;
+    while ctx.section_ids.contains(&id) {
+        id = format!("{}-{}", slug, suffix);
+        suffix += 1;
+    }
+    ctx.section_ids.insert(id.clone());
+    return id;
+}
+
+/// Derives an identifier from the given text. Lowercase letters and digits are kept and all
+/// other runs of characters are replaced with a single hyphen.
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+            continue;
+        }
+        if slug.len() > 0 && !slug.ends_with('-') { slug.push('-'); }
+    }
+    while slug.ends_with('-') { slug.pop(); }
+    if slug.len() == 0 { slug.push_str("section"); }
+    return slug;
+}
+
 fn interpret_invocation<'a, 'b>(scope: &mut impl Container<'a>, 
     invocation: mtree::ast::DirectiveInvocation<'a>, ctx: &mut Context<'a, 'b>)
 {
@@ -326,13 +815,14 @@ fn interpret_inline_verbatim<'a>(ast_node: ttree::ast::InlineVerbatim<'a>) -> An
     if remove_first(&mut tags, |t| t.as_ref() == "m").is_some() {
         let inner_spans = ast_node.inner_spans;
         let snippet = AnyInline::InlineCodeSnippet(InlineCodeSnippet { inner_spans });
-        let mut wrapper = TaggedSpan { child_root: InlineRoot::default(), tags };
+        let mut wrapper = TaggedSpan { child_root: InlineRoot::default(), tags,
+            attrs: Attributes::default() };
         wrapper.child_root.children.push(snippet);
         return AnyInline::TaggedSpan(wrapper);
     }
 
     let content = ast_node.inner_spans;
-    return AnyInline::Verbatim(InlineVerbatim { content, tags });
+    return AnyInline::Verbatim(InlineVerbatim { content, tags, attrs: Attributes::default() });
 }
 
 fn interpret_delimeted_text<'a>(ast_node: ttree::ast::DelimitedText<'a>) -> AnyInline<'a> {
@@ -358,7 +848,8 @@ fn interpret_delimeted_text<'a>(ast_node: ttree::ast::DelimitedText<'a>) -> AnyI
 fn interpret_bracketed_text<'a>(ast_node: ttree::ast::BracketedText<'a>) -> AnyInline<'a> {
     AnyInline::TaggedSpan(TaggedSpan { 
         child_root: interpret_ttree_root(ast_node.child_root),
-        tags: make_tags(ast_node.trailing_qualifier)
+        tags: make_tags(ast_node.trailing_qualifier),
+        attrs: Attributes::default()
     })
 }
 
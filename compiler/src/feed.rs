@@ -0,0 +1,255 @@
+//! This module implements the sitemap and the Atom feed of a site. Both are written into the output
+//! directory by [`crate::compile::compile_dir`] when a base URL is given, since each must contain
+//! absolute URLs.
+//!
+//! - `sitemap.xml` lists every page of the site, including generated index pages.
+//! - `feed.xml` contains an entry for every document which declares its date, like a blog post.
+//!   The date is given by the `date` metadata, formatted as `YYYY-MM-DD`, or as an RFC 3339
+//!   timestamp. The summary of an entry is the first paragraph of the document.
+//!
+//! ```md2
+//! | meta date 2024-05-01
+//! | meta author Duncan
+//! ```
+
+use std::path::Path;
+use crate::codegen_html::write_escaped;
+use crate::ctree::{self, BlockChild};
+use crate::misc::relative_url_path;
+use crate::report::{Issue, AnnotatedSourceSection, Severity};
+use crate::scan::SourceSpan;
+use crate::site::SitePage;
+
+pub const SITEMAP_FILE_NAME: &str = "sitemap.xml";
+pub const FEED_FILE_NAME: &str = "feed.xml";
+
+/// A document which declares its date.
+struct FeedEntry {
+    /// The path of the finished document relative to the output directory.
+    url: String,
+    title: String,
+    author: Option<String>,
+    /// An RFC 3339 timestamp.
+    updated: String,
+    summary: Option<String>
+}
+
+/// The settings of the feed, given by the project configuration.
+#[derive(Clone, Default, Debug)]
+pub struct FeedOptions {
+    /// The title of the feed. If not given, the title of the index page of the site is used.
+    pub title: Option<String>,
+    /// The author of every entry which does not declare its own `author` metadata. If not given,
+    /// the title of the feed is used, since an Atom feed must name an author.
+    pub author: Option<String>
+}
+
+pub struct Feed<'a> {
+    /// The absolute URL of the output directory, ending with a slash.
+    base_url: String,
+    entries: Vec<FeedEntry>,
+    /// The problems found with the dates declared by documents.
+    issues: Vec<Issue<'a>>
+}
+
+impl<'a> Feed<'a> {
+    pub fn new(base_url: &str) -> Self {
+        let mut base_url = base_url.to_string();
+        if !base_url.ends_with('/') { base_url.push('/'); }
+        return Feed { base_url, entries: Vec::new(), issues: Vec::new() };
+    }
+
+    /// Adds `root`, the document at `page_path`, relative to the output directory, to the feed,
+    /// if it declares its date.
+    pub fn add_page(&mut self, page_path: &Path, root: &ctree::Root<'a>) {
+        let Some(date) = root.metadata.get("date") else { return; };
+        let Some(updated) = parse_date(date.as_ref()) else {
+            self.issues.push(invalid_date_issue(date));
+            return;
+        };
+        let title = root.title().unwrap_or_else(|| {
+            page_path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+        });
+        self.entries.push(FeedEntry {
+            url: relative_url_path(Path::new(""), page_path),
+            title,
+            author: root.metadata.get("author").map(|author| author.as_ref().to_string()),
+            updated,
+            summary: first_paragraph(&root.block.children)
+        });
+    }
+
+    pub fn entry_count(&self) -> usize { self.entries.len() }
+
+    /// Returns the problems found with the dates declared by documents, which were left out of
+    /// the feed.
+    pub fn issues(&self) -> &[Issue<'a>] { &self.issues }
+
+    /// Writes the sitemap, listing every one of `pages`, and the feed, if it has any entries,
+    /// into `dest_dir_path`. `site_title` is the title of the index page of the site. Returns
+    /// the paths of the files written.
+    pub fn write(&mut self, dest_dir_path: &Path, pages: &[SitePage], site_title: Option<&str>,
+        options: &FeedOptions) -> std::io::Result<Vec<std::path::PathBuf>>
+    {
+        let mut written: Vec<std::path::PathBuf> = Vec::new();
+        let sitemap_path = dest_dir_path.join(SITEMAP_FILE_NAME);
+        let mut sitemap: Vec<u8> = Vec::new();
+        self.write_sitemap(pages, &mut sitemap)?;
+        std::fs::write(&sitemap_path, sitemap)?;
+        written.push(sitemap_path);
+        if self.entries.len() == 0 { return Ok(written); }
+
+        // The newest entries come first. The timestamps are compared as text, which orders
+        // them correctly as long as they share a time zone.
+        self.entries.sort_by(|a, b| b.updated.cmp(&a.updated));
+        let feed_path = dest_dir_path.join(FEED_FILE_NAME);
+        let mut feed: Vec<u8> = Vec::new();
+        let title = options.title.as_deref().or(site_title).unwrap_or("Feed");
+        self.write_feed(title, options, &mut feed)?;
+        std::fs::write(&feed_path, feed)?;
+        written.push(feed_path);
+        return Ok(written);
+    }
+
+    fn write_sitemap<W>(&self, pages: &[SitePage], out: &mut W) -> std::io::Result<()>
+    where W: std::io::Write
+    {
+        write!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        write!(out, "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n")?;
+        for page in pages {
+            let url = relative_url_path(Path::new(""), &page.path);
+            write!(out, "<url><loc>")?;
+            write_escaped(&self.absolute_url(&url), out)?;
+            write!(out, "</loc>")?;
+            if let Some(entry) = self.entries.iter().find(|entry| entry.url == url) {
+                write!(out, "<lastmod>")?;
+                write_escaped(&entry.updated, out)?;
+                write!(out, "</lastmod>")?;
+            }
+            write!(out, "</url>\n")?;
+        }
+        write!(out, "</urlset>\n")?;
+        return Ok(());
+    }
+
+    fn write_feed<W>(&self, title: &str, options: &FeedOptions, out: &mut W) -> std::io::Result<()>
+    where W: std::io::Write
+    {
+        write!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        write!(out, "<feed xmlns=\"http://www.w3.org/2005/Atom\">\n")?;
+        write_element("title", title, out)?;
+        write!(out, "<link href=\"")?;
+        write_escaped(&self.absolute_url(FEED_FILE_NAME), out)?;
+        write!(out, "\" rel=\"self\"/>\n<link href=\"")?;
+        write_escaped(&self.base_url, out)?;
+        write!(out, "\"/>\n")?;
+        write_element("id", &self.base_url, out)?;
+        write_element("updated", &self.entries[0].updated, out)?;
+        write_author(options.author.as_deref().unwrap_or(title), out)?;
+        for entry in &self.entries {
+            write!(out, "<entry>\n")?;
+            write_element("title", &entry.title, out)?;
+            let url = self.absolute_url(&entry.url);
+            write!(out, "<link href=\"")?;
+            write_escaped(&url, out)?;
+            write!(out, "\"/>\n")?;
+            write_element("id", &url, out)?;
+            write_element("updated", &entry.updated, out)?;
+            if let Some(author) = &entry.author { write_author(author, out)?; }
+            if let Some(summary) = &entry.summary { write_element("summary", summary, out)?; }
+            write!(out, "</entry>\n")?;
+        }
+        write!(out, "</feed>\n")?;
+        return Ok(());
+    }
+
+    fn absolute_url(&self, url: &str) -> String {
+        return format!("{}{}", self.base_url, encode_url_path(url));
+    }
+}
+
+fn write_element<W>(name: &str, text: &str, out: &mut W) -> std::io::Result<()>
+where W: std::io::Write
+{
+    write!(out, "<{}>", name)?;
+    write_escaped(text, out)?;
+    write!(out, "</{}>\n", name)?;
+    return Ok(());
+}
+
+fn write_author<W>(name: &str, out: &mut W) -> std::io::Result<()> where W: std::io::Write {
+    write!(out, "<author>\n")?;
+    write_element("name", name, out)?;
+    write!(out, "</author>\n")?;
+    return Ok(());
+}
+
+/// Percent-encodes the characters of the URL path `url` which may not appear in a URL as is.
+fn encode_url_path(url: &str) -> String {
+    let mut encoded = String::with_capacity(url.len());
+    for byte in url.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char);
+            },
+            _ => encoded.push_str(&format!("%{:02X}", byte))
+        }
+    }
+    return encoded;
+}
+
+/// Returns the plain text of the first paragraph within `children`, searching sections too.
+fn first_paragraph<'a>(children: &[BlockChild<'a>]) -> Option<String> {
+    for child in children {
+        let paragraph = match child {
+            BlockChild::Paragraph(paragraph) => Some(paragraph.content.plain_text()),
+            BlockChild::Section(section) => first_paragraph(&section.children),
+            BlockChild::Block(block) => first_paragraph(&block.children),
+            _ => None
+        };
+        if let Some(paragraph) = paragraph {
+            let text = paragraph.split_whitespace().collect::<Vec<&str>>().join(" ");
+            if text.len() > 0 { return Some(text); }
+        }
+    }
+    return None;
+}
+
+/// Converts `date`, formatted as `YYYY-MM-DD`, or as an RFC 3339 timestamp, into an RFC 3339
+/// timestamp. Returns `None` if the date is formatted otherwise.
+fn parse_date(date: &str) -> Option<String> {
+    let (day, time) = match date.split_once('T') {
+        Some((day, time)) => (day, Some(time)),
+        None => (date, None)
+    };
+    let fields: Vec<&str> = day.split('-').collect();
+    let [year, month, day] = fields[..] else { return None; };
+    let is_numeric = |field: &str, len: usize| field.len() == len
+        && field.bytes().all(|byte| byte.is_ascii_digit());
+    if !is_numeric(year, 4) || !is_numeric(month, 2) || !is_numeric(day, 2) { return None; }
+    if !(1..=12).contains(&month.parse::<u32>().ok()?) { return None; }
+    if !(1..=31).contains(&day.parse::<u32>().ok()?) { return None; }
+    let Some(time) = time else { return Some(format!("{}T00:00:00Z", date)); };
+    let is_timestamp = time.len() >= 9
+        && time.bytes().take(8).enumerate().all(|(i, byte)| match i {
+            2 | 5 => byte == b':',
+            _ => byte.is_ascii_digit()
+        })
+        && (time.ends_with('Z') || time.contains(['+', '-']));
+    if !is_timestamp { return None; }
+    return Some(date.to_string());
+}
+
+fn invalid_date_issue<'a>(date: SourceSpan<'a>) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&date);
+    quote.highlight(date.begin.byte_pos, date.end.byte_pos);
+    Issue {
+        quote,
+        title: "Date is not formatted as YYYY-MM-DD".into(),
+        subtext: "A date must be formatted as YYYY-MM-DD, or as an RFC 3339 timestamp like \
+                  2024-05-01T09:30:00Z.\nThe document will be left out of the feed.".into(),
+        severity: Severity::Warning,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
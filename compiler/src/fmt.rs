@@ -0,0 +1,224 @@
+//! This module implements the canonical source formatter. The formatter reproduces the source
+//! text from its *LTree*, except the indentation and separation of blocks is made to satisfy
+//! the conventions enforced by [`crate::ltree::verify_ltree`]...
+//!
+//! - A child block is indented exactly three spaces past its parent.
+//! - A child block is separated from its preceding sibling by a blank line.
+//! - Redundant blank lines are removed.
+//! - The lines of a backtick block begin at the same column as its declarator.
+//! - Closing backtick declarators and closing fences are as long as the opening ones.
+//!
+//! The content of every line, including the content of backtick blocks, is preserved
+//! byte-for-byte. Problems which are not merely stylistic, like an unclosed backtick block,
+//! are left as they are.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use crate::ltree::{ast, make_ltree};
+use crate::misc::ansi;
+use crate::source_map::FileId;
+
+/// The maximum number of times the formatter is reapplied to its own output. Formatting one
+/// block can change how its neighbors are grouped, so the formatter is reapplied until the
+/// source text stops changing.
+const MAX_PASSES: usize = 8;
+
+/// Returns the canonically formatted version of `source`.
+pub fn format_source(source: &str) -> String {
+    let mut formatted = source.to_string();
+    for _ in 0..MAX_PASSES {
+        let next = format_once(&formatted);
+        if next == formatted { break; }
+        formatted = next;
+    }
+    return formatted;
+}
+
+fn format_once(source: &str) -> String {
+    let ltree = make_ltree(source, FileId::ANONYMOUS);
+    let mut lines: Vec<String> = Vec::new();
+    let separation = Separation::Verified { allow_double_break: false };
+    format_block(&ltree.block, 0, 0, separation, &mut lines);
+    // Three blank lines following a nested block end the document. The text following them
+    // is not part of the LTree, but it must not be lost.
+    let remainder = &source[ltree.block.span.end.byte_pos..];
+    if remainder.len() == 0 {
+        while lines.last().is_some_and(|line| line.len() == 0) { lines.pop(); }
+    }
+    let mut formatted = String::new();
+    for line in lines {
+        formatted.push_str(&line);
+        formatted.push('\n');
+    }
+    formatted.push_str(remainder);
+    return formatted;
+}
+
+/// Describes whether the blank separator lines within a block are checked by the linter,
+/// and if so, how many blank lines are allowed at the end of the block.
+#[derive(Clone, Copy)]
+enum Separation {
+    Verified { allow_double_break: bool },
+    Unverified
+}
+
+#[allow(unused_assignments)]
+fn format_block<'b>(block: &ast::Block<'b>, indent: usize, depth: usize, separation: Separation,
+    lines: &mut Vec<String>)
+{
+    let mut blank_line_count: usize = 0;
+    macro_rules! push_blank_lines { ($is_tail:expr) => {
+        let limit = match separation {
+            // Three blank lines at the end of a nested block end the entire document, so
+            // they are not merely stylistic.
+            _ if $is_tail && depth > 0 && blank_line_count > 2 => blank_line_count,
+            Separation::Verified { allow_double_break } =>
+                if $is_tail && allow_double_break { 2 } else { 1 },
+            Separation::Unverified => blank_line_count,
+        };
+        for _ in 0..std::cmp::min(blank_line_count, limit) { lines.push(String::new()); }
+        blank_line_count = 0;
+    }}
+
+    for (i, child) in block.children.iter().enumerate() {
+        if let ast::BlockChild::VerticalSpace(_) = child {
+            blank_line_count += 1;
+            continue;
+        }
+        push_blank_lines!(/* is_tail = */ false);
+        match child {
+            ast::BlockChild::Line(line) =>
+                lines.push(format!("{}{}", spaces(indent), line.line_content.as_ref())),
+            ast::BlockChild::Block(child_block) => {
+                let mut allow_double_break = false;
+                if i > 0 {
+                    let prev = &block.children[i - 1];
+                    // A child block must be separated from its preceding sibling.
+                    if !matches!(tail(prev), ast::BlockChild::VerticalSpace(_)) {
+                        lines.push(String::new());
+                    }
+                    // Once formatted, sibling blocks share the same indent, so the tail of the
+                    // preceding sibling shares this block's indent only if it is that sibling.
+                    if let ast::BlockChild::Block(prev_block) = prev {
+                        allow_double_break = std::ptr::eq(tail_block(prev_block), prev_block);
+                    }
+                }
+                format_block(child_block, indent + 3, depth + 1,
+                    Separation::Verified { allow_double_break }, lines);
+            },
+            ast::BlockChild::List(list) => {
+                for element in &list.children {
+                    let first_line_i = lines.len();
+                    format_block(&element.content, indent + 3, depth + 1, Separation::Unverified, lines);
+                    let content = match lines.get(first_line_i) {
+                        Some(first_line) => first_line.trim_start().to_string(),
+                        None => { lines.push(String::new()); String::new() }
+                    };
+                    lines[first_line_i] = format!("{}-- {}", spaces(indent), content);
+                }
+            },
+            ast::BlockChild::DefinitionList(list) => {
+                for definition in &list.children {
+                    lines.push(format!("{}:: {}", spaces(indent), definition.term.as_ref()));
+                    format_block(&definition.content, indent + 3, depth + 1, Separation::Unverified,
+                        lines);
+                }
+            },
+            ast::BlockChild::Verbatim(verbatim) => {
+                let fence = verbatim.open.as_ref();
+                lines.push(format!("{}{}", spaces(indent), fence));
+                for line in &verbatim.lines {
+                    lines.push(format!("{}{}", spaces(indent), line.as_ref()));
+                }
+                if verbatim.close.is_some() {
+                    let tail = verbatim.tail.as_ref().map(|tail| tail.as_ref()).unwrap_or("");
+                    lines.push(format!("{}{}{}", spaces(indent), fence, tail));
+                }
+            },
+            ast::BlockChild::FencedBlock(fenced) => {
+                let fence = fenced.open.as_ref();
+                lines.push(format!("{}{}", spaces(indent), fence));
+                let separation = Separation::Verified { allow_double_break: false };
+                format_block(&fenced.content, indent, depth + 1, separation, lines);
+                if fenced.close.is_some() {
+                    let tail = fenced.tail.as_ref().map(|tail| tail.as_ref()).unwrap_or("");
+                    lines.push(format!("{}{}{}", spaces(indent), fence, tail));
+                }
+            },
+            ast::BlockChild::VerticalSpace(_) => unreachable!(),
+        }
+    }
+    push_blank_lines!(/* is_tail = */ true);
+}
+
+fn spaces(count: usize) -> String { " ".repeat(count) }
+
+/// Returns the last node within `node`, descending into blocks, lists, and definition lists.
+/// This mirrors the procedure used by the linter to find the separator preceding a block.
+fn tail<'a, 'b>(node: &'a ast::BlockChild<'b>) -> &'a ast::BlockChild<'b> {
+    let last = match node {
+        ast::BlockChild::Block(block) => block.children.last(),
+        ast::BlockChild::List(list) =>
+            list.children.last().and_then(|element| element.content.children.last()),
+        ast::BlockChild::DefinitionList(list) =>
+            list.children.last().and_then(|definition| definition.content.children.last()),
+        _ => None
+    };
+    return match last {
+        Some(last) => tail(last),
+        None => node
+    };
+}
+
+/// Returns the last block within `block`, descending into blocks, lists, and definition lists.
+fn tail_block<'a, 'b>(block: &'a ast::Block<'b>) -> &'a ast::Block<'b> {
+    return match block.children.last() {
+        Some(ast::BlockChild::Block(child)) => tail_block(child),
+        Some(ast::BlockChild::List(list)) => match list.children.last() {
+            Some(element) => tail_block(&element.content),
+            None => block
+        },
+        Some(ast::BlockChild::DefinitionList(list)) => match list.children.last() {
+            Some(definition) => tail_block(&definition.content),
+            None => block
+        },
+        _ => block
+    };
+}
+
+/// Formats the source file at `path` in place. Or, if `check` is set, leaves the file
+/// untouched. Returns true if the file was, or would have been, changed.
+pub fn format_file(path: &Path, check: bool) -> std::io::Result<bool> {
+    let source = std::fs::read_to_string(path)?;
+    let formatted = format_source(&source);
+    if formatted == source { return Ok(false); }
+    if check {
+        println!("{}#{} {}\"{}\"{} is not formatted", ansi::BOLD, ansi::STOP_BOLD,
+            ansi::FG_GREY, path.display(), ansi::FG_DEFAULT);
+        return Ok(true);
+    }
+    std::fs::write(path, formatted)?;
+    println!("{}#{} formatted {}\"{}\"{}", ansi::BOLD, ansi::STOP_BOLD,
+        ansi::FG_GREY, path.display(), ansi::FG_DEFAULT);
+    return Ok(true);
+}
+
+/// Formats every `.md2` file within the directory at `path` and its subdirectories.
+/// Returns the number of files which were, or would have been, changed.
+pub fn format_dir(path: &Path, check: bool) -> std::io::Result<usize> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    let mut changed_count: usize = 0;
+    for entry in entries {
+        if entry.is_dir() {
+            changed_count += format_dir(&entry, check)?;
+            continue;
+        }
+        if entry.extension() == Some(OsStr::new("md2")) && format_file(&entry, check)? {
+            changed_count += 1;
+        }
+    }
+    return Ok(changed_count);
+}
@@ -0,0 +1,570 @@
+//! This module implements the builtin syntax highlighter. A code block is highlighted when its
+//! verbatim is tagged with the language of the code, for instance `{m, lang:rust}`.
+//!
+//! The highlighter is a simple lexer which recognizes the comments, strings, numbers, keywords
+//! and the like of each language. It does not parse the code, so it never fails, but it can be
+//! fooled by unusual code. Each token is written as a `<span>` whose class names its kind, for
+//! instance `hl-keyword`, and is colored by a stylesheet. [`THEME_CSS`] is the default one.
+
+use crate::ctree::BlockChild;
+
+/// The default stylesheet for highlighted code. It adapts to the color scheme of the reader.
+pub const THEME_CSS: &str = include_str!("highlight.css");
+
+/// The name of the file which the default stylesheet is written to when a directory is compiled.
+pub const THEME_FILE_NAME: &str = "highlight.css";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language { Rust, Shell, Json, Toml, Python, Merry }
+
+/// The names by which each language is selected, the first being its canonical name.
+const LANGUAGE_NAMES: &[(Language, &[&str])] = &[
+    (Language::Rust, &["rust", "rs"]),
+    (Language::Shell, &["shell", "sh", "bash", "zsh", "console"]),
+    (Language::Json, &["json"]),
+    (Language::Toml, &["toml"]),
+    (Language::Python, &["python", "py"]),
+    (Language::Merry, &["merry", "md2"]),
+];
+
+impl Language {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        return LANGUAGE_NAMES.iter()
+            .find(|(_, names)| names.contains(&name.as_str()))
+            .map(|(language, _)| *language);
+    }
+
+    /// The canonical name of the language, for instance `rust`.
+    pub fn name(&self) -> &'static str {
+        return LANGUAGE_NAMES.iter()
+            .find(|(language, _)| language == self)
+            .map(|(_, names)| names[0])
+            .unwrap();
+    }
+
+    /// Returns the canonical names of every language, for use in messages.
+    pub fn names() -> Vec<&'static str> {
+        return LANGUAGE_NAMES.iter().map(|(_, names)| names[0]).collect();
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+    Keyword,
+    Type,
+    /// A literal denoted by a word, like `true`, `false`, or `None`.
+    Constant,
+    Number,
+    String,
+    Comment,
+    Function,
+    /// A shell variable expansion, like `$HOME`.
+    Variable,
+    /// A key of a JSON object or a TOML table.
+    Key,
+    /// A Merry heading, or a TOML table header.
+    Heading,
+    /// A Merry directive invocation.
+    Directive,
+    /// A Merry trailing qualifier, like `{m}`.
+    Tag
+}
+
+impl TokenKind {
+    /// The class of the `<span>` which this kind of token is written as.
+    pub fn class(&self) -> &'static str {
+        match self {
+            TokenKind::Keyword => "hl-keyword",
+            TokenKind::Type => "hl-type",
+            TokenKind::Constant => "hl-constant",
+            TokenKind::Number => "hl-number",
+            TokenKind::String => "hl-string",
+            TokenKind::Comment => "hl-comment",
+            TokenKind::Function => "hl-function",
+            TokenKind::Variable => "hl-variable",
+            TokenKind::Key => "hl-key",
+            TokenKind::Heading => "hl-heading",
+            TokenKind::Directive => "hl-directive",
+            TokenKind::Tag => "hl-tag",
+        }
+    }
+}
+
+/// A piece of code. Text which is not a token of any kind, like whitespace and punctuation,
+/// has no kind.
+#[derive(Clone, Copy, Debug)]
+pub struct Token<'t> { pub kind: Option<TokenKind>, pub text: &'t str }
+
+/// Splits `code` into tokens. Concatenating the text of the tokens yields `code`.
+pub fn highlight<'t>(language: Language, code: &'t str) -> Vec<Token<'t>> {
+    let mut lexer = Lexer { code, pos: 0, plain_begin: 0, tokens: Vec::new() };
+    match language {
+        Language::Rust => lexer.lex(&RUST),
+        Language::Shell => lexer.lex(&SHELL),
+        Language::Json => lexer.lex(&JSON),
+        Language::Toml => lexer.lex(&TOML),
+        Language::Python => lexer.lex(&PYTHON),
+        Language::Merry => lexer.lex_merry(),
+    }
+    lexer.flush_plain();
+    return lexer.tokens;
+}
+
+/// Returns true if there is a highlighted code block among `children`.
+pub fn has_highlighted_code<'a>(children: &[BlockChild<'a>]) -> bool {
+    return children.iter().any(|child| match child {
+        BlockChild::CodeSnippet(snippet) => snippet.language.is_some(),
+        BlockChild::Section(section) => has_highlighted_code(&section.children),
+        BlockChild::Block(block) => has_highlighted_code(&block.children),
+        BlockChild::List(list) => list.elements.iter()
+            .any(|element| has_highlighted_code(&element.content.children)),
+        BlockChild::DefinitionList(list) => list.definitions.iter()
+            .any(|definition| has_highlighted_code(&definition.content.children)),
+        BlockChild::TaggedBlock(node) => has_highlighted_code(&node.content.children),
+        BlockChild::Admonition(node) => has_highlighted_code(&node.content.children),
+        _ => false
+    });
+}
+
+/// A kind of string literal.
+struct StringSyntax {
+    open: &'static str,
+    close: &'static str,
+    /// Whether a backslash escapes the character following it.
+    escapes: bool,
+    /// Whether the string may span many lines. Otherwise it ends at the end of the line.
+    multiline: bool
+}
+
+const fn string(open: &'static str, close: &'static str, escapes: bool, multiline: bool)
+-> StringSyntax
+{
+    return StringSyntax { open, close, escapes, multiline };
+}
+
+/// Describes the tokens of a language for the generic lexer.
+struct Syntax {
+    line_comment: Option<&'static str>,
+    /// Whether a line comment must begin a word, like in the shell, where `a#b` is one word.
+    comment_begins_word: bool,
+    block_comment: Option<(&'static str, &'static str)>,
+    /// The kinds of string literal, tried in order, so longer delimiters must come first.
+    strings: &'static [StringSyntax],
+    keywords: &'static [&'static str],
+    constants: &'static [&'static str],
+    types: &'static [&'static str],
+    /// Whether capitalized words are types, like in Rust.
+    capitalized_types: bool,
+    /// Whether a word followed by `(` is a function.
+    calls: bool,
+    /// Whether a word followed by `!` is a macro, like in Rust. Macros are functions too.
+    macros: bool,
+    /// Characters which may continue a number besides digits, letters, underscores, and dots.
+    number_chars: &'static str,
+    /// Whether `$NAME` and `${NAME}` are variables, like in the shell.
+    variables: bool,
+    /// Whether a string followed by a colon is a key, like in JSON.
+    string_keys: bool,
+    /// Whether a line of the form `key = value` begins with a key, and a line of the form
+    /// `[table]` is a heading, like in TOML.
+    toml_lines: bool,
+    /// Whether `'a'` is a character literal while `'a` alone is a lifetime, like in Rust.
+    char_literals: bool,
+    /// Whether `@name` at the beginning of a line is a decorator, like in Python.
+    decorators: bool
+}
+
+const RUST: Syntax = Syntax {
+    line_comment: Some("//"),
+    comment_begins_word: false,
+    block_comment: Some(("/*", "*/")),
+    strings: &[string("r#\"", "\"#", false, true), string("r\"", "\"", false, true),
+        string("b\"", "\"", true, true), string("\"", "\"", true, true)],
+    keywords: &["as", "async", "await", "break", "const", "continue", "crate", "dyn", "else",
+        "enum", "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while", "yield"],
+    constants: &["true", "false", "None", "Some", "Ok", "Err"],
+    types: &["bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32",
+        "i64", "i128", "isize", "f32", "f64"],
+    capitalized_types: true,
+    calls: true,
+    macros: true,
+    number_chars: "",
+    variables: false,
+    string_keys: false,
+    toml_lines: false,
+    char_literals: true,
+    decorators: false
+};
+
+const SHELL: Syntax = Syntax {
+    line_comment: Some("#"),
+    comment_begins_word: true,
+    block_comment: None,
+    strings: &[string("'", "'", false, true), string("\"", "\"", true, true)],
+    keywords: &["if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case",
+        "esac", "in", "function", "return", "local", "export", "readonly", "select", "time"],
+    constants: &["true", "false"],
+    types: &[],
+    capitalized_types: false,
+    calls: false,
+    macros: false,
+    number_chars: "",
+    variables: true,
+    string_keys: false,
+    toml_lines: false,
+    char_literals: false,
+    decorators: false
+};
+
+const JSON: Syntax = Syntax {
+    line_comment: None,
+    comment_begins_word: false,
+    block_comment: None,
+    strings: &[string("\"", "\"", true, false)],
+    keywords: &[],
+    constants: &["true", "false", "null"],
+    types: &[],
+    capitalized_types: false,
+    calls: false,
+    macros: false,
+    number_chars: "+-",
+    variables: false,
+    string_keys: true,
+    toml_lines: false,
+    char_literals: false,
+    decorators: false
+};
+
+const TOML: Syntax = Syntax {
+    line_comment: Some("#"),
+    comment_begins_word: false,
+    block_comment: None,
+    strings: &[string("\"\"\"", "\"\"\"", true, true), string("'''", "'''", false, true),
+        string("\"", "\"", true, false), string("'", "'", false, false)],
+    keywords: &[],
+    constants: &["true", "false", "inf", "nan"],
+    types: &[],
+    capitalized_types: false,
+    calls: false,
+    macros: false,
+    // Dates and times, like 1979-05-27T07:32:00Z, are numbers too.
+    number_chars: "+-:",
+    variables: false,
+    string_keys: false,
+    toml_lines: true,
+    char_literals: false,
+    decorators: false
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comment: Some("#"),
+    comment_begins_word: false,
+    block_comment: None,
+    strings: &[string("\"\"\"", "\"\"\"", true, true), string("'''", "'''", true, true),
+        string("\"", "\"", true, false), string("'", "'", true, false)],
+    keywords: &["and", "as", "assert", "async", "await", "break", "class", "continue", "def",
+        "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in",
+        "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with",
+        "yield", "match", "case"],
+    constants: &["True", "False", "None", "self"],
+    types: &["int", "float", "str", "bool", "list", "dict", "set", "tuple", "bytes", "object"],
+    capitalized_types: false,
+    calls: true,
+    macros: false,
+    number_chars: "",
+    variables: false,
+    string_keys: false,
+    toml_lines: false,
+    char_literals: false,
+    decorators: true
+};
+
+struct Lexer<'t> {
+    code: &'t str,
+    pos: usize,
+    /// The beginning of the text preceding `pos` which is not yet part of any token.
+    plain_begin: usize,
+    tokens: Vec<Token<'t>>
+}
+
+impl<'t> Lexer<'t> {
+    fn rest(&self) -> &'t str { &self.code[self.pos..] }
+
+    fn is_end(&self) -> bool { self.pos >= self.code.len() }
+
+    /// Returns true if the character preceding the cursor cannot be part of a word.
+    fn at_word_begin(&self) -> bool {
+        return !self.code[..self.pos].chars().next_back().is_some_and(is_word_char);
+    }
+
+    /// Returns true if nothing but indentation precedes the cursor on its line.
+    fn at_line_begin(&self) -> bool {
+        let line = self.code[..self.pos].rsplit('\n').next().unwrap_or("");
+        return line.trim_start_matches([' ', '\t']).len() == 0;
+    }
+
+    /// Returns the byte length of the line remaining after the cursor, excluding the linebreak.
+    fn line_len(&self) -> usize {
+        return self.rest().find('\n').unwrap_or(self.rest().len());
+    }
+
+    /// Returns the first character after the cursor and `offset` bytes which is not a space.
+    fn next_nonspace(&self, offset: usize) -> Option<char> {
+        return self.rest()[offset..].chars().find(|c| *c != ' ' && *c != '\t');
+    }
+
+    fn flush_plain(&mut self) {
+        if self.plain_begin < self.pos {
+            self.tokens.push(Token { kind: None, text: &self.code[self.plain_begin..self.pos] });
+        }
+        self.plain_begin = self.pos;
+    }
+
+    /// Makes the `len` bytes following the cursor a token of the given kind.
+    fn push(&mut self, kind: TokenKind, len: usize) {
+        self.flush_plain();
+        self.pos += len;
+        self.tokens.push(Token { kind: Some(kind), text: &self.code[self.plain_begin..self.pos] });
+        self.plain_begin = self.pos;
+    }
+
+    /// Advances the cursor past one character without making it a part of any token.
+    fn skip(&mut self) {
+        self.pos += self.rest().chars().next().map(char::len_utf8).unwrap_or(1);
+    }
+
+    fn lex(&mut self, syntax: &Syntax) {
+        while !self.is_end() {
+            if syntax.toml_lines && self.at_line_begin() && self.lex_toml_line_begin() { continue; }
+            if let Some(len) = self.match_comment(syntax) {
+                self.push(TokenKind::Comment, len);
+                continue;
+            }
+            if let Some(len) = self.match_string(syntax) {
+                let is_key = syntax.string_keys && self.next_nonspace(len) == Some(':');
+                self.push(if is_key { TokenKind::Key } else { TokenKind::String }, len);
+                continue;
+            }
+            if syntax.variables {
+                if let Some(len) = self.match_variable() {
+                    self.push(TokenKind::Variable, len);
+                    continue;
+                }
+            }
+            if syntax.decorators && self.rest().starts_with('@') && self.at_line_begin() {
+                let len = 1 + word_len(&self.rest()[1..]);
+                self.push(TokenKind::Function, len);
+                continue;
+            }
+            let c = self.rest().chars().next().unwrap();
+            if c.is_ascii_digit() && self.at_word_begin() {
+                let len = self.number_len(syntax);
+                self.push(TokenKind::Number, len);
+                continue;
+            }
+            if is_word_char(c) && self.at_word_begin() {
+                let len = word_len(self.rest());
+                match self.classify_word(&self.rest()[..len], len, syntax) {
+                    Some(kind) => self.push(kind, len),
+                    None => self.pos += len
+                }
+                continue;
+            }
+            self.skip();
+        }
+    }
+
+    fn match_comment(&self, syntax: &Syntax) -> Option<usize> {
+        if let Some(prefix) = syntax.line_comment {
+            let is_comment = self.rest().starts_with(prefix)
+                && (!syntax.comment_begins_word || self.code[..self.pos].chars().next_back()
+                    .is_none_or(char::is_whitespace));
+            if is_comment { return Some(self.line_len()); }
+        }
+        if let Some((open, close)) = syntax.block_comment {
+            if self.rest().starts_with(open) {
+                let len = self.rest()[open.len()..].find(close)
+                    .map(|i| open.len() + i + close.len())
+                    .unwrap_or(self.rest().len());
+                return Some(len);
+            }
+        }
+        return None;
+    }
+
+    fn match_string(&self, syntax: &Syntax) -> Option<usize> {
+        let rest = self.rest();
+        if syntax.char_literals && rest.starts_with('\'') {
+            return char_literal_len(rest);
+        }
+        for kind in syntax.strings {
+            if !rest.starts_with(kind.open) { continue; }
+            // A prefixed string, like `r"..."`, must begin a word.
+            let is_prefixed = kind.open.starts_with(|c: char| c.is_alphabetic());
+            if is_prefixed && !self.at_word_begin() { continue; }
+            let mut chars = rest.char_indices().skip(kind.open.chars().count()).peekable();
+            while let Some((i, c)) = chars.next() {
+                if rest[i..].starts_with(kind.close) { return Some(i + kind.close.len()); }
+                if c == '\n' && !kind.multiline { return Some(i); }
+                if c == '\\' && kind.escapes { chars.next(); }
+            }
+            return Some(rest.len());
+        }
+        return None;
+    }
+
+    fn match_variable(&self) -> Option<usize> {
+        let rest = self.rest().strip_prefix('$')?;
+        if rest.starts_with('{') {
+            return Some(1 + rest.find('}').map(|i| i + 1).unwrap_or(rest.len()));
+        }
+        let len = word_len(rest);
+        if len > 0 { return Some(1 + len); }
+        let special = rest.chars().next().filter(|c| "@*#?$!-0123456789".contains(*c))?;
+        return Some(1 + special.len_utf8());
+    }
+
+    fn number_len(&self, syntax: &Syntax) -> usize {
+        let rest = self.rest();
+        let mut len = 0;
+        for (i, c) in rest.char_indices() {
+            let continues = is_word_char(c) || syntax.number_chars.contains(c)
+                // A dot continues a number only if a digit follows, since `1..2` is a range.
+                || (c == '.' && rest[i + 1..].starts_with(|c: char| c.is_ascii_digit()));
+            if !continues { break; }
+            len = i + c.len_utf8();
+        }
+        return len;
+    }
+
+    fn classify_word(&self, word: &str, len: usize, syntax: &Syntax) -> Option<TokenKind> {
+        if syntax.keywords.contains(&word) { return Some(TokenKind::Keyword); }
+        if syntax.constants.contains(&word) { return Some(TokenKind::Constant); }
+        if syntax.types.contains(&word) { return Some(TokenKind::Type); }
+        let next = self.rest()[len..].chars().next();
+        if (syntax.calls && next == Some('(')) || (syntax.macros && next == Some('!')) {
+            return Some(TokenKind::Function);
+        }
+        if syntax.capitalized_types && word.starts_with(|c: char| c.is_uppercase()) {
+            return Some(TokenKind::Type);
+        }
+        return None;
+    }
+
+    /// Lexes the table header or key beginning the TOML line at the cursor. Returns false
+    /// if the line begins with neither.
+    fn lex_toml_line_begin(&mut self) -> bool {
+        let line = &self.rest()[..self.line_len()];
+        let indent = line.len() - line.trim_start().len();
+        let content = line.trim_start();
+        if content.starts_with('[') {
+            let len = content.find(']').map(|i| i + 1).unwrap_or(content.len());
+            let len = len + content[len..].starts_with(']') as usize;
+            self.pos += indent;
+            self.push(TokenKind::Heading, len);
+            return true;
+        }
+        let Some(equals) = content.find('=') else { return false; };
+        let key = content[..equals].trim_end();
+        let is_key = key.len() > 0 && key.chars()
+            .all(|c| is_word_char(c) || "-.\"' ".contains(c));
+        if !is_key { return false; }
+        self.pos += indent;
+        self.push(TokenKind::Key, key.len());
+        return true;
+    }
+
+    /// Lexes Merry source text. Headings and directive invocations span their whole line, while
+    /// verbatims and trailing qualifiers are recognized within other lines.
+    fn lex_merry(&mut self) {
+        let mut fence: Option<&str> = None;
+        while !self.is_end() {
+            let line = &self.rest()[..self.line_len()];
+            let content = line.trim_start();
+            let indent = line.len() - content.len();
+
+            // Within a verbatim block, every line is a part of the verbatim, up to and
+            // including the closing fence.
+            if let Some(open) = fence {
+                if content.starts_with(open) && !content[open.len()..].starts_with('`') {
+                    self.push(TokenKind::String, indent + open.len());
+                    self.lex_merry_inline_rest();
+                    fence = None;
+                } else {
+                    self.push(TokenKind::String, line.len());
+                }
+                self.skip_linebreak();
+                continue;
+            }
+            if content.starts_with("```") {
+                let open = &content[..content.len() - content.trim_start_matches('`').len()];
+                fence = Some(open);
+                self.pos += indent;
+                self.push(TokenKind::String, line.len() - indent);
+                self.skip_linebreak();
+                continue;
+            }
+            self.pos += indent;
+            if content.starts_with('#') {
+                self.push(TokenKind::Heading, content.len());
+            } else if content.starts_with('|') {
+                self.push(TokenKind::Directive, content.len());
+            } else {
+                if content.starts_with("--") || content.starts_with("::") {
+                    self.push(TokenKind::Keyword, 2);
+                }
+                self.lex_merry_inline_rest();
+            }
+            self.skip_linebreak();
+        }
+    }
+
+    /// Lexes the inline verbatims and trailing qualifiers remaining on the current line.
+    fn lex_merry_inline_rest(&mut self) {
+        loop {
+            let line = &self.rest()[..self.line_len()];
+            if line.len() == 0 { return; }
+            if line.starts_with('`') {
+                let ticks = line.len() - line.trim_start_matches('`').len();
+                let len = line[ticks..].find(&line[..ticks])
+                    .map(|i| ticks + i + ticks)
+                    .unwrap_or(line.len());
+                self.push(TokenKind::String, len);
+                continue;
+            }
+            if line.starts_with('{') {
+                let len = line.find('}').map(|i| i + 1).unwrap_or(line.len());
+                self.push(TokenKind::Tag, len);
+                continue;
+            }
+            self.skip();
+        }
+    }
+
+    fn skip_linebreak(&mut self) {
+        if self.rest().starts_with('\n') { self.pos += 1; }
+    }
+}
+
+fn is_word_char(c: char) -> bool { c.is_alphanumeric() || c == '_' }
+
+fn word_len(text: &str) -> usize {
+    return text.find(|c: char| !is_word_char(c)).unwrap_or(text.len());
+}
+
+/// Returns the length of the Rust character literal at the beginning of `text`, or `None` if
+/// the quote begins a lifetime instead, like `'a`.
+fn char_literal_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    let (_, c) = chars.next()?;
+    if c == '\\' {
+        let close = text[2..].find('\'')?;
+        return Some(2 + close + 1);
+    }
+    let (i, close) = chars.next()?;
+    if close != '\'' { return None; }
+    return Some(i + 1);
+}
@@ -0,0 +1,566 @@
+//! This module converts CommonMark documents, including the common GitHub Flavored Markdown
+//! extensions, into idiomatic Merry source text. The output is formatted according to the
+//! conventions enforced by [`crate::ltree::verify_ltree`], so it compiles without warnings.
+//!
+//! The Markdown document is first parsed into a small intermediate tree, which is then written
+//! out as Merry source text.
+//!
+//! - Emphasis becomes `~italic~` and strong emphasis becomes `*bold*`.
+//! - Inline links and images become tagged spans, and an `href` directive for each distinct url
+//!   is placed at the end of the enclosing section. Links within headings are not preserved,
+//!   since a heading lies outside the scope of the directives in its own section.
+//! - Code blocks become `{m}` verbatims, and code spans become `{m}` inline verbatims.
+//! - Bulleted and numbered lists both become `--` lists, since Merry has no ordered lists.
+//! - Block quotes become fenced blocks rendered as `<blockquote>` using the `element` directive,
+//!   and GitHub alerts become admonitions.
+//! - Strikethrough text becomes a tagged span rendered as `<s>`.
+//! - Raw HTML and tables have no equivalent, so they are preserved verbatim.
+//! - Thematic breaks have no equivalent and are dropped.
+
+use std::ffi::OsStr;
+use std::path::Path;
+use pulldown_cmark::{BlockQuoteKind, Event, Options, Parser, Tag};
+use crate::misc::{ansi, longest_run_of};
+
+/// The column at which paragraphs are wrapped onto the next line.
+const WRAP_COLUMN: usize = 100;
+
+/// Stands in for a space which must not be used as a line break, for instance within an
+/// inline verbatim. It is replaced by an ordinary space after wrapping.
+const NO_BREAK_SPACE: char = '\u{1}';
+
+/// Stands in for an empty line within a verbatim block. Unlike a blank separator line, these
+/// lines must be indented along with the rest of the verbatim.
+const VERBATIM_BLANK_LINE: &str = "\u{2}";
+
+/// Converts the given Markdown document into Merry source text.
+pub fn import_markdown(source: &str) -> String {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | Options::ENABLE_GFM
+        | Options::ENABLE_TABLES | Options::ENABLE_DEFINITION_LIST;
+    let mut events = Parser::new_ext(source, options).into_offset_iter();
+    let blocks = parse_blocks(&mut events, source);
+    let mut writer = Writer::default();
+    return writer.write_document(&blocks);
+}
+
+/// Converts the Markdown file at `input_file` into a Merry source file at `output_file`.
+pub fn import_file(input_file: &Path, output_file: &Path) -> std::io::Result<()> {
+    let source_text = std::fs::read_to_string(input_file)?;
+    std::fs::write(output_file, import_markdown(&source_text))?;
+    println!("{}#{} imported {}\"{}\"{} as {}\"{}\"{}", ansi::BOLD, ansi::STOP_BOLD,
+        ansi::FG_GREY, input_file.display(), ansi::FG_DEFAULT,
+        ansi::FG_GREY, output_file.display(), ansi::FG_DEFAULT);
+    return Ok(());
+}
+
+/// Converts every `.md` file within `src_dir_path`, and its subdirectories, into a `.md2` file
+/// at the same relative path within `dest_dir_path`.
+pub fn import_dir(src_dir_path: &Path, dest_dir_path: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dest_dir_path)?;
+    let mut entries = std::fs::read_dir(src_dir_path)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let dest_path = dest_dir_path.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            import_dir(&entry.path(), &dest_path)?;
+            continue;
+        }
+        if entry.path().extension() == Some(OsStr::new("md")) {
+            import_file(&entry.path(), &dest_path.with_extension("md2"))?;
+        }
+    }
+    return Ok(());
+}
+
+// # Intermediate Tree
+
+enum MdBlock {
+    Paragraph(Vec<MdInline>),
+    Heading(usize, Vec<MdInline>),
+    List(Vec<MdListItem>),
+    DefinitionList(Vec<MdDefinition>),
+    /// A block of text which is interpreted literally. When `is_code` is set, the text is
+    /// rendered in monospace.
+    Verbatim { text: String, is_code: bool },
+    /// A block quote, or a GitHub alert, which becomes a fenced block with the given tag.
+    Fenced { tag: &'static str, content: Vec<MdBlock> }
+}
+
+struct MdListItem { task: Option<bool>, content: Vec<MdBlock> }
+
+struct MdDefinition { term: Vec<MdInline>, content: Vec<MdBlock> }
+
+enum MdInline {
+    Text(String),
+    Code(String),
+    Emphasis(Vec<MdInline>),
+    Strong(Vec<MdInline>),
+    Strikethrough(Vec<MdInline>),
+    Link(String, Vec<MdInline>),
+    Space,
+    TaskMarker(bool)
+}
+
+type Events<'a> = pulldown_cmark::OffsetIter<'a, pulldown_cmark::DefaultBrokenLinkCallback>;
+
+/// Parses block nodes until the end of the enclosing tag, consuming that end tag.
+fn parse_blocks<'a>(events: &mut Events<'a>, source: &'a str) -> Vec<MdBlock> {
+    let mut blocks: Vec<MdBlock> = Vec::new();
+    // The items of a tight list contain inline nodes directly, without an enclosing paragraph.
+    let mut loose_inlines: Vec<MdInline> = Vec::new();
+    macro_rules! push_loose_paragraph { () => {
+        if loose_inlines.len() > 0 {
+            blocks.push(MdBlock::Paragraph(std::mem::take(&mut loose_inlines)));
+        }
+    }}
+
+    while let Some((event, range)) = events.next() {
+        let tag = match event {
+            Event::End(_) => break,
+            Event::Start(tag) => tag,
+            Event::Rule => { push_loose_paragraph!(); continue; },
+            Event::Html(html) => {
+                push_loose_paragraph!();
+                blocks.push(MdBlock::Verbatim { text: html.to_string(), is_code: false });
+                continue;
+            },
+            inline_event => {
+                push_inline_event(inline_event, events, &mut loose_inlines);
+                continue;
+            }
+        };
+        if is_inline_tag(&tag) {
+            push_inline_event(Event::Start(tag), events, &mut loose_inlines);
+            continue;
+        }
+        push_loose_paragraph!();
+        match tag {
+            Tag::Paragraph => blocks.push(MdBlock::Paragraph(parse_inlines(events))),
+            Tag::Heading { level, .. } =>
+                blocks.push(MdBlock::Heading(level as usize, parse_inlines(events))),
+            Tag::BlockQuote(kind) => {
+                let tag = match kind {
+                    None => "quote",
+                    Some(BlockQuoteKind::Note) | Some(BlockQuoteKind::Important) => "note",
+                    Some(BlockQuoteKind::Tip) => "tip",
+                    Some(BlockQuoteKind::Warning) => "warning",
+                    Some(BlockQuoteKind::Caution) => "danger",
+                };
+                blocks.push(MdBlock::Fenced { tag, content: parse_blocks(events, source) });
+            },
+            Tag::CodeBlock(_) =>
+                blocks.push(MdBlock::Verbatim { text: collect_text(events), is_code: true }),
+            Tag::HtmlBlock =>
+                blocks.push(MdBlock::Verbatim { text: collect_text(events), is_code: false }),
+            Tag::List(_) => blocks.push(MdBlock::List(parse_list_items(events, source))),
+            Tag::DefinitionList =>
+                blocks.push(MdBlock::DefinitionList(parse_definitions(events, source))),
+            Tag::Table(_) => {
+                skip_tag(events);
+                let text = source[range].trim_end().to_string();
+                blocks.push(MdBlock::Verbatim { text, is_code: true });
+            },
+            _ => blocks.extend(parse_blocks(events, source)),
+        }
+    }
+    push_loose_paragraph!();
+    return blocks;
+}
+
+fn parse_list_items<'a>(events: &mut Events<'a>, source: &'a str) -> Vec<MdListItem> {
+    let mut items: Vec<MdListItem> = Vec::new();
+    while let Some((event, _)) = events.next() {
+        match event {
+            Event::Start(Tag::Item) => {
+                let mut content = parse_blocks(events, source);
+                let task = take_task_marker(&mut content);
+                items.push(MdListItem { task, content });
+            },
+            Event::End(_) => break,
+            _ => continue
+        }
+    }
+    return items;
+}
+
+/// Removes the task marker from the beginning of a list item's content, if it has one.
+fn take_task_marker(content: &mut Vec<MdBlock>) -> Option<bool> {
+    let Some(MdBlock::Paragraph(inlines)) = content.first_mut() else { return None; };
+    let Some(MdInline::TaskMarker(checked)) = inlines.first() else { return None; };
+    let checked = *checked;
+    inlines.remove(0);
+    if inlines.len() == 0 { content.remove(0); }
+    return Some(checked);
+}
+
+fn parse_definitions<'a>(events: &mut Events<'a>, source: &'a str) -> Vec<MdDefinition> {
+    let mut definitions: Vec<MdDefinition> = Vec::new();
+    while let Some((event, _)) = events.next() {
+        match event {
+            Event::Start(Tag::DefinitionListTitle) => {
+                let term = parse_inlines(events);
+                definitions.push(MdDefinition { term, content: Vec::new() });
+            },
+            Event::Start(Tag::DefinitionListDefinition) => {
+                let content = parse_blocks(events, source);
+                match definitions.last_mut() {
+                    Some(definition) => definition.content.extend(content),
+                    None => definitions.push(MdDefinition { term: Vec::new(), content }),
+                }
+            },
+            Event::End(_) => break,
+            _ => continue
+        }
+    }
+    return definitions;
+}
+
+/// Parses inline nodes until the end of the enclosing tag, consuming that end tag.
+fn parse_inlines<'a>(events: &mut Events<'a>) -> Vec<MdInline> {
+    let mut inlines: Vec<MdInline> = Vec::new();
+    while let Some((event, _)) = events.next() {
+        if let Event::End(_) = event { break; }
+        push_inline_event(event, events, &mut inlines);
+    }
+    return inlines;
+}
+
+fn push_inline_event<'a>(event: Event<'a>, events: &mut Events<'a>, inlines: &mut Vec<MdInline>) {
+    let node = match event {
+        Event::Text(text) | Event::Html(text) | Event::InlineHtml(text)
+            | Event::InlineMath(text) | Event::DisplayMath(text) => {
+            // The parser splits text at characters which might have been syntax.
+            if let Some(MdInline::Text(prev)) = inlines.last_mut() {
+                prev.push_str(&text);
+                return;
+            }
+            MdInline::Text(text.to_string())
+        },
+        Event::Code(code) => MdInline::Code(code.to_string()),
+        Event::SoftBreak | Event::HardBreak => MdInline::Space,
+        Event::TaskListMarker(checked) => MdInline::TaskMarker(checked),
+        Event::FootnoteReference(name) => MdInline::Text(format!("[^{}]", name)),
+        Event::Start(Tag::Emphasis) => MdInline::Emphasis(parse_inlines(events)),
+        Event::Start(Tag::Strong) => MdInline::Strong(parse_inlines(events)),
+        Event::Start(Tag::Strikethrough) => MdInline::Strikethrough(parse_inlines(events)),
+        Event::Start(Tag::Link { dest_url, .. }) | Event::Start(Tag::Image { dest_url, .. }) =>
+            MdInline::Link(dest_url.to_string(), parse_inlines(events)),
+        Event::Start(_) => {
+            for node in parse_inlines(events) { inlines.push(node); }
+            return;
+        },
+        Event::End(_) | Event::Rule => return,
+    };
+    inlines.push(node);
+}
+
+fn is_inline_tag(tag: &Tag) -> bool {
+    return matches!(tag, Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript
+        | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. });
+}
+
+/// Concatenates the text within the enclosing tag, consuming that end tag.
+fn collect_text<'a>(events: &mut Events<'a>) -> String {
+    let mut text = String::new();
+    for (event, _) in events.by_ref() {
+        match event {
+            Event::End(_) => break,
+            Event::Text(t) | Event::Html(t) => text.push_str(&t),
+            _ => continue
+        }
+    }
+    return text;
+}
+
+/// Discards all events up to and including the end of the enclosing tag.
+fn skip_tag<'a>(events: &mut Events<'a>) {
+    let mut depth: usize = 0;
+    for (event, _) in events.by_ref() {
+        match event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) if depth == 0 => break,
+            Event::End(_) => depth -= 1,
+            _ => continue
+        }
+    }
+}
+
+// # Merry Source Text
+
+/// Writes the intermediate tree as Merry source text. Each block is written as a list of lines
+/// without indentation. The lines of nested blocks are indented by their parent.
+#[derive(Default)]
+struct Writer {
+    /// The urls linked to from within the current section. A link's tag is its index plus one.
+    hrefs: Vec<String>,
+    uses_quote: bool,
+    uses_strikethrough: bool,
+    /// The delimiters of the spans enclosing the inline node being written.
+    open_delims: Vec<char>
+}
+
+impl Writer {
+    fn write_document(&mut self, blocks: &[MdBlock]) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for block in blocks {
+            if let MdBlock::Heading(..) = block { self.write_section_directives(&mut lines); }
+            let block_lines = self.write_block(block, 0);
+            if block_lines.len() == 0 { continue; }
+            if lines.len() > 0 { lines.push(String::new()); }
+            lines.extend(block_lines);
+        }
+        self.write_section_directives(&mut lines);
+
+        let mut out = String::new();
+        for line in lines {
+            out.push_str(&line.replace(VERBATIM_BLANK_LINE, ""));
+            out.push('\n');
+        }
+        return out;
+    }
+
+    /// Writes the directives which give meaning to the tags used within the current section.
+    fn write_section_directives(&mut self, lines: &mut Vec<String>) {
+        let mut directives: Vec<String> = Vec::new();
+        for (i, href) in self.hrefs.drain(..).enumerate() {
+            directives.push(format!("| href {} {}", i + 1, href.replace(' ', "%20")));
+        }
+        if std::mem::take(&mut self.uses_quote) {
+            directives.push(String::from("| element quote blockquote"));
+        }
+        if std::mem::take(&mut self.uses_strikethrough) {
+            directives.push(String::from("| element strike s"));
+        }
+        if directives.len() == 0 { return; }
+        if lines.len() > 0 { lines.push(String::new()); }
+        lines.extend(directives);
+    }
+
+    fn write_blocks(&mut self, blocks: &[MdBlock], indent: usize) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for block in blocks {
+            let block_lines = self.write_block(block, indent);
+            if block_lines.len() == 0 { continue; }
+            if lines.len() > 0 { lines.push(String::new()); }
+            lines.extend(block_lines);
+        }
+        return lines;
+    }
+
+    fn write_block(&mut self, block: &MdBlock, indent: usize) -> Vec<String> {
+        match block {
+            MdBlock::Paragraph(inlines) => {
+                let mut text = String::new();
+                self.write_inlines(inlines, &mut text);
+                return wrap(&text, WRAP_COLUMN.saturating_sub(indent));
+            },
+            MdBlock::Heading(hlevel, inlines) => {
+                let mut text = "#".repeat(*hlevel);
+                text.push(' ');
+                self.write_inlines(&strip_links(inlines), &mut text);
+                return vec![text.replace(NO_BREAK_SPACE, " ")];
+            },
+            MdBlock::List(items) => {
+                let mut lines: Vec<String> = Vec::new();
+                for item in items {
+                    let mut prefix = String::from("-- ");
+                    match item.task {
+                        Some(true) => prefix.push_str("[x] "),
+                        Some(false) => prefix.push_str("[ ] "),
+                        None => {}
+                    }
+                    let content = self.write_blocks(&item.content, indent + 3);
+                    lines.extend(prefix_lines(content, prefix.trim_end(), &prefix, "   "));
+                }
+                return lines;
+            },
+            MdBlock::DefinitionList(definitions) => {
+                let mut lines: Vec<String> = Vec::new();
+                for definition in definitions {
+                    let mut term = String::from(":: ");
+                    self.write_inlines(&strip_links(&definition.term), &mut term);
+                    lines.push(term.replace(NO_BREAK_SPACE, " "));
+                    let content = self.write_blocks(&definition.content, indent + 3);
+                    lines.extend(prefix_lines(content, "", "   ", "   "));
+                }
+                return lines;
+            },
+            MdBlock::Verbatim { text, is_code } => {
+                let text = text.trim_end_matches('\n');
+                let longest_run = text.lines()
+                    .map(|line| line.trim_start().chars().take_while(|c| *c == '`').count())
+                    .max().unwrap_or(0);
+                let fence = "`".repeat(std::cmp::max(3, longest_run + 1));
+                let mut lines: Vec<String> = vec![fence.clone()];
+                for line in text.lines() {
+                    if line.trim().len() == 0 { lines.push(String::from(VERBATIM_BLANK_LINE)); }
+                    else { lines.push(line.to_string()); }
+                }
+                lines.push(if *is_code { format!("{}{{m}}", fence) } else { fence });
+                return lines;
+            },
+            MdBlock::Fenced { tag, content } => {
+                if *tag == "quote" { self.uses_quote = true; }
+                // A nested fenced block must have a shorter fence than its parent.
+                let fence = ":".repeat(3 + fence_height(content));
+                let mut lines: Vec<String> = vec![fence.clone()];
+                lines.extend(self.write_blocks(content, indent));
+                lines.push(format!("{}{{{}}}", fence, tag));
+                return lines;
+            }
+        }
+    }
+
+    fn write_inlines(&mut self, inlines: &[MdInline], out: &mut String) {
+        for inline in inlines {
+            match inline {
+                MdInline::Text(text) => write_text(text, out),
+                MdInline::Code(code) => {
+                    if code.len() == 0 { continue; }
+                    out.push_str(&inline_verbatim(code));
+                    out.push_str("{m}");
+                },
+                MdInline::Emphasis(children) => self.write_delimited('~', children, out),
+                MdInline::Strong(children) => self.write_delimited('*', children, out),
+                MdInline::Strikethrough(children) => {
+                    self.uses_strikethrough = true;
+                    out.push('[');
+                    self.write_inlines(children, out);
+                    out.push_str("]{strike}");
+                },
+                MdInline::Link(href, children) => {
+                    let tag = match self.hrefs.iter().position(|h| h == href) {
+                        Some(i) => i + 1,
+                        None => { self.hrefs.push(href.clone()); self.hrefs.len() }
+                    };
+                    out.push('[');
+                    if children.len() == 0 { write_text(href, out); }
+                    self.write_inlines(children, out);
+                    out.push_str(&format!("]{{{}}}", tag));
+                },
+                MdInline::Space => out.push(' '),
+                MdInline::TaskMarker(checked) => write_text(if *checked { "[x]" } else { "[ ]" }, out),
+            }
+        }
+    }
+
+    fn write_delimited(&mut self, delim: char, children: &[MdInline], out: &mut String) {
+        // A span can not be nested within another span of the same kind.
+        if self.open_delims.contains(&delim) {
+            self.write_inlines(children, out);
+            return;
+        }
+        self.open_delims.push(delim);
+        out.push(delim);
+        self.write_inlines(children, out);
+        out.push(delim);
+        self.open_delims.pop();
+    }
+}
+
+/// Writes the given plain text. Words containing symbols which would otherwise have a special
+/// meaning are written as inline verbatims.
+fn write_text(text: &str, out: &mut String) {
+    for (i, word) in text.split(' ').enumerate() {
+        if i > 0 { out.push(' '); }
+        if word.len() == 0 { continue; }
+        let is_paragraph_begin = out.len() == 0;
+        if word.contains(['*', '_', '~', '[', ']', '{', '}', '`'])
+            || (is_paragraph_begin && is_block_syntax(word))
+        {
+            out.push_str(&inline_verbatim(word));
+            continue;
+        }
+        out.push_str(word);
+    }
+}
+
+fn inline_verbatim(content: &str) -> String {
+    let fence = "`".repeat(longest_run_of(content, '`') + 1);
+    // A verbatim which begins or ends with a backtick must be padded to distinguish the
+    // backtick from the fence.
+    let pad = if content.starts_with('`') || content.ends_with('`') { " " } else { "" };
+    let content = content.replace(' ', &NO_BREAK_SPACE.to_string());
+    return format!("{}{}{}{}{}", fence, pad, content, pad, fence);
+}
+
+/// Determines if a line beginning with the given word would be interpreted as something other
+/// than a line of paragraph text.
+fn is_block_syntax(word: &str) -> bool {
+    return word.starts_with(['#', '|']) || word == "--" || word == "::"
+        || word.chars().all(|c| c == ':') || word.chars().all(|c| c == '<');
+}
+
+/// Greedily breaks the given paragraph text into lines no wider than `width`. A line is never
+/// broken before a word which would then be interpreted as something other than text.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split(' ').filter(|word| word.len() > 0) {
+        let word = word.replace(NO_BREAK_SPACE, " ");
+        let fits = line.chars().count() + 1 + word.chars().count() <= width;
+        if line.len() > 0 && (fits || is_block_syntax(&word)) {
+            line.push(' ');
+            line.push_str(&word);
+            continue;
+        }
+        if line.len() > 0 { lines.push(std::mem::take(&mut line)); }
+        line.push_str(&word);
+    }
+    if line.len() > 0 { lines.push(line); }
+    return lines;
+}
+
+/// Prefixes the first line with `first_prefix`, and all subsequent non-blank lines with
+/// `rest_prefix`. When there are no lines, a line containing only `empty_line` is produced.
+fn prefix_lines(lines: Vec<String>, empty_line: &str, first_prefix: &str, rest_prefix: &str)
+-> Vec<String>
+{
+    if lines.len() == 0 {
+        if empty_line.len() == 0 { return Vec::new(); }
+        return vec![empty_line.to_string()];
+    }
+    let mut prefixed: Vec<String> = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i == 0 { prefixed.push(format!("{}{}", first_prefix, line)); continue; }
+        if line.len() == 0 { prefixed.push(line); continue; }
+        prefixed.push(format!("{}{}", rest_prefix, line));
+    }
+    return prefixed;
+}
+
+/// Replaces every link in the given inline nodes with its text.
+fn strip_links(inlines: &[MdInline]) -> Vec<MdInline> {
+    let mut stripped: Vec<MdInline> = Vec::new();
+    for inline in inlines {
+        let node = match inline {
+            MdInline::Link(_, children) => { stripped.extend(strip_links(children)); continue; },
+            MdInline::Text(text) => MdInline::Text(text.clone()),
+            MdInline::Code(code) => MdInline::Code(code.clone()),
+            MdInline::Emphasis(children) => MdInline::Emphasis(strip_links(children)),
+            MdInline::Strong(children) => MdInline::Strong(strip_links(children)),
+            MdInline::Strikethrough(children) => MdInline::Strikethrough(strip_links(children)),
+            MdInline::Space => MdInline::Space,
+            MdInline::TaskMarker(checked) => MdInline::TaskMarker(*checked),
+        };
+        stripped.push(node);
+    }
+    return stripped;
+}
+
+/// Returns the number of fenced blocks nested within the given blocks.
+fn fence_height(blocks: &[MdBlock]) -> usize {
+    let mut height: usize = 0;
+    for block in blocks {
+        let block_height = match block {
+            MdBlock::Fenced { content, .. } => 1 + fence_height(content),
+            MdBlock::List(items) =>
+                items.iter().map(|item| fence_height(&item.content)).max().unwrap_or(0),
+            MdBlock::DefinitionList(definitions) =>
+                definitions.iter().map(|d| fence_height(&d.content)).max().unwrap_or(0),
+            _ => 0
+        };
+        height = std::cmp::max(height, block_height);
+    }
+    return height;
+}
@@ -1,11 +1,25 @@
 pub mod ltree;
 pub mod mtree;
 pub mod scan;
+pub mod source_map;
 pub mod misc;
 pub mod report;
 pub mod ttree;
 pub mod codegen_html;
+pub mod codegen_markdown;
 pub mod rewrite;
 pub mod builtins;
 pub mod ctree;
 pub mod compile;
+pub mod linkcheck;
+pub mod site;
+pub mod search;
+pub mod feed;
+pub mod highlight;
+pub mod assets;
+pub mod config;
+pub mod tasks;
+pub mod template;
+pub mod import;
+pub mod fmt;
+pub mod lsp;
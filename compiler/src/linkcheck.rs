@@ -0,0 +1,161 @@
+//! This module implements the link checker. Once every document in a directory has been compiled,
+//! each local hyperlink, that is a relative path or a fragment, is checked to lead to a file in the
+//! output directory, and, if it has a fragment, to a section within the finished document it leads
+//! to. No network requests are made. Absolute URLs are never checked.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use crate::ctree::{self, is_local_url, AnyInline};
+use crate::report::{Issue, AnnotatedSourceSection, Severity};
+use crate::rewrite::{rewrite_inline_root, rewrite_subtrees};
+use crate::scan::SourceSpan;
+
+pub struct LinkChecker<'a> {
+    /// The extension of the finished documents.
+    extension: &'static str,
+    /// The identifier of every section in each finished document, by path.
+    pages: HashMap<PathBuf, HashSet<String>>,
+    links: Vec<LocalLink<'a>>
+}
+
+struct LocalLink<'a> {
+    /// The URL argument of the `href` or `link` directive which created the link.
+    href: SourceSpan<'a>,
+    /// The path of the finished document containing the link.
+    page: PathBuf,
+    /// The URL as written to the finished document.
+    url: String
+}
+
+impl<'a> LinkChecker<'a> {
+    pub fn new(extension: &'static str) -> Self {
+        return LinkChecker { extension, pages: HashMap::new(), links: Vec::new() };
+    }
+
+    /// Records the sections and the local links of the finished document at `page`, which was
+    /// compiled from `root`.
+    pub fn add_page(&mut self, page: &Path, root: &mut ctree::Root<'a>) {
+        let page = normalize_path(page);
+        let mut section_ids: HashSet<String> = HashSet::new();
+        let mut hrefs: Vec<SourceSpan<'a>> = Vec::new();
+        rewrite_subtrees(&mut root.block, &mut |node| {
+            if let ctree::BlockChild::Section(section) = node {
+                section_ids.insert(section.id.clone());
+            }
+            for inline_content in node.inline_content_mut() {
+                rewrite_inline_root(inline_content, &mut |inline_node| {
+                    let AnyInline::Hyperlink(hyperlink) = inline_node else { return; };
+                    // Every span marked with the same tag shares the directive's URL.
+                    let is_recorded = hrefs.iter().any(|href| href.file == hyperlink.href.file
+                        && href.begin.byte_pos == hyperlink.href.begin.byte_pos);
+                    if is_recorded { return; }
+                    hrefs.push(hyperlink.href);
+                });
+            }
+        });
+        for href in hrefs {
+            if !is_local_url(href.as_ref()) { continue; }
+            let url = ctree::rewrite_source_url(href.as_ref(), self.extension);
+            self.links.push(LocalLink { href, page: page.clone(), url });
+        }
+        self.pages.insert(page, section_ids);
+    }
+
+    /// The number of local links recorded so far.
+    pub fn link_count(&self) -> usize { self.links.len() }
+
+    /// Returns an issue for every recorded link which leads nowhere. This should be called once
+    /// every document has been compiled, since a link may lead to any of them.
+    pub fn check(&self) -> Vec<Issue<'a>> {
+        let mut issues: Vec<Issue<'a>> = Vec::new();
+        for link in &self.links {
+            if let Some(problem) = self.find_link_problem(link) {
+                issues.push(broken_link_issue(link, problem));
+            }
+        }
+        return issues;
+    }
+
+    fn find_link_problem(&self, link: &LocalLink<'a>) -> Option<String> {
+        let (before_fragment, fragment) = match link.url.split_once('#') {
+            Some((before_fragment, fragment)) => (before_fragment, Some(fragment)),
+            None => (link.url.as_str(), None)
+        };
+        let path = before_fragment.split('?').next().unwrap_or("");
+        let path = percent_decode(path);
+        let mut target = match path.len() {
+            0 => link.page.clone(),
+            _ => normalize_path(&link.page.parent().unwrap_or(Path::new("")).join(&path))
+        };
+        // A web server answers a request for a directory with the index document inside it.
+        if path.ends_with('/') || target.is_dir() {
+            target = target.join(format!("index.{}", self.extension));
+        }
+        let Some(section_ids) = self.pages.get(&target) else {
+            // A file which was not compiled may still have been placed in the output directory.
+            if target.is_file() { return None; }
+            return Some(format!("There is no file at `{}` in the output directory.",
+                target.display()));
+        };
+        let fragment = fragment?;
+        if fragment.len() == 0 { return None; }
+        let fragment = percent_decode(fragment);
+        if section_ids.contains(&fragment) { return None; }
+        return Some(format!("There is no section with the identifier `{}` in `{}`.", fragment,
+            target.display()));
+    }
+}
+
+fn broken_link_issue<'a>(link: &LocalLink<'a>, problem: String) -> Issue<'a> {
+    let mut quote = AnnotatedSourceSection::from_span(&link.href);
+    quote.highlight(link.href.begin.byte_pos, link.href.end.byte_pos);
+    Issue {
+        quote,
+        title: format!("Link to `{}` is broken", link.url).into(),
+        subtext: format!("{}\nThe link appears in `{}`.", problem, link.page.display()).into(),
+        severity: Severity::Warning,
+        fix: None,
+        elaborations: Vec::new()
+    }
+}
+
+/// Resolves the `.` and `..` components of `path` without consulting the file system, so that
+/// paths to the same finished document compare equal before that document is written.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                match normalized.components().next_back() {
+                    Some(Component::Normal(_)) => { normalized.pop(); },
+                    Some(Component::RootDir) => (),
+                    _ => normalized.push(".."),
+                }
+            },
+            _ => normalized.push(component)
+        }
+    }
+    return normalized;
+}
+
+/// Decodes the percent-encoded bytes in a URL path or fragment, for instance `%20` for a space.
+/// Malformed escapes are left as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let digits = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(digits, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    return String::from_utf8_lossy(&decoded).into_owned();
+}
@@ -0,0 +1,593 @@
+//! This module implements the language server, `merryc lsp`. The server speaks the Language
+//! Server Protocol over stdin and stdout and provides...
+//!
+//! - Diagnostics for every issue found by the compiler.
+//! - A document outline built from the section tree.
+//! - Go-to-definition from a tag to the `href` or `rewrite` directive which consumes it.
+//! - Hover information showing the target of a hyperlink.
+//! - Completion of directive names, and of the tags named by the directives in scope.
+//! - Formatting, as done by `merryc fmt`.
+//!
+//! Documents are parsed from scratch for every request. The compiler is fast enough that
+//! caching the trees is not worth the trouble of their borrowed lifetimes.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as _, PublishDiagnostics};
+use lsp_types::request::{Completion, DocumentSymbolRequest, Formatting, GotoDefinition,
+    HoverRequest, Request as _};
+use lsp_types::{CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse,
+    Hover, HoverContents, HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf,
+    Position, PublishDiagnosticsParams, ServerCapabilities, SymbolKind, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Url};
+use crate::builtins::{BUILTIN_DIRECTIVES, BUILTIN_TAGS};
+use crate::config::{apply_lint_level, discover_config, parse_config};
+use crate::ctree::{make_ctree, Environment};
+use crate::fmt::format_source;
+use crate::ltree::{make_ltree, verify_ltree};
+use crate::mtree::{self, make_mtree, verify_mtree};
+use crate::report::{Issue, Severity};
+use crate::scan::SourceSpan;
+use crate::source_map::{FileId, SourceCache, SourceMap};
+use crate::ttree;
+
+/// Runs the language server until the client asks it to exit.
+pub fn run_server() -> std::io::Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![String::from("|"), String::from("{"), String::from(" ")]),
+            ..CompletionOptions::default()
+        }),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    };
+    let capabilities = serde_json::to_value(capabilities).map_err(std::io::Error::other)?;
+    connection.initialize(capabilities).map_err(std::io::Error::other)?;
+    serve(&connection)?;
+    std::mem::drop(connection);
+    io_threads.join()?;
+    return Ok(());
+}
+
+fn serve(connection: &Connection) -> std::io::Result<()> {
+    let mut documents: HashMap<Url, String> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request).map_err(std::io::Error::other)? {
+                    break;
+                }
+                let response = handle_request(&documents, request);
+                connection.sender.send(Message::Response(response)).map_err(std::io::Error::other)?;
+            },
+            Message::Notification(notification) => {
+                let Some(uri) = handle_notification(&mut documents, notification) else { continue; };
+                let diagnostics = match documents.get(&uri) {
+                    Some(source) => make_diagnostics(source, &uri),
+                    None => Vec::new()
+                };
+                let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+                let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                connection.sender.send(Message::Notification(notification))
+                    .map_err(std::io::Error::other)?;
+            },
+            Message::Response(_) => (),
+        }
+    }
+    return Ok(());
+}
+
+/// Updates the open `documents` as described by `notification`. Returns the URI of the
+/// document which changed, if any.
+fn handle_notification(documents: &mut HashMap<Url, String>, notification: Notification)
+-> Option<Url>
+{
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params: lsp_types::DidOpenTextDocumentParams =
+                serde_json::from_value(notification.params).ok()?;
+            documents.insert(params.text_document.uri.clone(), params.text_document.text);
+            return Some(params.text_document.uri);
+        },
+        DidChangeTextDocument::METHOD => {
+            let params: lsp_types::DidChangeTextDocumentParams =
+                serde_json::from_value(notification.params).ok()?;
+            // The server asks for full synchronization, so the last change is the entire text.
+            let change = params.content_changes.into_iter().last()?;
+            documents.insert(params.text_document.uri.clone(), change.text);
+            return Some(params.text_document.uri);
+        },
+        DidCloseTextDocument::METHOD => {
+            let params: lsp_types::DidCloseTextDocumentParams =
+                serde_json::from_value(notification.params).ok()?;
+            documents.remove(&params.text_document.uri);
+            return Some(params.text_document.uri);
+        },
+        _ => return None
+    }
+}
+
+fn handle_request(documents: &HashMap<Url, String>, request: Request) -> Response {
+    let document = |uri: &Url| documents.get(uri);
+    match request.method.as_str() {
+        DocumentSymbolRequest::METHOD => respond::<DocumentSymbolRequest>(request, |params| {
+            let source = document(&params.text_document.uri)?;
+            return Some(DocumentSymbolResponse::Nested(make_outline(source)));
+        }),
+        GotoDefinition::METHOD => respond::<GotoDefinition>(request, |params| {
+            let uri = params.text_document_position_params.text_document.uri;
+            let source = document(&uri)?;
+            let lines = LineIndex::new(source);
+            let offset = lines.offset(params.text_document_position_params.position);
+            let directive = find_consuming_directive(source, offset)?;
+            let range = lines.range(directive.range());
+            return Some(GotoDefinitionResponse::Scalar(Location { uri, range }));
+        }),
+        HoverRequest::METHOD => respond::<HoverRequest>(request, |params| {
+            let uri = params.text_document_position_params.text_document.uri;
+            let source = document(&uri)?;
+            let lines = LineIndex::new(source);
+            let offset = lines.offset(params.text_document_position_params.position);
+            let directive = find_consuming_directive(source, offset)?;
+            let text = match directive.cmd() {
+                "href" | "link" => format!("Links to <{}>", directive.args.get(2)?.as_ref()),
+                "ref" => format!("Refers to `{}`", directive.args.get(2)?.as_ref()),
+                _ => format!("Rewritten by `{}`", directive.args[2..].iter()
+                    .map(|arg| arg.as_ref()).collect::<Vec<&str>>().join(" ")),
+            };
+            let contents = HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown,
+                value: text });
+            return Some(Hover { contents, range: None });
+        }),
+        Completion::METHOD => respond::<Completion>(request, |params| {
+            let source = document(&params.text_document_position.text_document.uri)?;
+            let offset = LineIndex::new(source).offset(params.text_document_position.position);
+            return Some(CompletionResponse::Array(make_completions(source, offset)));
+        }),
+        Formatting::METHOD => respond::<Formatting>(request, |params| {
+            let source = document(&params.text_document.uri)?;
+            let formatted = format_source(source);
+            if &formatted == source { return Some(Vec::new()); }
+            let range = LineIndex::new(source).range(0..source.len());
+            return Some(vec![TextEdit { range, new_text: formatted }]);
+        }),
+        _ => {
+            let message = format!("{} is not supported", request.method);
+            return Response::new_err(request.id, ErrorCode::MethodNotFound as i32, message);
+        }
+    }
+}
+
+/// Deserializes the parameters of `request`, passes them to `handler`, and then packages
+/// the result into a response.
+fn respond<R>(request: Request, handler: impl FnOnce(R::Params) -> R::Result) -> Response
+where R: lsp_types::request::Request
+{
+    match serde_json::from_value::<R::Params>(request.params) {
+        Ok(params) => Response::new_ok(request.id, handler(params)),
+        Err(err) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, err.to_string()),
+    }
+}
+
+// # Diagnostics
+
+fn make_diagnostics(source: &str, uri: &Url) -> Vec<Diagnostic> {
+    let sources = SourceMap::new();
+    let path = uri.to_file_path().ok();
+    let file = match &path {
+        Some(path) => sources.add_path(path.clone()),
+        None => FileId::ANONYMOUS
+    };
+    // Problems with the configuration are not reported here, since it is not being edited.
+    let config = path.as_deref().and_then(discover_config)
+        .and_then(|config_path| {
+            let text = std::fs::read_to_string(&config_path).ok()?;
+            let (config_file, text) = sources.add(config_path.clone(), text);
+            return parse_config(text, config_file, &config_path, &sources, &mut Vec::new());
+        })
+        .unwrap_or_default();
+    let env = Environment { 
+        directives: config.directives.iter().cloned().collect(),
+        allowed_commands: config.allowed_commands.clone(),
+        ..Environment::default()
+    };
+    let ltree = make_ltree(source, file);
+    let mtree = make_mtree(&ltree);
+    let mut issues: Vec<Issue> = Vec::new();
+    for issue in verify_ltree(&ltree) { 
+        issues.extend(apply_lint_level(issue.lint_name(), issue.into(), &config.lints));
+    }
+    for issue in verify_mtree(&mtree) { 
+        issues.extend(apply_lint_level(issue.lint_name(), issue.into(), &config.lints));
+    }
+    // Directives which run external processes do so during code generation, so interpreting
+    // the document is safe to do on every keystroke.
+    let cwd = path
+        .and_then(|path| path.parent().map(|parent| parent.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."));
+    make_ctree(mtree, &mut issues, cwd, &SourceCache::new(&sources), &env);
+
+    let lines = LineIndex::new(source);
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for issue in issues {
+        // Issues within included files are reported when those files are opened themselves.
+        if issue.quote.file != file { continue; }
+        let severity = match issue.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        };
+        diagnostics.push(Diagnostic {
+            range: lines.range(issue.quote.focus()),
+            severity: Some(severity),
+            source: Some(String::from("merryc")),
+            message: format!("{}\n{}", issue.title, issue.subtext),
+            ..Diagnostic::default()
+        });
+    }
+    return diagnostics;
+}
+
+// # Outline
+
+#[allow(deprecated)] // `DocumentSymbol::deprecated` must be given even though it is deprecated.
+fn make_outline(source: &str) -> Vec<DocumentSymbol> {
+    let ltree = make_ltree(source, FileId::ANONYMOUS);
+    let mtree = make_mtree(&ltree);
+    let lines = LineIndex::new(source);
+
+    fn make_symbols(children: &[mtree::ast::BlockChild], end: usize, lines: &LineIndex)
+    -> Vec<DocumentSymbol>
+    {
+        let mut symbols: Vec<DocumentSymbol> = Vec::new();
+        for (i, child) in children.iter().enumerate() {
+            let mtree::ast::BlockChild::Section(section) = child else { continue; };
+            // A section extends until the next section which is not its descendant.
+            let section_end = children[(i + 1)..].iter()
+                .find_map(|sibling| match sibling {
+                    mtree::ast::BlockChild::Section(s) => Some(s.heading.span.begin.byte_pos),
+                    mtree::ast::BlockChild::ExplicitSectionClose(c) => Some(c.span.begin.byte_pos),
+                    _ => None
+                })
+                .unwrap_or(end);
+            let heading = &section.heading;
+            let mut name = heading.span.as_ref()[heading.pounds_span.as_ref().len()..].trim();
+            if name.len() == 0 { name = heading.pounds_span.as_ref(); }
+            let begin = heading.span.begin.byte_pos;
+            symbols.push(DocumentSymbol {
+                name: name.to_string(),
+                detail: None,
+                kind: SymbolKind::NAMESPACE,
+                tags: None,
+                deprecated: None,
+                range: lines.range(begin..section_end),
+                selection_range: lines.range(begin..heading.span.end.byte_pos),
+                children: Some(make_symbols(&section.children, section_end, lines))
+            });
+        }
+        return symbols;
+    }
+
+    return make_symbols(&mtree.block.children, source.len(), &lines);
+}
+
+// # Tags and Directives
+
+/// A directive invocation, along with its position in the container hierarchy.
+struct DirectiveEntry<'b> {
+    /// The command followed by its arguments.
+    args: Vec<SourceSpan<'b>>,
+    container: usize,
+    order: usize
+}
+
+impl<'b> DirectiveEntry<'b> {
+    fn cmd(&self) -> &str { self.args[0].as_ref() }
+
+    fn range(&self) -> Range<usize> {
+        return self.args[0].begin.byte_pos..self.args[self.args.len() - 1].end.byte_pos;
+    }
+}
+
+/// A node which is qualified with tags, along with its position in the container hierarchy.
+struct TaggedEntry<'b> {
+    tags: Vec<SourceSpan<'b>>,
+    range: Range<usize>,
+    container: usize,
+    order: usize
+}
+
+/// An index of the directives and tags in a document.
+///
+/// A directive applies to the nodes which precede it within its container. So every node is
+/// assigned an `order` according to its position in a pre-order traversal of the document,
+/// and every container is assigned an identifier. The root container is zero.
+#[derive(Default)]
+struct Index<'b> {
+    container_parents: Vec<Option<usize>>,
+    directives: Vec<DirectiveEntry<'b>>,
+    tagged: Vec<TaggedEntry<'b>>,
+    /// The byte position of the beginning of every indexed node, along with its container.
+    anchors: Vec<(usize, usize)>,
+    next_order: usize
+}
+
+impl<'b> Index<'b> {
+    fn new(root: &mtree::ast::Root<'b>) -> Self {
+        let mut index = Index::default();
+        index.container_parents.push(None);
+        index.index_children(&root.block.children, 0);
+        index.anchors.sort_by_key(|(begin, _)| *begin);
+        return index;
+    }
+
+    fn make_order(&mut self) -> usize {
+        self.next_order += 1;
+        return self.next_order;
+    }
+
+    fn make_container(&mut self, parent: usize) -> usize {
+        self.container_parents.push(Some(parent));
+        return self.container_parents.len() - 1;
+    }
+
+    fn index_children(&mut self, children: &[mtree::ast::BlockChild<'b>], container: usize) {
+        for child in children {
+            match child {
+                mtree::ast::BlockChild::Paragraph(paragraph) =>
+                    self.index_inline_root(&paragraph.content, container),
+                mtree::ast::BlockChild::Heading(heading) =>
+                    self.index_inline_root(&heading.content, container),
+                mtree::ast::BlockChild::Invoke(invocation) => {
+                    if invocation.args.len() == 0 { continue; }
+                    let order = self.make_order();
+                    self.anchors.push((invocation.args[0].begin.byte_pos, container));
+                    self.directives.push(DirectiveEntry { args: invocation.args.clone(),
+                        container, order });
+                },
+                mtree::ast::BlockChild::Block(block) => {
+                    let block_container = self.make_container(container);
+                    self.index_children(&block.children, block_container);
+                },
+                mtree::ast::BlockChild::List(list) => {
+                    for element in &list.elements {
+                        let element_container = self.make_container(container);
+                        self.index_children(&element.content.children, element_container);
+                    }
+                },
+                mtree::ast::BlockChild::DefinitionList(list) => {
+                    for definition in &list.definitions {
+                        self.index_inline_root(&definition.term, container);
+                        let definition_container = self.make_container(container);
+                        self.index_children(&definition.content.children, definition_container);
+                    }
+                },
+                mtree::ast::BlockChild::VerbatimBlock(verbatim) => {
+                    let Some(qualifier) = &verbatim.trailing_qualifier else { continue; };
+                    let begin = verbatim.lines.first().map(|line| line.begin.byte_pos)
+                        .unwrap_or(qualifier.open.begin.byte_pos);
+                    self.push_tagged(qualifier, begin, container);
+                },
+                mtree::ast::BlockChild::Section(section) => {
+                    // The heading of a section belongs to the enclosing container.
+                    self.index_inline_root(&section.heading.content, container);
+                    let section_container = self.make_container(container);
+                    self.index_children(&section.children, section_container);
+                },
+                mtree::ast::BlockChild::FencedBlock(fenced) => {
+                    let fenced_container = self.make_container(container);
+                    self.index_children(&fenced.content.children, fenced_container);
+                    let Some(qualifier) = &fenced.trailing_qualifier else { continue; };
+                    self.push_tagged(qualifier, qualifier.open.begin.byte_pos, container);
+                },
+                mtree::ast::BlockChild::ExplicitSectionClose(_) => (),
+            }
+        }
+    }
+
+    fn index_inline_root(&mut self, root: &ttree::ast::Root<'b>, container: usize) {
+        for child in &root.children {
+            match child {
+                ttree::ast::AnyInline::Plain(plain) =>
+                    self.anchors.push((plain.span.begin.byte_pos, container)),
+                ttree::ast::AnyInline::Delimited(delimited) =>
+                    self.index_inline_root(&delimited.child_root, container),
+                ttree::ast::AnyInline::Bracketed(bracketed) => {
+                    self.index_inline_root(&bracketed.child_root, container);
+                    let Some(qualifier) = &bracketed.trailing_qualifier else { continue; };
+                    self.push_tagged(qualifier, bracketed.open.begin.byte_pos, container);
+                },
+                ttree::ast::AnyInline::InlineVerbatim(verbatim) => {
+                    let Some(qualifier) = &verbatim.trailing_qualifier else { continue; };
+                    self.push_tagged(qualifier, verbatim.open.begin.byte_pos, container);
+                },
+                ttree::ast::AnyInline::ImplicitSpace(_) => (),
+            }
+        }
+    }
+
+    fn push_tagged(&mut self, qualifier: &ttree::ast::TrailingQualifier<'b>, begin: usize,
+        container: usize)
+    {
+        let mut tags: Vec<SourceSpan<'b>> = Vec::new();
+        for tag in &qualifier.tags {
+            if let ttree::ast::Tag::Unsplit(tag) = tag { tags.push(tag.span); }
+        }
+        let end = qualifier.close.map(|close| close.end.byte_pos)
+            .or(tags.last().map(|tag| tag.end.byte_pos))
+            .unwrap_or(qualifier.open.end.byte_pos);
+        let order = self.make_order();
+        self.anchors.push((begin, container));
+        self.tagged.push(TaggedEntry { tags, range: begin..end, container, order });
+    }
+
+    /// Returns the innermost tagged node containing `offset`.
+    fn tagged_at(&self, offset: usize) -> Option<&TaggedEntry<'b>> {
+        return self.tagged.iter()
+            .filter(|entry| entry.range.start <= offset && offset <= entry.range.end)
+            .min_by_key(|entry| entry.range.len());
+    }
+
+    /// Returns the `href`, `rewrite`, `ref`, or `link` directive which consumes `tag` on the given node.
+    /// Directives in the node's own container act first, then those in its ancestors, and
+    /// finally the document-global `link` directives.
+    fn find_consumer(&self, entry: &TaggedEntry<'b>, tag: &str) -> Option<&DirectiveEntry<'b>> {
+        let mut container = Some(entry.container);
+        while let Some(current) = container {
+            let consumer = self.directives.iter()
+                .filter(|directive| directive.container == current && directive.order > entry.order)
+                .filter(|directive| matches!(directive.cmd(), "href" | "rewrite" | "ref"))
+                .find(|directive| directive.args.get(1).is_some_and(|arg| arg.as_ref() == tag));
+            if consumer.is_some() { return consumer; }
+            container = self.container_parents[current];
+        }
+        return self.directives.iter()
+            .filter(|directive| directive.cmd() == "link")
+            .find(|directive| directive.args.get(1).is_some_and(|arg| arg.as_ref() == tag));
+    }
+
+    /// Returns the containers enclosing `offset`, innermost first.
+    fn containers_at(&self, offset: usize) -> Vec<usize> {
+        let mut containers: Vec<usize> = Vec::new();
+        let i = self.anchors.partition_point(|(begin, _)| *begin <= offset);
+        let mut container = match i {
+            0 => Some(0),
+            _ => Some(self.anchors[i - 1].1)
+        };
+        while let Some(current) = container {
+            containers.push(current);
+            container = self.container_parents[current];
+        }
+        return containers;
+    }
+}
+
+/// Returns the directive which consumes the tag at `offset`. If `offset` is within a tagged node
+/// but not on any one of its tags, then the directive consuming the first consumed tag is returned.
+fn find_consuming_directive<'b>(source: &'b str, offset: usize) -> Option<DirectiveEntry<'b>> {
+    let ltree = make_ltree(source, FileId::ANONYMOUS);
+    let mtree = make_mtree(&ltree);
+    let index = Index::new(&mtree);
+    let entry = index.tagged_at(offset)?;
+    let tag_at_offset = entry.tags.iter()
+        .find(|tag| tag.begin.byte_pos <= offset && offset <= tag.end.byte_pos);
+    let consumer = match tag_at_offset {
+        Some(tag) => index.find_consumer(entry, tag.as_ref()),
+        None => entry.tags.iter().find_map(|tag| index.find_consumer(entry, tag.as_ref()))
+    }?;
+    return Some(DirectiveEntry { args: consumer.args.clone(), container: consumer.container,
+        order: consumer.order });
+}
+
+// # Completion
+
+fn make_completions(source: &str, offset: usize) -> Vec<CompletionItem> {
+    let line_begin = source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_prefix = &source[line_begin..offset];
+    let ltree = make_ltree(source, FileId::ANONYMOUS);
+    let mtree = make_mtree(&ltree);
+    let index = Index::new(&mtree);
+    let mut items: Vec<CompletionItem> = Vec::new();
+
+    // Within a directive invocation, complete the directive's name, or the tag it acts upon.
+    if let Some(invocation) = line_prefix.trim_start().strip_prefix('|') {
+        let words: Vec<&str> = invocation.trim_start().split(' ').collect();
+        if words.len() == 1 {
+            for directive in BUILTIN_DIRECTIVES {
+                items.push(CompletionItem { label: directive.name.to_string(),
+                    kind: Some(CompletionItemKind::KEYWORD),
+                    detail: Some(directive.usage.to_string()),
+                    ..CompletionItem::default() });
+            }
+        }
+        let is_tag_directive = BUILTIN_DIRECTIVES.iter()
+            .any(|directive| directive.is_tag_directive && directive.name == words[0]);
+        if words.len() == 2 && is_tag_directive {
+            let mut tags: Vec<&str> = index.tagged.iter()
+                .flat_map(|entry| entry.tags.iter().map(|tag| tag.as_ref()))
+                .collect();
+            tags.sort();
+            tags.dedup();
+            for tag in tags { items.push(tag_completion(tag, None)); }
+        }
+        return items;
+    }
+
+    // Within a trailing qualifier, complete the tags named by the directives in scope.
+    let Some(qualifier_begin) = line_prefix.rfind('{') else { return items; };
+    if line_prefix[qualifier_begin..].contains('}') { return items; }
+    let containers = index.containers_at(offset);
+    let mut labels: Vec<&str> = Vec::new();
+    for directive in &index.directives {
+        if !containers.contains(&directive.container) { continue; }
+        // A directive applies only to the nodes preceding it.
+        if directive.args[0].begin.byte_pos < offset { continue; }
+        let is_tag_directive = BUILTIN_DIRECTIVES.iter()
+            .any(|usage| usage.is_tag_directive && usage.name == directive.cmd());
+        if !is_tag_directive { continue; }
+        let Some(tag) = directive.args.get(1) else { continue; };
+        if labels.contains(&tag.as_ref()) { continue; }
+        labels.push(tag.as_ref());
+        let detail = directive.args.iter().map(|arg| arg.as_ref()).collect::<Vec<&str>>().join(" ");
+        items.push(tag_completion(tag.as_ref(), Some(detail)));
+    }
+    for tag in BUILTIN_TAGS {
+        if labels.contains(tag) { continue; }
+        items.push(tag_completion(tag, Some(String::from("builtin"))));
+    }
+    return items;
+}
+
+fn tag_completion(tag: &str, detail: Option<String>) -> CompletionItem {
+    return CompletionItem { label: tag.to_string(), kind: Some(CompletionItemKind::CONSTANT),
+        detail, ..CompletionItem::default() };
+}
+
+// # Positions
+
+/// Converts between byte positions in the source text and LSP positions, which count
+/// lines and UTF-16 code units.
+struct LineIndex<'a> {
+    source: &'a str,
+    line_begins: Vec<usize>
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(source: &'a str) -> Self {
+        let mut line_begins: Vec<usize> = vec![0];
+        for (i, byte) in source.bytes().enumerate() {
+            if byte == b'\n' { line_begins.push(i + 1); }
+        }
+        return LineIndex { source, line_begins };
+    }
+
+    fn position(&self, byte_pos: usize) -> Position {
+        let line = self.line_begins.partition_point(|begin| *begin <= byte_pos) - 1;
+        let character = self.source[self.line_begins[line]..byte_pos].encode_utf16().count();
+        return Position { line: line as u32, character: character as u32 };
+    }
+
+    fn range(&self, range: Range<usize>) -> lsp_types::Range {
+        return lsp_types::Range { start: self.position(range.start), end: self.position(range.end) };
+    }
+
+    fn offset(&self, position: Position) -> usize {
+        let Some(line_begin) = self.line_begins.get(position.line as usize) else {
+            return self.source.len();
+        };
+        let mut utf16_count: usize = 0;
+        for (i, c) in self.source[*line_begin..].char_indices() {
+            if utf16_count >= position.character as usize || c == '\n' { return line_begin + i; }
+            utf16_count += c.len_utf16();
+        }
+        return self.source.len();
+    }
+}
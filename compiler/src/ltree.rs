@@ -21,6 +21,15 @@
 //!   terminated when the *block* it contains is terminated.
 //! - Consecutive *list elements* in the same *block* are grouped into a list
 //!   (represented by [`Ast::List`]).
+//! - A *n*-indented line starting with a *definition declarator* begins a new *definition*
+//!   (represented by [`ast::Definition`]). The remainder of the line is the defined term, and
+//!   the *block* beginning on the subsequent line, which should be indented +3, is its definition.
+//!   Consecutive *definitions* are grouped into a definition list (represented by
+//!   [`ast::DefinitionList`]).
+//! - A *n*-indented line consisting solely of three or more colons opens a *fenced block*
+//!   (represented by [`ast::FencedBlock`]). The *fenced block* contains a complete *block*,
+//!   with the same indentation as the fence, and is terminated by a subsequent *n*-indented
+//!   line beginning with at least as many colons. 
 //!
 //! Beyond this hierarchy, there is one final and important property of the *LTree*.
 //! That is, recoverability. Given an unmodified *LTree*, the source text can be reproduced 
@@ -49,7 +58,9 @@ pub mod ast {
         Line(Line<'a>),
         VerticalSpace(VerticalSpace<'a>),
         Block(Block<'a>),
-        Verbatim(Verbatim<'a>)
+        Verbatim(Verbatim<'a>),
+        FencedBlock(FencedBlock<'a>),
+        DefinitionList(DefinitionList<'a>)
     }
 
     #[derive(Debug, Default)]
@@ -62,6 +73,17 @@ pub mod ast {
         pub content: Block<'a>
     }
 
+    #[derive(Debug, Default)]
+    pub struct DefinitionList<'a> {
+        pub children: Vec<Definition<'a>>
+    }
+
+    #[derive(Debug)]
+    pub struct Definition<'a> {
+        pub term: SourceSpan<'a>,
+        pub content: Block<'a>
+    }
+
     #[derive(Debug)]
     pub struct Line<'a> { 
         pub indent_span: SourceSpan<'a>,
@@ -79,15 +101,28 @@ pub mod ast {
         pub tail: Option<SourceSpan<'a>>,
         pub open: SourceSpan<'a>
     }
+
+    #[derive(Debug)]
+    pub struct FencedBlock<'a> {
+        pub open: SourceSpan<'a>,
+        pub content: Block<'a>,
+        pub close: Option<SourceSpan<'a>>,
+        /// The tail of a FencedBlock is the remainder left of the last line after
+        /// the closing fence is consumed.
+        pub tail: Option<SourceSpan<'a>>,
+        pub span: SourceSpan<'a>,
+        pub indent: usize
+    }
 }
 
 use crate::scan::{ForwardCursor, SourceSpan};
+use crate::source_map::FileId;
 use crate::scanner;
 
 /// Constructs an *LTree* from the entirety of the given source text. The module-level 
 /// documentation contains an explanation of *LTree*.
-pub fn make_ltree<'a>(source: &'a String) -> ast::Root<'a> {
-    let mut ctx = ParseContext { cursor: ForwardCursor::new(source) };
+pub fn make_ltree<'a>(source: &'a str, file: FileId) -> ast::Root<'a> {
+    let mut ctx = ParseContext { cursor: ForwardCursor::new(source, file), fences: Vec::new() };
     let block = parse_block(&mut ctx, 0, 0).node;
     return ast::Root { block };
 }
@@ -95,7 +130,11 @@ pub fn make_ltree<'a>(source: &'a String) -> ast::Root<'a> {
 
 // *LTree* Praser
 
-pub struct ParseContext<'a> { cursor: ForwardCursor<'a> }
+pub struct ParseContext<'a> { 
+    cursor: ForwardCursor<'a>,
+    /// The indent and colon count of every fence enclosing the cursor, innermost last.
+    fences: Vec<(usize, usize)>
+}
 
 #[derive(Debug)]
 enum TreeDestin { Root, Parent }
@@ -142,6 +181,11 @@ fn parse_block<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize, depth: usiz
             if consec_blank_line_count == 2 { break TreeDestin::Parent; }
         }
         if ctx.cursor.is_end() { break TreeDestin::Root; }
+        if let Some(&(fence_indent, fence_len)) = ctx.fences.last() {
+            if fence_indent == indent && ctx.cursor.at_scan(fence_close(indent, fence_len)).is_some() {
+                break TreeDestin::Parent;
+            }
+        }
         if let Some(decl) = ctx.cursor.at_scan(nested_block_decl(indent)) {
             use_result!(parse_block(ctx, decl.end.colu_pos, depth + 1),
                  |child| children.push(ast::BlockChild::Block(child)));
@@ -150,10 +194,18 @@ fn parse_block<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize, depth: usiz
             use_result!(parse_list(ctx, indent, depth), 
                 |list| children.push(ast::BlockChild::List(list)));
         }
+        if ctx.cursor.at_scan(definition_decl(indent)).is_some() {
+            use_result!(parse_definition_list(ctx, indent, depth), 
+                |list| children.push(ast::BlockChild::DefinitionList(list)));
+        }
         if ctx.cursor.at_scan(verbatim_decl(indent)).is_some() {
             children.push(ast::BlockChild::Verbatim(parse_verbatim(ctx, indent)));
             continue;
         }
+        if ctx.cursor.at_scan(fence_open(indent)).is_some() {
+            use_result!(parse_fenced_block(ctx, indent, depth),
+                |fenced| children.push(ast::BlockChild::FencedBlock(fenced)));
+        }
         if let Some(indent_span) = ctx.cursor.match_scan(block_continuation(indent)) {
             let line_content = ctx.cursor.pop_line();
             children.push(ast::BlockChild::Line(ast::Line { line_content, indent_span }));
@@ -162,7 +214,7 @@ fn parse_block<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize, depth: usiz
         break TreeDestin::Parent;    
     };
     let end = ctx.cursor.pos();
-    let block_span: SourceSpan<'b> = SourceSpan { source: ctx.cursor.source, begin, end };
+    let block_span: SourceSpan<'b> = SourceSpan { source: ctx.cursor.source, file: ctx.cursor.file, begin, end };
     let node: ast::Block<'b> = ast::Block { children, indent, span: block_span };
     return ParseResult { destin, node };
 }
@@ -185,6 +237,61 @@ fn parse_list<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize, depth: usize
     return ParseResult {destin, node };
 }
 
+/// Advances the cursor past the next *definition list* and assembles an [`ast::DefinitionList`]
+/// to represent the content.
+/// 
+/// This procedure will *never* return in the middle of a line. In other words, the caller can
+/// assume that the cursor is placed at the beginning of a subsequent line (or EOF) after
+/// `parse` returns.
+fn parse_definition_list<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize, depth: usize) 
+-> ParseResult<ast::DefinitionList<'b>>
+{
+    let mut node: ast::DefinitionList<'b> = <ast::DefinitionList as Default>::default();
+    let destin: TreeDestin = loop {
+        if !ctx.cursor.match_scan(definition_decl(indent)).is_some() { break TreeDestin::Parent; }
+        let term = ctx.cursor.pop_line();
+        // The definition is parsed at whatever indent it actually has, so that an improperly
+        // indented definition is still attached to its term. The indent is verified later.
+        let content_indent = ctx.cursor.at_scan(nested_block_decl(indent))
+            .map(|decl| decl.end.colu_pos)
+            .unwrap_or(indent + 3);
+        use_result!(parse_block(ctx, content_indent, depth + 1), 
+            |content| node.children.push(ast::Definition { term, content }));
+    };
+    return ParseResult { destin, node };
+}
+
+/// Advances the cursor past the next *fenced block* and assembles an [`ast::FencedBlock`] to
+/// represent the content.
+/// 
+/// This procedure will *never* return in the middle of a line. In other words, the caller can
+/// assume that the cursor is placed at the beginning of a subsequent line (or EOF) after
+/// `parse` returns.
+fn parse_fenced_block<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize, depth: usize)
+-> ParseResult<ast::FencedBlock<'b>>
+{
+    ctx.cursor.pop_spaces();
+    let begin = ctx.cursor.pos();
+    let open = ctx.cursor.match_scan(fence_open(indent)).unwrap();
+    assert!(ctx.cursor.match_linebreak());
+    let fence_len = open.as_ref().len();
+    ctx.fences.push((indent, fence_len));
+    let ParseResult { mut destin, node: content } = parse_block(ctx, indent, depth + 1);
+    ctx.fences.pop();
+    let mut close: Option<SourceSpan<'b>> = None;
+    let mut tail: Option<SourceSpan<'b>> = None;
+    if ctx.cursor.at_scan(fence_close(indent, fence_len)).is_some() {
+        ctx.cursor.pop_spaces();
+        close = ctx.cursor.match_scan(fence_symbol());
+        tail = Some(ctx.cursor.pop_line());
+        destin = TreeDestin::Parent;
+    }
+    let end = ctx.cursor.pos();
+    let span = SourceSpan { source: ctx.cursor.source, file: ctx.cursor.file, begin, end };
+    let node = ast::FencedBlock { open, content, close, tail, span, indent };
+    return ParseResult { destin, node };
+}
+
 // Token Scanners
 
 scanner! { 
@@ -195,6 +302,14 @@ scanner! {
     }
 }
 
+scanner! { 
+    definition_decl (indent: usize) |cursor| {
+        cursor.pop_spaces();
+        if cursor.pos().colu_pos != indent { return false }
+        cursor.match_symbol(":: ").is_some()
+    }
+}
+
 scanner! {
     blank_line () |cursor| {
         cursor.pop_spaces();
@@ -239,6 +354,29 @@ scanner! {
     }
 }
 
+scanner! {
+    fence_symbol () |cursor| {
+        cursor.repeat_match_symbol(":") >= 3
+    }
+}
+
+scanner! {
+    fence_open (indent: usize) |cursor| {
+        cursor.pop_spaces();
+        if cursor.pos().colu_pos != indent { return false }
+        if cursor.repeat_match_symbol(":") < 3 { return false; }
+        cursor.at_symbol("\n")
+    }
+}
+
+scanner! {
+    fence_close (indent: usize, expect_colon_count: usize) |cursor| {
+        cursor.pop_spaces();
+        if cursor.pos().colu_pos != indent { return false }
+        cursor.repeat_match_symbol(":") >= expect_colon_count
+    }
+}
+
 fn parse_verbatim<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize) -> ast::Verbatim<'b> {
     assert_eq!(ctx.cursor.pop_spaces().end.colu_pos, indent);
     let begin = ctx.cursor.pos();
@@ -267,7 +405,7 @@ fn parse_verbatim<'a, 'b>(ctx: &'a mut ParseContext<'b>, indent: usize) -> ast::
         lines.push(ctx.cursor.pop_line());
     }
     let end = ctx.cursor.pos();
-    let span = SourceSpan { source: ctx.cursor.source, begin, end };
+    let span = SourceSpan { source: ctx.cursor.source, file: ctx.cursor.file, begin, end };
     return ast::Verbatim { span, lines, close, indent, tail, open };
 }
 
@@ -302,7 +440,9 @@ pub enum AnyLTreeIssue<'a, 'b> {
 
     VerbatimUnderindented(VerbatimUnderindentedWarning<'a, 'b>),
 
-    LongVerbatimCloseWarning(LongVerbatimCloseWarning<'a, 'b>)
+    LongVerbatimCloseWarning(LongVerbatimCloseWarning<'a, 'b>),
+
+    UnclosedFencedBlock(UnclosedFencedBlockError<'a, 'b>)
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -320,7 +460,9 @@ pub struct ExcessiveIndentWarning<'a, 'b> {
 #[derive(Clone, Copy, Debug)]
 pub struct ExcessiveVerticalSpaceWarning<'a> {
     pub span: SourceSpan<'a>,
-    pub limit: usize
+    pub limit: usize,
+    /// Whether the blank lines belong to a nested block, where three of them end the document.
+    pub is_nested: bool
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -338,6 +480,11 @@ pub struct VerbatimUnderindentedWarning<'a, 'b> {
     verbatim: &'a ast::Verbatim<'b>
 }
 
+#[derive(Clone, Copy, Debug)]
+pub struct UnclosedFencedBlockError<'a, 'b> {
+    node: &'a ast::FencedBlock<'b>
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct LongVerbatimCloseWarning<'a, 'b> { 
     node: &'a ast::Verbatim<'b>,
@@ -346,7 +493,7 @@ pub struct LongVerbatimCloseWarning<'a, 'b> {
 
 pub fn verify_ltree<'a, 'b>(root: &'a ast::Root<'b>) -> Vec<AnyLTreeIssue<'a, 'b>> {
     let mut report: Vec<AnyLTreeIssue<'a, 'b>> = Vec::new();
-    verify_seperation(&root.block, &mut report, false);
+    verify_seperation(&root.block, &mut report, false, false);
     verify_block(&root.block, &mut report, 0);  
     return report;
 }
@@ -356,6 +503,16 @@ fn verify_block<'a, 'b>(block: &'a ast::Block<'b>, report: &mut Vec<AnyLTreeIssu
 {
     verify_block_indent(block, report, expect_indent);
 
+    // The separation of each child block is reported before the problems within it. A fix which
+    // separates the child block must be applied first, since a fix which reindents it could
+    // otherwise merge it into this block.
+    for (i, next) in block.children.iter().skip(1).enumerate() {
+        let ast::BlockChild::Block(ref next_block) = next else { continue };
+        if matches!(tail(&block.children[i]), ast::BlockChild::VerticalSpace(_)) { continue; }
+        let acb_warning = AbruptChildBlockWarning { child_block: next_block };
+        report.push(AnyLTreeIssue::AbruptChildBlock(acb_warning));
+    }
+
     let mut prev_child: Option<&'a ast::BlockChild<'b>> = None;
     for child in &block.children {
         if let ast::BlockChild::Block(child_block) = child {
@@ -365,38 +522,40 @@ fn verify_block<'a, 'b>(block: &'a ast::Block<'b>, report: &mut Vec<AnyLTreeIssu
                 allow_double_break = 
                     tail_block(prev_block).indent == child_block.indent;
             }
-            verify_seperation(child_block, report, allow_double_break);
+            verify_seperation(child_block, report, allow_double_break, true);
         }
         if let ast::BlockChild::List(list) = child {
             for element in &list.children {
                 verify_block(&element.content, report, block.indent + 3);
             }
         }
+        if let ast::BlockChild::DefinitionList(list) = child {
+            for definition in &list.children {
+                verify_block(&definition.content, report, block.indent + 3);
+            }
+        }
         if let ast::BlockChild::Verbatim(verbatim) = child {
             verify_verbatim(verbatim, report);
         }
+        if let ast::BlockChild::FencedBlock(fenced) = child {
+            verify_fenced_block(fenced, report);
+        }
         prev_child = Some(child);
     }
-    
-    for (i, next) in block.children.iter().skip(1).enumerate() {
-        let ast::BlockChild::Block(ref next_block) = next else { continue };
-        if matches!(tail(&block.children[i]), ast::BlockChild::VerticalSpace(_)) { continue; }
-        let acb_warning = AbruptChildBlockWarning { child_block: next_block };
-        report.push(AnyLTreeIssue::AbruptChildBlock(acb_warning));
-    }
+
 }
 
 fn verify_seperation<'a, 'b>(block: &'a ast::Block<'b>, report: &mut Vec<AnyLTreeIssue<'a, 'b>>,
-    allow_double_break: bool) 
+    allow_double_break: bool, is_nested: bool) 
 {
     let mut vspace_bounds: Option<(&ast::VerticalSpace, &ast::VerticalSpace)> = None;
     macro_rules! push_vspace_error { ($tail_call:expr) => {
         if let Some((first, last)) = vspace_bounds.take() {
             let limit = if $tail_call && allow_double_break { 2 } else { 1 };
             if last.span.end.line_pos - first.span.begin.line_pos > limit {
-                let span = SourceSpan { source: first.span.source,
+                let span = SourceSpan { source: first.span.source, file: first.span.file,
                     begin: first.span.begin, end: last.span.begin };
-                let evs_warning = ExcessiveVerticalSpaceWarning { span, limit };
+                let evs_warning = ExcessiveVerticalSpaceWarning { span, limit, is_nested };
                 report.push(AnyLTreeIssue::ExcessiveVerticalSpace(evs_warning));
             }
          }
@@ -458,7 +617,35 @@ fn verify_verbatim<'a, 'b>(verbatim: &'a ast::Verbatim<'b>,
     }
 }
 
-use crate::report::{Issue, AnnotatedSourceSection, Severity, BarrierStyle};
+fn verify_fenced_block<'a, 'b>(node: &'a ast::FencedBlock<'b>, 
+    report: &mut Vec<AnyLTreeIssue<'a, 'b>>)
+{
+    verify_seperation(&node.content, report, false, true);
+    verify_block(&node.content, report, node.indent);
+    if node.close.is_none() {
+        let error = UnclosedFencedBlockError { node };
+        report.push(AnyLTreeIssue::UnclosedFencedBlock(error));
+    }
+}
+
+use crate::report::{Issue, AnnotatedSourceSection, Severity, BarrierStyle, Fix};
+
+impl<'a, 'b> AnyLTreeIssue<'a, 'b> {
+    /// Returns the name by which the severity of this issue is configured, or `None` if this
+    /// issue is an error, since the severity of errors cannot be configured.
+    pub fn lint_name(&self) -> Option<&'static str> {
+        match self {
+            AnyLTreeIssue::InsufficientIndent(_) => Some("insufficient-indent"),
+            AnyLTreeIssue::ExcessiveIndent(_) => Some("excessive-indent"),
+            AnyLTreeIssue::ExcessiveVerticalSpace(_) => Some("excessive-vertical-space"),
+            AnyLTreeIssue::AbruptChildBlock(_) => Some("abrupt-child-block"),
+            AnyLTreeIssue::UnclosedVerbatim(_) => None,
+            AnyLTreeIssue::VerbatimUnderindented(_) => Some("verbatim-underindented"),
+            AnyLTreeIssue::LongVerbatimCloseWarning(_) => Some("long-verbatim-close"),
+            AnyLTreeIssue::UnclosedFencedBlock(_) => None,
+        }
+    }
+}
 
 impl<'a, 'b> From<AnyLTreeIssue<'a, 'b>> for Issue<'b> {
     fn from(any: AnyLTreeIssue<'a, 'b>) -> Self {
@@ -470,6 +657,7 @@ impl<'a, 'b> From<AnyLTreeIssue<'a, 'b>> for Issue<'b> {
             AnyLTreeIssue::UnclosedVerbatim(spec) => spec.into(),
             AnyLTreeIssue::VerbatimUnderindented(spec) => spec.into(),
             AnyLTreeIssue::LongVerbatimCloseWarning(spec) => spec.into(),
+            AnyLTreeIssue::UnclosedFencedBlock(spec) => spec.into(),
         }
     }
 }
@@ -484,9 +672,11 @@ impl<'a, 'b> From<InsufficientIndentWarning<'a, 'b>> for Issue<'b> {
         quote.limit = Some(value.block.span.begin.line_pos + 3);
         Issue {
             quote,
-            title: "Too few spaces before block lines",
-            subtext: "Conventionally, a block's indent is exactly three greater than its parent's.",
+            title: format!("Too few spaces before block lines, expected {} but found {}", 
+                value.expect_indent, value.block.indent).into(),
+            subtext: "Conventionally, a block's indent is exactly three greater than its parent's.".into(),
             severity: Severity::Warning,
+            fix: Some(reindent_fix(value.block, value.expect_indent)),
             elaborations: Vec::new()
         }
     }
@@ -502,9 +692,11 @@ impl<'a, 'b> From<ExcessiveIndentWarning<'a, 'b>> for Issue<'b> {
         quote.limit = Some(value.block.span.begin.line_pos + 3);
         Issue {
             quote,
-            title: "Too many spaces before block lines",
-            subtext: "Conventionally, a block's indent is exactly three greater than its parent's.",
+            title: format!("Too many spaces before block lines, expected {} but found {}", 
+                value.expect_indent, value.block.indent).into(),
+            subtext: "Conventionally, a block's indent is exactly three greater than its parent's.".into(),
             severity: Severity::Warning,
+            fix: Some(reindent_fix(value.block, value.expect_indent)),
             elaborations: Vec::new()
         }
     }
@@ -517,11 +709,24 @@ impl<'a> From<ExcessiveVerticalSpaceWarning<'a>> for Issue<'a> {
         quote.extend_down();
         quote.place_barrier_before(value.span.begin.line_pos + value.limit, 
             BarrierStyle::Placeholder, "blank lines could've ended here");
+        // The removed text begins with the linebreak ending the last of the kept lines,
+        // so that the lines following the blank lines are not part of the fix.
+        let source = value.span.source;
+        let mut begin = value.span.begin.byte_pos;
+        for _ in 0..value.limit { begin += source[begin..].find('\n').unwrap() + 1; }
+        let end = value.span.end.byte_pos + source[value.span.end.byte_pos..].find('\n').unwrap();
+        let excess_count = source[(begin - 1)..end].matches('\n').count();
+        // Three blank lines within a nested block end the document, so removing them changes
+        // its interpretation.
+        let is_safe = !value.is_nested || value.limit + excess_count <= 2;
+        let fix = Fix::from_edits(source, format!("remove {} blank line{}", excess_count, 
+            plural(excess_count)), vec![((begin - 1)..end, String::new())], is_safe);
         Issue {
             quote,
-            title: "Too many blank separator lines",
-            subtext: "A lesser number of linebreaks has equivalent interpretation.",
+            title: "Too many blank separator lines".into(),
+            subtext: "A lesser number of linebreaks has equivalent interpretation.".into(),
             severity: Severity::Warning,            
+            fix: Some(fix),
             elaborations: Vec::new()
         }
     }
@@ -530,6 +735,7 @@ impl<'a> From<ExcessiveVerticalSpaceWarning<'a>> for Issue<'a> {
 impl<'a, 'b> From<AbruptChildBlockWarning<'a, 'b>> for Issue<'b> {
     fn from(value: AbruptChildBlockWarning<'a, 'b>) -> Self {
         let first_line = value.child_block.span.begin.line_pos;
+        let first_line_begin = value.child_block.span.begin.byte_pos;
         let mut quote = AnnotatedSourceSection::from_span(&value.child_block.span);
         quote.extend_up(1);
         quote.limit = Some(first_line);
@@ -537,9 +743,12 @@ impl<'a, 'b> From<AbruptChildBlockWarning<'a, 'b>> for Issue<'b> {
             "expected blank separator line here");
         Issue {
             quote,
-            title: "Missing blank separator line",
-            subtext: "Conventionally, a child block is seperated from its parent by a blank line.",
+            title: "Missing blank separator line".into(),
+            subtext: "Conventionally, a child block is seperated from its parent by a blank line.".into(),
             severity: Severity::Warning,
+            fix: Some(Fix::from_edits(value.child_block.span.source, 
+                String::from("insert a blank line"),
+                vec![(first_line_begin..first_line_begin, String::from("\n"))], true)),
             elaborations: Vec::new()
         }
     }
@@ -553,9 +762,10 @@ impl<'a, 'b> From<VerbatimUnderindentedWarning<'a, 'b>> for Issue<'b> {
             "expected indent in these columns");
         Issue {
             quote,
-            title: "Backtick block contains under-indented lines",
-            subtext: "The lines in a backtick block should begin at the same level as the declarator.",
+            title: "Backtick block contains under-indented lines".into(),
+            subtext: "The lines in a backtick block should begin at the same level as the declarator.".into(),
             severity: Severity::Warning,
+            fix: Some(verbatim_indent_fix(value.verbatim)),
             elaborations: Vec::new()
         }
     }
@@ -567,10 +777,11 @@ impl<'a, 'b> From<UnclosedVerbatimError<'a, 'b>> for Issue<'b> {
         quote.limit = Some(value.verbatim.span.begin.line_pos + 1);
          Issue {
             quote,
-            title: "Backtick block is never closed",
+            title: "Backtick block is never closed".into(),
             subtext: "This backtick block should end with a closing declarator matching the \
-                      opening declarator.",
+                      opening declarator.".into(),
             severity: Severity::Error,
+            fix: Some(verbatim_close_fix(value.verbatim)),
             elaborations: Vec::new()
         }
     }
@@ -583,14 +794,100 @@ impl<'a, 'b> From<LongVerbatimCloseWarning<'a, 'b>> for Issue<'b> {
         quote.highlight(value.close.begin.byte_pos, value.close.end.byte_pos);
         Issue { 
             quote,
-            title: "Too many closing backticks",
-            subtext: "The number of closing backticks should equal the number of opening backticks.",
+            title: format!("Too many closing backticks, expected {} but found {}",
+                value.node.open.as_ref().len(), value.close.as_ref().len()).into(),
+            subtext: "The number of closing backticks should equal the number of opening backticks.".into(),
             severity: Severity::Warning,            
+            fix: Some(shorten_close_fix(value.node.open, value.close)),
+            elaborations: Vec::new()
+        }
+    }
+}
+
+impl<'a, 'b> From<UnclosedFencedBlockError<'a, 'b>> for Issue<'b> {
+    fn from(value: UnclosedFencedBlockError<'a, 'b>) -> Self {
+        let mut quote = AnnotatedSourceSection::from_span(&value.node.span);
+        quote.highlight(value.node.open.begin.byte_pos, value.node.open.end.byte_pos);
+        quote.limit = Some(value.node.span.begin.line_pos + 1);
+        Issue {
+            quote,
+            title: "Fenced block is never closed".into(),
+            subtext: "This fenced block should end with a closing fence at the same indent \
+                      as the opening fence.".into(),
+            severity: Severity::Error,
+            fix: None,
             elaborations: Vec::new()
         }
     }
 }
 
+/// Constructs a fix which shifts every line of `block` so that the block begins at `expect_indent`.
+/// Nested blocks are shifted along with it.
+fn reindent_fix<'b>(block: &ast::Block<'b>, expect_indent: usize) -> Fix {
+    let source = block.span.source;
+    let mut edits: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+    let mut line_begin = block.span.begin.byte_pos;
+    for line in source[block.span.begin.byte_pos..block.span.end.byte_pos].split_inclusive('\n') {
+        let content = line.trim_end_matches('\n');
+        if content.len() > 0 {
+            if expect_indent > block.indent {
+                edits.push((line_begin..line_begin, " ".repeat(expect_indent - block.indent)));
+            } else {
+                let leading_space_count = content.len() - content.trim_start_matches(' ').len();
+                let remove_count = std::cmp::min(leading_space_count, block.indent - expect_indent);
+                edits.push((line_begin..(line_begin + remove_count), String::new()));
+            }
+        }
+        line_begin += line.len();
+    }
+    let description = match expect_indent > block.indent {
+        true => format!("insert {} space{} before each line", expect_indent - block.indent,
+            plural(expect_indent - block.indent)),
+        false => format!("remove {} space{} before each line", block.indent - expect_indent,
+            plural(block.indent - expect_indent)),
+    };
+    return Fix::from_edits(source, description, edits, true);
+}
+
+/// Constructs a fix which indents every under-indented line of `verbatim`, including its
+/// closing declarator, to the column of its opening declarator.
+fn verbatim_indent_fix<'b>(verbatim: &ast::Verbatim<'b>) -> Fix {
+    let mut edits: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+    for line_begin in verbatim.lines.iter().map(|line| line.begin).chain(verbatim.close.map(|c| c.begin)) {
+        if line_begin.colu_pos >= verbatim.indent { continue; }
+        let insert_at = line_begin.byte_pos;
+        edits.push((insert_at..insert_at, " ".repeat(verbatim.indent - line_begin.colu_pos)));
+    }
+    return Fix::from_edits(verbatim.span.source, String::from("indent lines to the declarator"),
+        edits, true);
+}
+
+/// Constructs a fix which closes `verbatim` at the end of the document. This does not change
+/// the interpretation of the document, but it is likely not where the author meant to close it,
+/// so the fix is not safe.
+fn verbatim_close_fix<'b>(verbatim: &ast::Verbatim<'b>) -> Fix {
+    let source = verbatim.span.source;
+    let mut close = String::new();
+    if !source.ends_with('\n') { close.push('\n'); }
+    close.push_str(&" ".repeat(verbatim.indent));
+    close.push_str(verbatim.open.as_ref());
+    close.push('\n');
+    let end = source.len();
+    return Fix::from_edits(source, String::from("add a closing declarator"), 
+        vec![(end..end, close)], false);
+}
+
+/// Constructs a fix which shortens the closing declarator `close` to match `open`.
+pub fn shorten_close_fix<'b>(open: SourceSpan<'b>, close: SourceSpan<'b>) -> Fix {
+    let excess_count = close.as_ref().len() - open.as_ref().len();
+    let description = format!("remove {} backtick{}", excess_count, plural(excess_count));
+    let range = close.begin.byte_pos..close.end.byte_pos;
+    return Fix::from_edits(close.source, description, vec![(range, open.as_ref().to_string())], true);
+}
+
+pub fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
 
 // Utilities
 
@@ -607,6 +904,13 @@ fn tail<'a, 'b>(root: &'a ast::BlockChild<'b>) -> &'a ast::BlockChild<'b> {
             } 
         }
     } 
+    if let ast::BlockChild::DefinitionList(list) = root {
+        if let Some(child) = list.children.last() {
+            if let Some(grandchild) = child.content.children.last() {
+               return tail(grandchild);
+            } 
+        }
+    } 
     return root;
 }
 
@@ -620,5 +924,10 @@ fn tail_block<'a, 'b>(root: &'a ast::Block<'b>) -> &'a ast::Block<'b> {
             return tail_block(&child_element.content);
         }
     } 
+    if let ast::BlockChild::DefinitionList(list) = last {
+        if let Some(child_definition) = list.children.last() {
+            return tail_block(&child_definition.content);
+        }
+    } 
     return root;
 }
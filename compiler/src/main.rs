@@ -1,30 +1,200 @@
 use std::path::PathBuf;
 
-use clap::Parser;
-use merry_compiler::compile::{compile_dir, compile_file};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap::error::ErrorKind;
+use glob::Pattern;
+use merry_compiler::config::{discover_config, is_absolute_url, load_config};
+use merry_compiler::compile::{compile_dir, compile_file, load_template, Options, OutputFormat};
+use merry_compiler::tasks::{report_tasks_dir, report_tasks_file};
+use merry_compiler::import::{import_dir, import_file};
+use merry_compiler::fmt::{format_dir, format_file};
+use merry_compiler::lsp::run_server;
+use merry_compiler::source_map::{SourceCache, SourceMap};
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Cli {
-    input_path: PathBuf,
-    output_path: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    input_path: Option<PathBuf>,
+    /// The output file or directory. May be omitted if the project configuration gives
+    /// an output directory.
+    output_path: Option<PathBuf>,
+    /// The template used for every document, unless overriden by a directory template
+    /// or by the document itself. This takes precedence over the project configuration.
     #[arg(short, long)]
-    head: Option<PathBuf>
+    template: Option<PathBuf>,
+    /// The language of the finished documents.
+    #[arg(short, long, value_enum, default_value_t = Format::Html)]
+    format: Format,
+    /// Applies the safe suggested fixes to the source files in place before compiling them.
+    #[arg(long)]
+    fix: bool,
+    /// Defines a value which documents reference with an empty span marked `name`, unless
+    /// the document defines the value itself. May be given more than once.
+    #[arg(short = 'D', value_name = "NAME=VALUE", value_parser = parse_define)]
+    define: Vec<(String, String)>,
+    /// Copies only the files, other than source files, matching this glob into the output
+    /// directory. May be given more than once. By default every file is copied, except
+    /// hidden files. This takes precedence over the project configuration.
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    include: Vec<Pattern>,
+    /// Never copies the files matching this glob into the output directory. May be given
+    /// more than once. This takes precedence over the project configuration.
+    #[arg(long, value_name = "GLOB", value_parser = parse_glob)]
+    exclude: Vec<Pattern>,
+    /// Removes the files in the output directory which were produced by the previous build but
+    /// not by this one, for instance the finished document of a source file since deleted.
+    #[arg(long)]
+    clean: bool,
+    /// Writes a search index of every document, and the script which searches it, into the
+    /// output directory.
+    #[arg(long)]
+    search: bool,
+    /// Includes the text of embedded HTML, such as rewritten math, in the search index.
+    /// Implies `--search`.
+    #[arg(long)]
+    search_html: bool,
+    /// The absolute URL at which the output directory is published. When given, a sitemap and
+    /// an Atom feed of the dated documents are written into the output directory. This takes
+    /// precedence over the project configuration.
+    #[arg(long, value_name = "URL", value_parser = parse_base_url)]
+    base_url: Option<String>
+}
+
+fn parse_define(arg: &str) -> Result<(String, String), String> {
+    let Some((name, value)) = arg.split_once('=') else {
+        return Err(String::from("expected NAME=VALUE"));
+    };
+    if name.len() == 0 || name.contains([' ', ',']) {
+        return Err(format!("`{}` is not a valid name, since a tag cannot be empty or contain \
+            spaces or commas", name));
+    }
+    return Ok((name.to_string(), value.to_string()));
+}
+
+fn parse_glob(arg: &str) -> Result<Pattern, String> {
+    return Pattern::new(arg).map_err(|err| err.to_string());
+}
+
+fn parse_base_url(arg: &str) -> Result<String, String> {
+    if !is_absolute_url(arg) {
+        return Err(String::from("expected an absolute URL beginning with http:// or https://"));
+    }
+    return Ok(arg.to_string());
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Html,
+    /// CommonMark with GitHub Flavored Markdown extensions
+    Markdown
+}
+
+impl From<Format> for OutputFormat {
+    fn from(value: Format) -> Self {
+        match value {
+            Format::Html => OutputFormat::Html,
+            Format::Markdown => OutputFormat::Markdown,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Lists the unchecked task list elements in a source file or directory.
+    Tasks { input_path: PathBuf },
+    /// Converts a Markdown file, or every `.md` file in a directory, into Merry source.
+    /// By default the output is placed alongside the input.
+    Import { input_path: PathBuf, output_path: Option<PathBuf> },
+    /// Rewrites a source file, or every source file in a directory, to satisfy the
+    /// stylistic conventions enforced by the linter.
+    Fmt {
+        input_path: PathBuf,
+        /// Lists the files which are not formatted instead of rewriting them, and exits
+        /// unsuccessfully if there are any.
+        #[arg(long)]
+        check: bool
+    },
+    /// Starts the language server, which communicates with the editor over stdin and stdout.
+    Lsp
 }
 
 fn main() -> std::io::Result<()> {
     let cli = Cli::parse();
 
+    if let Some(Command::Tasks { input_path }) = cli.command {
+        if input_path.is_dir() { return report_tasks_dir(&input_path); }
+        return report_tasks_file(&input_path);
+    }
+
+    if let Some(Command::Import { input_path, output_path }) = cli.command {
+        if input_path.is_dir() {
+            let output_path = output_path.unwrap_or_else(|| input_path.clone());
+            return import_dir(&input_path, &output_path);
+        }
+        let output_path = output_path.unwrap_or_else(|| input_path.with_extension("md2"));
+        return import_file(&input_path, &output_path);
+    }
+
+    if let Some(Command::Fmt { input_path, check }) = cli.command {
+        let changed_count = match input_path.is_dir() {
+            true => format_dir(&input_path, check)?,
+            false => format_file(&input_path, check)? as usize
+        };
+        if check && changed_count > 0 { std::process::exit(1); }
+        return Ok(());
+    }
+
+    if let Some(Command::Lsp) = cli.command {
+        return run_server();
+    }
+
     let mut input_path = std::env::current_dir()?;
-    input_path.push(&cli.input_path);
+    input_path.push(cli.input_path.unwrap());
+    let sources = SourceMap::new();
+    let config = discover_config(&input_path)
+        .and_then(|config_path| load_config(&config_path, &sources))
+        .unwrap_or_default();
+    let output_path = match (cli.output_path, &config.output) {
+        (Some(output_path), _) => output_path,
+        (None, Some(output_dir)) if input_path.is_file() => {
+            let mut output_path = output_dir.join(input_path.file_name().unwrap());
+            output_path.set_extension(OutputFormat::from(cli.format).extension());
+            output_path
+        },
+        (None, Some(output_dir)) => output_dir.clone(),
+        (None, None) => Cli::command()
+            .error(ErrorKind::MissingRequiredArgument, 
+                "the output path must be given, since no project configuration gives it")
+            .exit()
+    };
+    let template = cli.template.or(config.template.clone())
+        .and_then(|path| load_template(&path, &sources));
+    let mut options = Options {
+        format: OutputFormat::from(cli.format),
+        template: template.as_ref(),
+        clean: cli.clean,
+        ..Options::default()
+    };
+    options.configure(&config);
+    options.environment.defines = cli.define;
+    if cli.include.len() > 0 { options.assets.include = cli.include; }
+    if cli.exclude.len() > 0 { options.assets.exclude = cli.exclude; }
+    if cli.search || cli.search_html { options.search_index = true; }
+    if cli.search_html { options.search_html = true; }
+    if let Some(base_url) = cli.base_url { options.base_url = Some(base_url); }
+    let cache = SourceCache::new(&sources);
   
     if input_path.is_dir() {
-        compile_dir(input_path, cli.output_path, &cli.head)?;
+        compile_dir(input_path, output_path, &options, cli.fix, &cache)?;
         return Ok(());
     }
 
     if input_path.is_file() {
-        std::fs::create_dir_all(cli.output_path.parent().unwrap())?;
-        compile_file(input_path, cli.output_path, &cli.head)?;
+        std::fs::create_dir_all(output_path.parent().unwrap())?;
+        compile_file(input_path, output_path, &options, cli.fix, &cache)?;
         return Ok(());
     }
     
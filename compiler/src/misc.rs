@@ -10,6 +10,7 @@ pub mod ansi {
     pub const FG_DEFAULT: &str = "\x1b[39m";
     pub const FG_RED: &str = "\x1b[31m";
     pub const FG_YELLOW: &str = "\x1b[33m";
+    pub const FG_GREEN: &str = "\x1b[32m";
     pub const FG_GREY: &str = "\x1b[90m";
     pub const BG_DEFAULT: &str = "\x1b[49m";
     pub const BG_RED: &str = "\x1b[41m";
@@ -31,6 +32,42 @@ pub fn pad(text: &str, max_len: usize) -> String {
 }
 
 
+/// Returns the current date in the UTC timezone formatted as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let unix_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((unix_time / 86400) as i64);
+    return format!("{:04}-{:02}-{:02}", year, month, day);
+}
+
+/// Converts a number of days since the Unix epoch into a Gregorian calendar date.
+/// This is Howard Hinnant's `civil_from_days` algorithm.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = (z - era * 146097) as u32;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    return (if m <= 2 { y + 1 } else { y }, m, d);
+}
+
+/// Returns the length of the longest run of consecutive `symbol`s in `text`.
+pub fn longest_run_of(text: &str, symbol: char) -> usize {
+    let mut longest: usize = 0;
+    let mut current: usize = 0;
+    for c in text.chars() {
+        if c == symbol { current += 1; } else { current = 0; }
+        longest = std::cmp::max(longest, current);
+    }
+    return longest;
+}
+
 /// Removes the first element in `vec` matching the predicate `pred` and returns it.
 /// Or, if no element matches the predicate returns `None`.
 pub fn remove_first<T>(vec: &mut Vec<T>, pred: impl Fn(&T) -> bool) -> Option<T>
@@ -61,3 +98,16 @@ macro_rules! req {
     
     ($p:pat, $v:expr) => { let $p = $v else { return; }; }
 }
+
+/// Returns the relative URL path from the directory `from` to the file `to`. Both paths must be
+/// absolute, and should be canonical, since `..` components are not resolved.
+pub fn relative_url_path(from: &std::path::Path, to: &std::path::Path) -> String {
+    let from: Vec<std::path::Component> = from.components().collect();
+    let to: Vec<std::path::Component> = to.components().collect();
+    let common_count = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut segments: Vec<String> = vec![String::from(".."); from.len() - common_count];
+    for component in &to[common_count..] {
+        segments.push(component.as_os_str().to_string_lossy().into_owned());
+    }
+    return segments.join("/");
+}
@@ -40,7 +40,9 @@ pub mod ast {
         List(List<'a>),
         VerbatimBlock(VerbatimBlock<'a>),
         Section(Section<'a>),
-        ExplicitSectionClose(ExplicitSectionClose<'a>)
+        ExplicitSectionClose(ExplicitSectionClose<'a>),
+        FencedBlock(FencedBlock<'a>),
+        DefinitionList(DefinitionList<'a>)
     }
 
     #[derive(Debug)]
@@ -53,6 +55,25 @@ pub mod ast {
 
     #[derive(Debug)]
     pub struct ListElement<'a> {
+        pub content: Block<'a>,
+        pub task: Option<TaskMarker<'a>>
+    }
+
+    /// The `[ ]` or `[x]` which begins the first line of a task list element.
+    #[derive(Debug, Clone, Copy)]
+    pub struct TaskMarker<'a> {
+        pub checked: bool,
+        pub span: SourceSpan<'a>
+    }
+
+    #[derive(Debug)]
+    pub struct DefinitionList<'a> {
+        pub definitions: Vec<Definition<'a>>
+    }
+
+    #[derive(Debug)]
+    pub struct Definition<'a> {
+        pub term: ttree::ast::Root<'a>,
         pub content: Block<'a>
     }
 
@@ -68,6 +89,15 @@ pub mod ast {
         pub lines: Vec<SourceSpan<'a>>
     }
     
+    /// A nested block which is delimited by fences in the source text. Unlike a verbatim block,
+    /// the content of a fenced block is interpreted like any other block. The fences exist only
+    /// so that a trailing qualifier can be attached to the block as a whole.
+    #[derive(Debug)]
+    pub struct FencedBlock<'a> {
+        pub trailing_qualifier: Option<ttree::ast::TrailingQualifier<'a>>,
+        pub content: Block<'a>
+    }
+
     #[derive(Debug)]
     pub struct DirectiveInvocation<'a> {
         pub args: Vec<SourceSpan<'a>>,
@@ -106,6 +136,15 @@ scanner! {
 }
 
 fn make_block<'a, 'b>(ltree_block: &'a ltree::ast::Block<'b>) -> ast::Block<'b> {
+    return make_block_from(ltree_block, None);
+}
+
+/// Constructs an [`ast::Block`] from the given *LTree* block. If `first_line_begin` is given,
+/// the content of the block's first line is considered to begin there instead. This is used 
+/// to exclude syntax which prefixes the block, like a task marker, from its content.
+fn make_block_from<'a, 'b>(ltree_block: &'a ltree::ast::Block<'b>, 
+    mut first_line_begin: Option<SourceLocation>) -> ast::Block<'b> 
+{
     let mut mtree_children: Vec<ast::BlockChild<'b>> = Vec::new();
     let mut paragraph_lines: Vec<SourceSpan<'b>> = Vec::new();
 
@@ -120,6 +159,10 @@ fn make_block<'a, 'b>(ltree_block: &'a ltree::ast::Block<'b>) -> ast::Block<'b>
     
     for ltree_child in &ltree_block.children {
         if let ltree::ast::BlockChild::Line(line) = ltree_child {
+            let mut line = ltree::ast::Line { indent_span: line.indent_span, 
+                line_content: line.line_content };
+            if let Some(begin) = first_line_begin.take() { line.line_content.begin = begin; }
+            let line = &line;
             if line.line_content.begin().at_symbol("#") {
                 push_paragraph!();
                 let heading = make_heading(line);
@@ -157,6 +200,16 @@ fn make_block<'a, 'b>(ltree_block: &'a ltree::ast::Block<'b>) -> ast::Block<'b>
             mtree_children.push(ast::BlockChild::List(mtree_list));
             continue;
         }
+        if let ltree::ast::BlockChild::DefinitionList(ltree_list) = ltree_child {
+            let mtree_list = make_definition_list(ltree_list);
+            mtree_children.push(ast::BlockChild::DefinitionList(mtree_list));
+            continue;
+        }
+        if let ltree::ast::BlockChild::FencedBlock(ltree_fenced) = ltree_child {
+            let mtree_fenced = make_fenced_block(ltree_fenced);
+            mtree_children.push(ast::BlockChild::FencedBlock(mtree_fenced));
+            continue;
+        }
     }
     push_paragraph!();
     return ast::Block { children: mtree_children }
@@ -165,12 +218,46 @@ fn make_block<'a, 'b>(ltree_block: &'a ltree::ast::Block<'b>) -> ast::Block<'b>
 fn make_list<'a, 'b>(ltree_list: &'a ltree::ast::List<'b>) -> ast::List<'b> {
     let mut elements: Vec<ast::ListElement<'b>> = Vec::new();
     for list_child in &ltree_list.children {
-        let content = make_block(&list_child.content);
-        elements.push(ast::ListElement { content });
+        let task = make_task_marker(&list_child.content);
+        let content = make_block_from(&list_child.content, task.map(|t| t.span.end));
+        elements.push(ast::ListElement { content, task });
     }
     return ast::List { elements };   
 }
 
+scanner! {
+    task_marker () |cursor| {
+        if cursor.match_symbol("[").is_none() { return false; }
+        if cursor.match_symbol(" ").is_none() && cursor.match_symbol("x").is_none() 
+            && cursor.match_symbol("X").is_none() { return false; }
+        if cursor.match_symbol("]").is_none() { return false; }
+        return cursor.match_symbol(" ").is_some() || cursor.is_end();
+    }
+}
+
+/// Returns the task marker beginning the first line of a list element's content,
+/// if that list element is a task.
+fn make_task_marker<'a, 'b>(content: &'a ltree::ast::Block<'b>) -> Option<ast::TaskMarker<'b>> {
+    let Some(ltree::ast::BlockChild::Line(first_line)) = content.children.first() else { 
+        return None; 
+    };
+    let span = first_line.line_content.begin().match_scan(task_marker())?;
+    let checked = span.as_ref().as_bytes()[1] != b' ';
+    return Some(ast::TaskMarker { checked, span });
+}
+
+fn make_definition_list<'a, 'b>(ltree_list: &'a ltree::ast::DefinitionList<'b>) 
+-> ast::DefinitionList<'b> 
+{
+    let mut definitions: Vec<ast::Definition<'b>> = Vec::new();
+    for list_child in &ltree_list.children {
+        let term = parse_ttree(&[list_child.term]);
+        let content = make_block(&list_child.content);
+        definitions.push(ast::Definition { term, content });
+    }
+    return ast::DefinitionList { definitions };   
+}
+
 fn make_heading<'a, 'b>(line: &'a ltree::ast::Line<'b>) -> ast::Heading<'b> {
     let mut cursor = line.line_content.begin();
     let begin = cursor.pos();
@@ -180,8 +267,8 @@ fn make_heading<'a, 'b>(line: &'a ltree::ast::Line<'b>) -> ast::Heading<'b> {
     let tail = cursor.pop_line();
     let content = parse_ttree(&[tail]);
     let end = cursor.pos();
-    let span = SourceSpan { source: cursor.source, begin, end };
-    let pounds_span = SourceSpan { source: cursor.source, begin, end: pounds_end };
+    let span = SourceSpan { source: cursor.source, file: cursor.file, begin, end };
+    let pounds_span = SourceSpan { source: cursor.source, file: cursor.file, begin, end: pounds_end };
     return ast::Heading { hlevel, content, span, pounds_span };
 }
 
@@ -195,6 +282,17 @@ fn make_verbatim<'a, 'b>(ltree_verbatim: &'a ltree::ast::Verbatim<'b>) -> ast::V
     return ast::VerbatimBlock { lines, trailing_qualifier };
 }
 
+fn make_fenced_block<'a, 'b>(ltree_fenced: &'a ltree::ast::FencedBlock<'b>) 
+-> ast::FencedBlock<'b>
+{
+    let mut trailing_qualifier: Option<ttree::ast::TrailingQualifier<'b>> = None;
+    if let Some(tail) = ltree_fenced.tail {
+        trailing_qualifier = ttree::parse_misc_trailing_qualifier(&[tail]);
+    }
+    let content = make_block(&ltree_fenced.content);
+    return ast::FencedBlock { trailing_qualifier, content };
+}
+
 #[allow(unused_assignments)]
 fn parse_directive_invocation<'a, 'b>(line: &'a ltree::ast::Line<'b>) 
 -> ast::DirectiveInvocation<'b>
@@ -207,7 +305,7 @@ fn parse_directive_invocation<'a, 'b>(line: &'a ltree::ast::Line<'b>)
 
     macro_rules! push_arg { () => {
         if let Some(begin) = current_arg_begin {
-            args.push(SourceSpan { source: cursor.source, begin, end: cursor.pos() });
+            args.push(SourceSpan { source: cursor.source, file: cursor.file, begin, end: cursor.pos() });
             current_arg_begin = None;
         }
         is_quoted = false;
@@ -280,6 +378,14 @@ fn sectionize<'a, 'b>(block: &'a mut ast::Block<'b>, pos: usize) {
                 sectionize_block(&mut element.content, section_heading.hlevel);
             }
         }
+        if let ast::BlockChild::DefinitionList(next_list) = &mut block.children[i] {
+            for definition in &mut next_list.definitions {
+                sectionize_block(&mut definition.content, section_heading.hlevel);
+            }
+        }
+        if let ast::BlockChild::FencedBlock(next_fenced) = &mut block.children[i] {
+            sectionize_block(&mut next_fenced.content, section_heading.hlevel);
+        }
         if let ast::BlockChild::ExplicitSectionClose(explicit_close) = &block.children[i] {
             if section_heading.hlevel > explicit_close.target_hlevel {
                 break;
@@ -301,6 +407,17 @@ pub enum AnyMTreeIssue<'a, 'b> {
     UnstructuredDocumentWarning(UnstructuredDocumentWarning<'a, 'b>),
 }
 
+impl<'a, 'b> AnyMTreeIssue<'a, 'b> {
+    /// Returns the name by which the severity of this issue is configured, or `None` if this
+    /// issue is an error, since the severity of errors cannot be configured.
+    pub fn lint_name(&self) -> Option<&'static str> {
+        match self {
+            AnyMTreeIssue::AnyTTreeIssue(spec) => spec.lint_name(),
+            AnyMTreeIssue::UnstructuredDocumentWarning(_) => Some("unstructured-document"),
+        }
+    }
+}
+
 impl<'a, 'b> From<AnyMTreeIssue<'a, 'b>> for Issue<'b> {
     fn from(value: AnyMTreeIssue<'a, 'b>) -> Self {
         match value {
@@ -317,11 +434,12 @@ impl<'a, 'b> From<UnstructuredDocumentWarning<'a, 'b>> for Issue<'b> {
             value.heading.pounds_span.end.byte_pos);
         Issue {
             quote,
-            title: "Cannot return to ancestor section here",
+            title: "Cannot return to ancestor section here".into(),
             subtext: "The target ancestor exists outside the current block. \
                       Increase the heading level until \nit is at least greater than \
-                      that of the section immediately enclosing the current block.",
+                      that of the section immediately enclosing the current block.".into(),
             severity: Severity::Warning,
+            fix: None,
             elaborations: Vec::new()
         }
     }
@@ -361,6 +479,12 @@ pub fn verify_block_child<'a, 'b>(child: &'a ast::BlockChild<'b>,
     if let ast::BlockChild::Section(section) = child {
         verify_section(section, issues);
     }
+    if let ast::BlockChild::FencedBlock(fenced) = child {
+        verify_fenced_block(fenced, issues);
+    }
+    if let ast::BlockChild::DefinitionList(list) = child {
+        verify_definition_list(list, issues);
+    }
 }
 
 pub fn verify_list<'a, 'b>(list: &'a ast::List<'b>, issues: &mut Vec<AnyMTreeIssue<'a, 'b>>) {
@@ -369,6 +493,17 @@ pub fn verify_list<'a, 'b>(list: &'a ast::List<'b>, issues: &mut Vec<AnyMTreeIss
     }
 }
 
+pub fn verify_definition_list<'a, 'b>(list: &'a ast::DefinitionList<'b>, 
+    issues: &mut Vec<AnyMTreeIssue<'a, 'b>>) 
+{
+    for definition in &list.definitions {
+        for issue in verify_ttree(&definition.term) {
+            issues.push(AnyMTreeIssue::AnyTTreeIssue(issue));
+        }
+        verify_block(&definition.content, issues);
+    }
+}
+
 pub fn verify_section<'a ,'b>(section: &'a ast::Section<'b>,
     issues: &mut Vec<AnyMTreeIssue<'a, 'b>>)
 {
@@ -396,3 +531,14 @@ pub fn verify_verbatim<'a, 'b>(verbatim: &'a ast::VerbatimBlock<'b>,
         issues.push(AnyMTreeIssue::AnyTTreeIssue(ttree_issue));
     }
 }
+
+pub fn verify_fenced_block<'a, 'b>(fenced: &'a ast::FencedBlock<'b>,
+    issues: &mut Vec<AnyMTreeIssue<'a, 'b>>)
+{
+    let mut ttree_issues: Vec<AnyTTreeIssue<'a, 'b>> = Vec::new();
+    ttree::verify_trailing_qualifier(&fenced.trailing_qualifier, &mut ttree_issues);
+    for ttree_issue in ttree_issues {
+        issues.push(AnyMTreeIssue::AnyTTreeIssue(ttree_issue));
+    }
+    verify_block(&fenced.content, issues);
+}
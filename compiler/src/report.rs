@@ -2,15 +2,21 @@
 //! an md2 source file. This module provides facilities for displaying rich
 //! and nicely formatted error messages for all these.
 
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::io::Write;
+use std::ops::Range;
+use std::path::PathBuf;
 use crate::misc::pad;
 use crate::misc::ansi::*;
 use crate::scan::SourceSpan;
+use crate::source_map::{FileId, SourceMap};
 
 /// A reference to zero or more consecutive lines in a source file along with annotations.
 pub struct AnnotatedSourceSection<'a> {
     source: &'a str,
+    /// The file which the quoted lines belong to.
+    pub file: FileId,
     pub first_line_no: usize,
     last_line_no: usize,
     first_line_begin_bpos: usize,
@@ -50,6 +56,7 @@ impl<'a> AnnotatedSourceSection<'a> {
         
         return AnnotatedSourceSection { 
             source: span.source, 
+            file: span.file,
             first_line_no: span.begin.line_pos,
             last_line_no: span.end.line_pos, 
             first_line_begin_bpos, last_line_end_bpos,
@@ -96,6 +103,29 @@ impl<'a> AnnotatedSourceSection<'a> {
         self.barriers.insert(line_no, Barrier { note, style });
     }
 
+    /// Returns the byte range in the source text which this quote draws attention to.
+    /// That is the first highlight, or else the line following the first barrier,
+    /// or else the entire quote.
+    pub fn focus(&self) -> std::ops::Range<usize> {
+        if let Some((begin_bpos, highlight)) = self.highlights.iter().next() {
+            return *begin_bpos..(*begin_bpos + highlight.byte_length);
+        }
+        if let Some(barrier_line_no) = self.barriers.keys().min() {
+            let mut line_begin_bpos = self.first_line_begin_bpos;
+            for _ in self.first_line_no..*barrier_line_no {
+                match self.source[line_begin_bpos..].find('\n') {
+                    Some(i) => line_begin_bpos += i + 1,
+                    None => break
+                }
+            }
+            let line_end_bpos = self.source[line_begin_bpos..].find('\n')
+                .map(|i| i + line_begin_bpos)
+                .unwrap_or(self.source.len());
+            return line_begin_bpos..line_end_bpos;
+        }
+        return self.first_line_begin_bpos..self.last_line_end_bpos;
+    }
+
     pub fn highlight(&mut self, begin_bpos: usize, end_bpos: usize) {
         assert!(begin_bpos >= self.first_line_begin_bpos);
         assert!(end_bpos <= self.last_line_end_bpos);
@@ -106,61 +136,79 @@ impl<'a> AnnotatedSourceSection<'a> {
 }
 
 fn print_src_quote(quote: &AnnotatedSourceSection) {
+    let mut stdout_handle = std::io::stdout().lock();
+    write_src_quote(quote, &mut stdout_handle, /* colored = */ true).unwrap();
+}
+
+/// Writes the quoted lines to `out`, prefixed by their line numbers, and annotated with
+/// the quote's barriers. If `colored` is set, the source text is greyed and the highlights
+/// are underlined using ANSI escape codes.
+fn write_src_quote<W>(quote: &AnnotatedSourceSection, out: &mut W, colored: bool) 
+-> std::io::Result<()> where W: Write
+{
     let quote_text = &quote.source[quote.first_line_begin_bpos..quote.last_line_end_bpos];
     let line_no_len = (quote.last_line_no + 1).to_string().len();
+    let style = |code: &'static str| if colored { code } else { "" };
     
     let mut line_no = quote.first_line_no;
     let mut byte_pos: usize = quote.first_line_begin_bpos;
     let mut stop_highlight_at: Option<usize> = None;
     for line_text in quote_text.lines() {
         if let Some(barrier) = quote.barriers.get(&line_no) {
-            for _ in 0..(line_no_len + 2) { print!(" "); }
+            for _ in 0..(line_no_len + 2) { write!(out, " ")?; }
             match barrier.style {
                 BarrierStyle::Ruler(col, width) => {
-                    for _ in 0..col { print!(" "); }
-                    for _ in 0..width { print!("-"); }
-                    println!(" {}", barrier.note);
+                    for _ in 0..col { write!(out, " ")?; }
+                    for _ in 0..width { write!(out, "-")?; }
+                    writeln!(out, " {}", barrier.note)?;
                 },
-                BarrierStyle::Placeholder => println!("** {} **", barrier.note),
+                BarrierStyle::Placeholder => writeln!(out, "** {} **", barrier.note)?,
             }
         }
         // TODO: Replace pad() with non-allocating for loop
-        print!("{}| ", pad(&(line_no + 1).to_string(), line_no_len));
-        print!("{}", FG_GREY);
-        let mut stdout_handle = std::io::stdout().lock();
+        write!(out, "{}| ", pad(&(line_no + 1).to_string(), line_no_len))?;
+        write!(out, "{}", style(FG_GREY))?;
         for byte in line_text.bytes() {
             if let Some(highlight) = quote.highlights.get(&byte_pos) {
-                stdout_handle.write_all(UNDERLINE.as_bytes()).unwrap();
+                out.write_all(style(UNDERLINE).as_bytes())?;
                 stop_highlight_at = Some(byte_pos + highlight.byte_length);
             }
             if let Some(stop_pos) = stop_highlight_at {
                 if stop_pos == byte_pos { 
-                    stdout_handle.write_all(STOP_UNDERLINE.as_bytes()).unwrap();
+                    out.write_all(style(STOP_UNDERLINE).as_bytes())?;
                     stop_highlight_at = None;
                 }
             }
-            stdout_handle.write(&[byte]).unwrap();
+            out.write_all(&[byte])?;
             byte_pos += 1;
         }
         if let Some(stop_pos) = stop_highlight_at {
             if stop_pos == byte_pos {
-                stdout_handle.write_all(STOP_UNDERLINE.as_bytes()).unwrap();
+                out.write_all(style(STOP_UNDERLINE).as_bytes())?;
                 stop_highlight_at = None;
             }
         }
-        std::mem::drop(stdout_handle);
-        print!("\n{}", FG_DEFAULT); // TODO: WindowsLinebreaks
+        write!(out, "\n{}", style(FG_DEFAULT))?; // TODO: WindowsLinebreaks
         if quote.limit == Some(line_no) { break; }
         line_no += 1;
         byte_pos += 1; // TODO WindowsLinebreaks
     }
-    print!("{}", STOP_BOLD);
+    write!(out, "{}", style(STOP_BOLD))?;
 
     if line_no < quote.last_line_no {
-        println!("and {} more line(s)...", quote.last_line_no - line_no);
+        writeln!(out, "and {} more line(s)...", quote.last_line_no - line_no)?;
     }
+    return Ok(());
+}
+
+/// Renders the quote as plain text, without any ANSI escape codes.
+fn render_src_quote(quote: &AnnotatedSourceSection) -> String {
+    let mut rendered: Vec<u8> = Vec::new();
+    write_src_quote(quote, &mut rendered, /* colored = */ false).unwrap();
+    return String::from_utf8_lossy(&rendered).into_owned();
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Severity {
     /// A syntactic or semantic issue has `Error` severity if the source-text is
     /// likely to be misinterpreted by the compiler. 
@@ -173,13 +221,161 @@ pub enum Severity {
 
 pub struct Issue<'a> {
     pub quote: AnnotatedSourceSection<'a>,
-    pub title: &'static str,
-    pub subtext: &'static str,
+    pub title: Cow<'static, str>,
+    pub subtext: Cow<'static, str>,
     pub severity: Severity,
+    pub fix: Option<Fix>,
     pub elaborations: Vec<Elaboration<'a>>
 }
 
-pub fn print_issue<'a>(issue: &Issue<'a>, source_name: &str) {    
+/// An owned summary of an [`Issue`]. Unlike an issue, a diagnostic does not borrow the
+/// source text, so it can be stored, returned from the function which owns the source text,
+/// and sent across threads.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub title: String,
+    pub subtext: String,
+    /// The path of the source file which the issue was found in, if the source text was
+    /// read from a file.
+    pub path: Option<PathBuf>,
+    /// The byte range in the source text which the issue draws attention to.
+    pub range: Range<usize>,
+    /// The position of the first character in `range`.
+    pub begin: LineColumn,
+    /// The position immediately following the last character in `range`.
+    pub end: LineColumn,
+    /// The quoted source lines and their annotations, rendered as plain text.
+    pub quote: String,
+    /// The elaborations, each rendered as plain text.
+    pub elaborations: Vec<String>,
+    pub fix: Option<Fix>
+}
+
+/// A position in some source text. Both the line and the column begin at one, and the
+/// column counts characters, not bytes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LineColumn { pub line: usize, pub column: usize }
+
+impl LineColumn {
+    fn from_byte_pos(source: &str, byte_pos: usize) -> Self {
+        let line_begin = source[..byte_pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line = source[..line_begin].matches('\n').count() + 1;
+        let column = source[line_begin..byte_pos].chars().count() + 1;
+        return LineColumn { line, column };
+    }
+}
+
+impl Diagnostic {
+    /// Constructs a diagnostic describing `issue`. The paths of the quoted files are
+    /// looked up in `sources`.
+    pub fn from_issue<'a>(issue: &Issue<'a>, sources: &SourceMap) -> Self {
+        let source = issue.quote.source;
+        let range = issue.quote.focus();
+        let mut elaborations: Vec<String> = Vec::new();
+        for elaboration in &issue.elaborations {
+            match elaboration {
+                Elaboration::SourceQuote(quote) => elaborations.push(format!("{}\nat {}\n{}", 
+                    quote.caption, quote_header(&quote.content, sources), 
+                    render_src_quote(&quote.content))),
+                Elaboration::Quote(quote) => 
+                    elaborations.push(format!("{}\n{}", quote.caption, quote.content)),
+            }
+        }
+        return Diagnostic {
+            severity: issue.severity,
+            title: issue.title.to_string(),
+            subtext: issue.subtext.to_string(),
+            path: sources.path(issue.quote.file),
+            begin: LineColumn::from_byte_pos(source, range.start),
+            end: LineColumn::from_byte_pos(source, range.end),
+            range,
+            quote: render_src_quote(&issue.quote),
+            elaborations,
+            fix: issue.fix.clone()
+        };
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    /// Formats the diagnostic as a single line, `path:line:column: severity: title`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(path) = &self.path { write!(f, "{}:", path.display())?; }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        return write!(f, "{}:{}: {}: {}", self.begin.line, self.begin.column, severity, self.title);
+    }
+}
+
+/// A machine-applicable change to the source text which resolves an issue. The source text
+/// within `range` is replaced by `replacement`.
+#[derive(Clone, Debug)]
+pub struct Fix {
+    /// A short imperative description of the change, for instance "insert 1 space".
+    pub description: String,
+    /// The byte range in the source text which is replaced.
+    pub range: std::ops::Range<usize>,
+    pub replacement: String,
+    /// A fix is safe if it changes only the formatting of the document and not its
+    /// interpretation. Safe fixes are applied by `--fix`, the rest are only suggested.
+    pub safe: bool
+}
+
+impl Fix {
+    /// Constructs a single fix which makes all of the given non-overlapping `edits` at once.
+    /// Each edit replaces a byte range in `source` with some text.
+    pub fn from_edits(source: &str, description: String, mut edits: Vec<(std::ops::Range<usize>, String)>,
+        safe: bool) -> Self
+    {
+        edits.sort_by_key(|(range, _)| range.start);
+        let begin = edits.first().map(|(range, _)| range.start).unwrap_or(0);
+        let end = edits.last().map(|(range, _)| range.end).unwrap_or(0);
+        let mut replacement = String::new();
+        let mut pos = begin;
+        for (range, text) in edits {
+            replacement.push_str(&source[pos..range.start]);
+            replacement.push_str(&text);
+            pos = range.end;
+        }
+        return Fix { description, range: begin..end, replacement, safe };
+    }
+}
+
+/// Applies every safe fix to `source`. Fixes which overlap an earlier fix are skipped, since
+/// they were computed against text which no longer exists, and so are fixes which begin where
+/// an earlier fix inserted text, since their order would be ambiguous. Either is left for the
+/// next pass. Returns the fixed source text and the number of fixes applied.
+pub fn apply_safe_fixes<'b>(source: &str, fixes: impl Iterator<Item = &'b Fix>) -> (String, usize) {
+    let mut fixes: Vec<&Fix> = fixes.filter(|fix| fix.safe).collect();
+    fixes.sort_by_key(|fix| (fix.range.start, fix.range.end));
+    let mut fixed = String::new();
+    let mut pos: usize = 0;
+    let mut insertion_pos: Option<usize> = None;
+    let mut applied_count: usize = 0;
+    for fix in fixes {
+        if fix.range.start < pos || insertion_pos == Some(fix.range.start) { continue; }
+        fixed.push_str(&source[pos..fix.range.start]);
+        fixed.push_str(&fix.replacement);
+        pos = fix.range.end;
+        if fix.range.is_empty() { insertion_pos = Some(pos); }
+        applied_count += 1;
+    }
+    fixed.push_str(&source[pos..]);
+    return (fixed, applied_count);
+}
+
+/// Returns the `path:line:col` of the text which `quote` draws attention to. The path is
+/// looked up in `sources`.
+fn quote_header(quote: &AnnotatedSourceSection, sources: &SourceMap) -> String {
+    let pos = LineColumn::from_byte_pos(quote.source, quote.focus().start);
+    return format!("{}:{}:{}", sources.name(quote.file), pos.line, pos.column);
+}
+
+/// Prints `issue` along with its quotes. Each quote is headed by the path of the file it
+/// belongs to, as registered in `sources`.
+pub fn print_issue<'a>(issue: &Issue<'a>, sources: &SourceMap) {    
     print!("{}", BOLD);
     match issue.severity {
         Severity::Error => print!("{}(Error) {}{} ", FG_RED, issue.title, FG_DEFAULT),
@@ -190,16 +386,26 @@ pub fn print_issue<'a>(issue: &Issue<'a>, source_name: &str) {
     println!("{}", issue.subtext);
 
     println!();
-    println!("at {}:{}", source_name, issue.quote.first_line_no + 1);
+    println!("at {}", quote_header(&issue.quote, sources));
     print_src_quote(&issue.quote);
     println!();
 
+    if let Some(fix) = &issue.fix {
+        match fix.safe {
+            true => println!("Suggested fix ({}), applied by --fix...", fix.description),
+            false => println!("Suggested fix ({})...", fix.description),
+        }
+        println!();
+        print_fix_diff(issue.quote.source, fix);
+        println!();
+    }
+
     for elaboration in &issue.elaborations {
         match elaboration {
             Elaboration::SourceQuote(quote) => {
                 println!("{}", quote.caption);
                 println!();
-                println!("at {}:{}", source_name, quote.content.first_line_no + 1);
+                println!("at {}", quote_header(&quote.content, sources));
                 print_src_quote(&quote.content);
             },
             Elaboration::Quote(quote) => {
@@ -212,6 +418,47 @@ pub fn print_issue<'a>(issue: &Issue<'a>, source_name: &str) {
     }
 }
 
+/// The maximum number of removed, and of inserted, lines shown in a suggested fix.
+const FIX_DIFF_LINE_LIMIT: usize = 8;
+
+/// Prints the lines affected by `fix` as they are now, and as they would be after the fix.
+fn print_fix_diff(source: &str, fix: &Fix) {
+    let lines_begin = source[..fix.range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let lines_end = source[fix.range.end..].find('\n').map(|i| i + fix.range.end)
+        .unwrap_or(source.len());
+    let first_line_no = source[..lines_begin].matches('\n').count();
+    let before = &source[lines_begin..lines_end];
+    let after = format!("{}{}{}", &source[lines_begin..fix.range.start], fix.replacement,
+        &source[fix.range.end..lines_end]);
+    let before_lines: Vec<&str> = before.split('\n').collect();
+    let after_lines: Vec<&str> = after.split('\n').collect();
+    let line_no_len = (first_line_no + std::cmp::max(before_lines.len(), after_lines.len()))
+        .to_string().len();
+    for (symbol, color, lines) in [("-", FG_RED, &before_lines), ("+", FG_GREEN, &after_lines)] {
+        for (i, line) in lines.iter().take(FIX_DIFF_LINE_LIMIT).enumerate() {
+            println!("{}{}| {}{}{}", color, pad(&(first_line_no + i + 1).to_string(), line_no_len),
+                symbol, line, FG_DEFAULT);
+        }
+        if lines.len() > FIX_DIFF_LINE_LIMIT {
+            println!("and {} more line(s)...", lines.len() - FIX_DIFF_LINE_LIMIT);
+        }
+    }
+}
+
+/// Prints an error which occurred while accessing a file that is not itself a source file,
+/// and therefore cannot be quoted. For instance, a template file which does not exist.
+pub fn print_file_error(title: &str, subtext: &str, path: &std::path::Path, error: &std::io::Error) {
+    print!("{}", BOLD);
+    print!("{}(Error) {}{} ", FG_RED, title, FG_DEFAULT);
+    print!("{}", STOP_BOLD);
+    println!();
+    println!("{}", subtext);
+    println!();
+    println!("at {}", path.display());
+    println!("{}{}{}", FG_GREY, error, FG_DEFAULT);
+    println!();
+}
+
 pub enum Elaboration<'a> {
     SourceQuote(SourceQuoteElaboration<'a>),
     Quote(QuoteElaboration)
@@ -30,6 +30,23 @@ pub fn rewrite_subtree<'a>(node: &mut ctree::BlockChild<'a>,
                 }
             }
         },
+        ctree::BlockChild::DefinitionList(list) => {
+            for definition in &mut list.definitions {
+                for child in &mut definition.content.children {
+                    rewrite_subtree(child, rewrite_node);
+                }
+            }
+        },
+        ctree::BlockChild::TaggedBlock(tagged) => {
+            for child in &mut tagged.content.children {
+                rewrite_subtree(child, rewrite_node);
+            }
+        },
+        ctree::BlockChild::Admonition(admonition) => {
+            for child in &mut admonition.content.children {
+                rewrite_subtree(child, rewrite_node);
+            }
+        },
         _ => ()
     }
 }
@@ -48,6 +65,7 @@ pub fn rewrite_subtree_inline<'a>(node: &mut ctree::AnyInline<'a>,
     (rewrite_node)(node);
     let maybe_root = match node {
         ctree::AnyInline::Hyperlink (child_node) => Some(&mut child_node.child_root),
+        ctree::AnyInline::CrossReference(child_node) => Some(&mut child_node.child_root),
         ctree::AnyInline::Emboldened(child_node) => Some(&mut child_node.child_root),
         ctree::AnyInline::Italicized(child_node) => Some(&mut child_node.child_root),
         ctree::AnyInline::Underlined(child_node) => Some(&mut child_node.child_root),
@@ -10,6 +10,8 @@ pub struct SourceLocation {
 #[derive(Clone, Copy)]
 pub struct SourceSpan<'a> {
     pub source: &'a str,
+    /// The file which `source` was read from.
+    pub file: FileId,
     pub begin: SourceLocation,
     pub end: SourceLocation
 }
@@ -29,12 +31,34 @@ impl<'a> std::fmt::Debug for SourceSpan<'a> {
 }
 
 impl<'a> SourceSpan<'a> {
-    /// Creates a *limited* cursor and places it at the beginning of this span. 
+    /// Constructs a span containing the entirety of `source`.
+    pub fn whole(source: &'a str, file: FileId) -> Self {
+        let mut cursor = ForwardCursor::new(source, file);
+        while cursor.pop_grapheme().is_some() {}
+        return SourceSpan { source, file, begin: SourceLocation::default(), end: cursor.pos() };
+    }
+
+    /// Constructs a span containing the bytes of `source` within `range`, which must begin and
+    /// end at character boundaries. This is used to quote text located by another parser.
+    pub fn from_range(source: &'a str, file: FileId, range: std::ops::Range<usize>) -> Self {
+        return SourceSpan { source, file, begin: locate(source, range.start),
+            end: locate(source, range.end) };
+    }
+
+    /// Creates a *limited* cursor and places it at the beginning of this span.
     pub fn begin<'b>(&'b self) -> ForwardCursor<'a> {
-        ForwardCursor { source: self.source, pos: self.begin, limit: self.end.byte_pos }
+        ForwardCursor { source: self.source, file: self.file, pos: self.begin, limit: self.end.byte_pos }
     }
 }
 
+/// Returns the location of the byte at `byte_pos` in `source`.
+fn locate(source: &str, byte_pos: usize) -> SourceLocation {
+    let before = &source[..byte_pos];
+    let line_begin = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    return SourceLocation { byte_pos, line_pos: before.matches('\n').count(),
+        colu_pos: before[line_begin..].width() };
+}
+
 /// A cursor in some source text. 
 ///
 /// - Methods beginning with `at` are *peeking*, they do not advance the cursor.
@@ -47,10 +71,12 @@ impl<'a> SourceSpan<'a> {
 pub struct ForwardCursor<'a> {
     pos: SourceLocation,
     pub source: &'a str,
+    pub file: FileId,
     pub limit: usize
 }
 
 use unicode_segmentation::UnicodeSegmentation;
+use crate::source_map::FileId;
 use unicode_width::UnicodeWidthStr;
 
 impl<'a> ForwardCursor<'a> {
@@ -64,7 +90,7 @@ impl<'a> ForwardCursor<'a> {
             self.pos.colu_pos += 1;
         }
         let end = self.pos;
-        return SourceSpan { source: self.source, begin, end };
+        return SourceSpan { source: self.source, file: self.file, begin, end };
     }
 
     /// Returns true if and only if `pred` is subsequent to the cursor.
@@ -83,7 +109,7 @@ impl<'a> ForwardCursor<'a> {
             self.pos.byte_pos += grapheme.len();
             self.pos.colu_pos += grapheme.width();
         }
-        return Some(SourceSpan { source: self.source, begin, end: self.pos() });
+        return Some(SourceSpan { source: self.source, file: self.file, begin, end: self.pos() });
     }
 
     pub fn repeat_match_symbol(&mut self, pred: &str) -> usize {
@@ -103,7 +129,7 @@ impl<'a> ForwardCursor<'a> {
     pub fn match_scan<'b>(&'b mut self, predicate: impl Scan) -> Option<SourceSpan<'a>> {
         let mut tmp_cursor = self.clone();
         if !predicate.scan(&mut tmp_cursor) { return None }
-        let span = SourceSpan { source: self.source, begin: self.pos(), end: tmp_cursor.pos() };
+        let span = SourceSpan { source: self.source, file: self.file, begin: self.pos(), end: tmp_cursor.pos() };
         *self = tmp_cursor;
         return Some(span);        
     }
@@ -111,7 +137,7 @@ impl<'a> ForwardCursor<'a> {
     pub fn at_scan(&mut self, predicate: impl Scan) -> Option<SourceSpan<'a>> {
         let mut tmp_cursor = self.clone();
         if !predicate.scan(&mut tmp_cursor) { return None; }
-        let span = SourceSpan { source: self.source, begin: self.pos(), end: tmp_cursor.pos() };
+        let span = SourceSpan { source: self.source, file: self.file, begin: self.pos(), end: tmp_cursor.pos() };
         return Some(span);
     }
 
@@ -133,7 +159,7 @@ impl<'a> ForwardCursor<'a> {
         }
         let begin = self.pos();
         self.pos = self_end;
-        return SourceSpan { source: self.source, begin, end: span_end };
+        return SourceSpan { source: self.source, file: self.file, begin, end: span_end };
     }
 
     pub fn pos(&self) -> SourceLocation { self.pos }
@@ -160,8 +186,8 @@ impl<'a> ForwardCursor<'a> {
         return Some(&self.source[begin_bpos..end_bpos]);
     }
 
-    pub fn new(source: &'a str) -> Self {
-        Self { source, pos: SourceLocation::default(), limit: source.len() }
+    pub fn new(source: &'a str, file: FileId) -> Self {
+        Self { source, file, pos: SourceLocation::default(), limit: source.len() }
     }
 
     // Internal
@@ -0,0 +1,79 @@
+// Searches the index written by `merryc --search`. Include this script in a template after a
+// search box and a list for the results, identified as below.
+//
+//     <input type="search" id="merry-search" placeholder="Search">
+//     <ul id="merry-search-results"></ul>
+//     <script src="{{root}}/search.js"></script>
+//
+// The index is fetched the first time the search box is used. Since browsers do not allow
+// fetching local files, the site must be served over HTTP.
+(function () {
+    const MAX_RESULTS = 20;
+    const EXCERPT_LENGTH = 160;
+
+    const root = new URL(".", document.currentScript.src);
+    const input = document.getElementById("merry-search");
+    const results = document.getElementById("merry-search-results");
+    if (!input || !results) return;
+
+    let index = null;
+
+    function loadIndex() {
+        if (index === null) {
+            index = fetch(new URL("search.json", root)).then(response => response.json());
+        }
+        return index;
+    }
+
+    // A section matches when every term appears within it. Terms found in the heading count
+    // for more than those found in the text.
+    function score(entry, terms) {
+        const heading = entry.heading.toLowerCase();
+        const text = entry.text.toLowerCase();
+        let score = 0;
+        for (const term of terms) {
+            if (heading.includes(term)) score += 10;
+            else if (text.includes(term)) score += 1;
+            else return 0;
+        }
+        return score;
+    }
+
+    function excerpt(text, term) {
+        const at = Math.max(0, text.toLowerCase().indexOf(term) - EXCERPT_LENGTH / 4);
+        const slice = text.slice(at, at + EXCERPT_LENGTH);
+        return (at > 0 ? "…" : "") + slice + (at + EXCERPT_LENGTH < text.length ? "…" : "");
+    }
+
+    function render(entries, query) {
+        results.replaceChildren();
+        const terms = query.toLowerCase().split(/\s+/).filter(term => term.length > 0);
+        if (terms.length === 0) return;
+        const matches = entries
+            .map(entry => ({ entry, score: score(entry, terms) }))
+            .filter(match => match.score > 0)
+            .sort((a, b) => b.score - a.score)
+            .slice(0, MAX_RESULTS);
+        for (const { entry } of matches) {
+            const item = document.createElement("li");
+            const link = document.createElement("a");
+            link.href = new URL(entry.url, root).href;
+            link.textContent = entry.heading === entry.page
+                ? entry.page : entry.page + " › " + entry.heading;
+            item.appendChild(link);
+            if (entry.text.length > 0) {
+                const paragraph = document.createElement("p");
+                paragraph.textContent = excerpt(entry.text, terms[0]);
+                item.appendChild(paragraph);
+            }
+            results.appendChild(item);
+        }
+    }
+
+    input.addEventListener("input", () => {
+        const query = input.value;
+        loadIndex().then(entries => {
+            if (input.value === query) render(entries, query);
+        });
+    });
+})();
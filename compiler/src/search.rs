@@ -0,0 +1,136 @@
+//! This module implements the client-side search index. When a directory is compiled with the
+//! search index enabled, the text of every section of every document is written to `search.json`
+//! in the output directory, along with `search.js`, a small script which searches the index from
+//! within the browser.
+//!
+//! The index is an array of entries, one for each section, in order of appearance.
+//!
+//! ```json
+//! [{ "url": "guide/install.html#usage", "page": "Installation", "heading": "Usage",
+//!    "text": "Run merryc with..." }]
+//! ```
+//!
+//! The text of a section excludes the text of its subsections, which have entries of their own.
+//! The content preceding the first section of a document is given an entry whose heading is the
+//! title of the document. Code blocks are not indexed, and neither is embedded HTML, such as
+//! rewritten math, unless `include_html` is set.
+
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::ctree::{self, write_html_text, BlockChild, InlineRoot};
+use crate::misc::relative_url_path;
+
+pub const SEARCH_INDEX_FILE_NAME: &str = "search.json";
+pub const SEARCH_SCRIPT_FILE_NAME: &str = "search.js";
+
+/// The script which searches the index. See the file itself for usage.
+const SEARCH_SCRIPT: &str = include_str!("search.js");
+
+#[derive(Serialize)]
+struct SearchEntry {
+    /// The URL of the section relative to the output directory.
+    url: String,
+    /// The title of the document containing the section.
+    page: String,
+    heading: String,
+    text: String
+}
+
+pub struct SearchIndex {
+    entries: Vec<SearchEntry>,
+    /// Whether the text of embedded HTML is indexed. Since that text is not known until
+    /// code-generation, the HTML is written a second time for the index.
+    include_html: bool
+}
+
+impl SearchIndex {
+    pub fn new(include_html: bool) -> Self {
+        return SearchIndex { entries: Vec::new(), include_html };
+    }
+
+    /// Indexes the sections of `root`, the document at `page_path`, relative to the output
+    /// directory.
+    pub fn add_page<'a>(&mut self, page_path: &Path, root: &ctree::Root<'a>) {
+        let url = relative_url_path(Path::new(""), page_path);
+        let title = root.title().unwrap_or_else(|| {
+            page_path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+        });
+        let position = self.entries.len();
+        let mut text = String::new();
+        self.index_children(&root.block.children, &url, &title, &mut text);
+        if text.trim().len() > 0 {
+            self.entries.insert(position, SearchEntry { url, heading: title.clone(), page: title,
+                text: normalize_whitespace(&text) });
+        }
+    }
+
+    /// Appends the text of `children` to `text`, and adds an entry for every section among them.
+    fn index_children<'a>(&mut self, children: &[BlockChild<'a>], url: &str, title: &str,
+        text: &mut String)
+    {
+        for child in children {
+            match child {
+                BlockChild::Section(section) => {
+                    // The entry of a section precedes the entries of its subsections.
+                    let position = self.entries.len();
+                    let mut section_text = String::new();
+                    self.index_children(&section.children, url, title, &mut section_text);
+                    let mut heading = String::new();
+                    self.write_inline_text(&section.heading.content, &mut heading);
+                    self.entries.insert(position, SearchEntry {
+                        url: format!("{}#{}", url, section.id),
+                        page: title.to_string(),
+                        heading: normalize_whitespace(&heading),
+                        text: normalize_whitespace(&section_text)
+                    });
+                },
+                BlockChild::Paragraph(paragraph) => self.write_inline_text(&paragraph.content, text),
+                BlockChild::Heading(heading) => self.write_inline_text(&heading.content, text),
+                BlockChild::List(list) => for element in &list.elements {
+                    self.index_children(&element.content.children, url, title, text);
+                },
+                BlockChild::DefinitionList(list) => for definition in &list.definitions {
+                    self.write_inline_text(&definition.term, text);
+                    self.index_children(&definition.content.children, url, title, text);
+                },
+                BlockChild::Block(block) => self.index_children(&block.children, url, title, text),
+                BlockChild::TaggedBlock(node) => {
+                    self.index_children(&node.content.children, url, title, text);
+                },
+                BlockChild::Admonition(node) => {
+                    self.index_children(&node.content.children, url, title, text);
+                },
+                BlockChild::HTML(node) => if self.include_html {
+                    write_html_text(node.value.as_ref(), text);
+                    text.push(' ');
+                },
+                BlockChild::Verbatim(_) => (),
+                BlockChild::CodeSnippet(_) => (),
+                BlockChild::ThematicBreak(_) => (),
+                BlockChild::None => (),
+            }
+        }
+    }
+
+    fn write_inline_text<'a>(&self, root: &InlineRoot<'a>, out: &mut String) {
+        root.write_text(self.include_html, out);
+        out.push(' ');
+    }
+
+    pub fn entry_count(&self) -> usize { self.entries.len() }
+
+    /// Writes the index and the script which searches it into `dest_dir_path`. Returns the paths
+    /// of the files written.
+    pub fn write(&self, dest_dir_path: &Path) -> std::io::Result<[PathBuf; 2]> {
+        let index_path = dest_dir_path.join(SEARCH_INDEX_FILE_NAME);
+        let index = serde_json::to_string(&self.entries).map_err(std::io::Error::other)?;
+        std::fs::write(&index_path, index)?;
+        let script_path = dest_dir_path.join(SEARCH_SCRIPT_FILE_NAME);
+        std::fs::write(&script_path, SEARCH_SCRIPT)?;
+        return Ok([index_path, script_path]);
+    }
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    return text.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
@@ -0,0 +1,225 @@
+//! This module implements site navigation. Before [`crate::compile::compile_dir`] compiles
+//! anything, every document within the source directory is indexed in reading order: the index
+//! page of a directory comes first, followed by its documents and subdirectories in order of name.
+//! Each page can then link to the pages before and after it, and to the index pages of the
+//! directories containing it. A directory which contains documents but no `index.md2` is given
+//! a generated index page listing its children.
+
+use std::path::{Path, PathBuf};
+use crate::builtins::document_title;
+use crate::ctree::{self, AnyInline, Block, BlockChild, Heading, HyperlinkText, InlineRoot, List,
+    ListElement, Metadata, Paragraph, PlainText, Section};
+use crate::misc::relative_url_path;
+use crate::scan::SourceSpan;
+use crate::source_map::{SourceCache, SourceMap};
+
+/// The name of the source file which becomes the index page of its directory.
+pub const INDEX_FILE_NAME: &str = "index.md2";
+
+/// A finished document within the output directory.
+pub struct SitePage {
+    /// The path of the finished document relative to the output directory.
+    pub path: PathBuf,
+    /// The title of the document, or if it has none, the name of its source file. The title
+    /// of a generated index page is the name of its directory.
+    pub title: String,
+    /// Whether this is an index page generated by the build, rather than compiled from source.
+    pub generated: bool
+}
+
+/// Every page of the site in reading order.
+pub struct Site {
+    pages: Vec<SitePage>,
+    extension: &'static str
+}
+
+/// The position of a page within the site, expressed as links relative to that page.
+#[derive(Clone, Default)]
+pub struct Navigation {
+    pub prev: Option<NavLink>,
+    pub next: Option<NavLink>,
+    /// The index pages of the directories containing the page, outermost first, followed by
+    /// the page itself.
+    pub breadcrumbs: Vec<NavLink>
+}
+
+#[derive(Clone)]
+pub struct NavLink { pub title: String, pub url: String }
+
+impl Site {
+    /// Indexes every md2 source file within `src_dir_path` (recursively), except those within
+    /// `excluded_dir_path`, the canonical path of the output directory. Every document is
+    /// interpreted to find its title, but its problems are not reported.
+    pub fn index(src_dir_path: &Path, excluded_dir_path: &Path, extension: &'static str,
+        sources: &SourceCache) -> std::io::Result<Self>
+    {
+        let mut site = Site { pages: Vec::new(), extension };
+        let root_name = src_dir_path.canonicalize()?.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("Index"));
+        site.index_dir(src_dir_path, Path::new(""), root_name, excluded_dir_path, sources)?;
+        return Ok(site);
+    }
+
+    fn index_dir(&mut self, src_dir_path: &Path, relative_dir_path: &Path, dir_name: String,
+        excluded_dir_path: &Path, sources: &SourceCache) -> std::io::Result<()>
+    {
+        let index_position = self.pages.len();
+        let index_source_path = src_dir_path.join(INDEX_FILE_NAME);
+        let has_index = index_source_path.is_file();
+        let title = match has_index {
+            true => document_title(&index_source_path, sources)?.unwrap_or(dir_name),
+            false => dir_name
+        };
+        self.pages.push(SitePage { path: self.index_path(relative_dir_path), title,
+            generated: !has_index });
+
+        let mut entries: Vec<std::fs::DirEntry> = std::fs::read_dir(src_dir_path)?
+            .collect::<std::io::Result<_>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let relative_path = relative_dir_path.join(&name);
+            if entry.file_type()?.is_dir() {
+                if entry.path().canonicalize()? == excluded_dir_path { continue; }
+                self.index_dir(&entry.path(), &relative_path, name, excluded_dir_path, sources)?;
+                continue;
+            }
+            let Some(stem) = name.strip_suffix(".md2") else { continue; };
+            if name == INDEX_FILE_NAME { continue; }
+            let title = document_title(&entry.path(), sources)?
+                .unwrap_or_else(|| stem.to_string());
+            let mut path = relative_path.clone();
+            path.set_extension(self.extension);
+            self.pages.push(SitePage { path, title, generated: false });
+        }
+
+        // A directory without any documents within it is not a part of the site.
+        if !has_index && self.pages.len() == index_position + 1 { self.pages.pop(); }
+        return Ok(());
+    }
+
+    /// Returns the path of the index page of the directory at `relative_dir_path`.
+    fn index_path(&self, relative_dir_path: &Path) -> PathBuf {
+        let mut path = relative_dir_path.join("index");
+        path.set_extension(self.extension);
+        return path;
+    }
+
+    /// Returns every page of the site in reading order.
+    pub fn pages(&self) -> &[SitePage] { &self.pages }
+
+    /// Returns the title of the index page of the output directory, if there is one.
+    pub fn title(&self) -> Option<&str> {
+        let index_path = self.index_path(Path::new(""));
+        return self.pages.first()
+            .filter(|page| page.path == index_path)
+            .map(|page| page.title.as_str());
+    }
+
+    /// Returns every page which is generated by the build rather than compiled from source.
+    pub fn generated_pages(&self) -> impl Iterator<Item = &SitePage> {
+        self.pages.iter().filter(|page| page.generated)
+    }
+
+    /// Returns the generated index page of the directory at `relative_dir_path`, if any.
+    pub fn generated_index(&self, relative_dir_path: &Path) -> Option<&SitePage> {
+        let path = self.index_path(relative_dir_path);
+        return self.generated_pages().find(|page| page.path == path);
+    }
+
+    /// Returns the position of the page at `path`, relative to the output directory. A page
+    /// which was not indexed has no neighbours.
+    pub fn navigation(&self, path: &Path) -> Navigation {
+        let Some(position) = self.pages.iter().position(|page| page.path == path) else {
+            return Navigation::default();
+        };
+        let from = parent_dir(path);
+        let link = |page: &SitePage| NavLink { title: page.title.clone(),
+            url: relative_url_path(from, &page.path) };
+        let mut breadcrumbs: Vec<NavLink> = Vec::new();
+        for ancestor in from.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            let index_path = self.index_path(ancestor);
+            if let Some(page) = self.pages.iter().find(|page| page.path == index_path) {
+                breadcrumbs.push(link(page));
+            }
+        }
+        if path != self.index_path(from) { breadcrumbs.push(link(&self.pages[position])); }
+        return Navigation {
+            prev: position.checked_sub(1).map(|prev| link(&self.pages[prev])),
+            next: self.pages.get(position + 1).map(link),
+            breadcrumbs
+        };
+    }
+
+    /// Returns the pages listed by the index page of the directory at `relative_dir_path`.
+    /// These are the documents within that directory, and the index pages of its immediate
+    /// subdirectories, in reading order.
+    fn children(&self, relative_dir_path: &Path) -> Vec<&SitePage> {
+        let own_index_path = self.index_path(relative_dir_path);
+        return self.pages.iter()
+            .filter(|page| page.path != own_index_path)
+            .filter(|page| {
+                let dir = parent_dir(&page.path);
+                match page.path == self.index_path(dir) {
+                    true => dir.parent() == Some(relative_dir_path),
+                    false => dir == relative_dir_path
+                }
+            })
+            .collect();
+    }
+
+    /// Constructs the *CTree* of the generated index page `page`. The page consists of a heading
+    /// followed by a list of links to its children. The text of the page is registered in
+    /// `sources` under `output_path`, the path of the finished page.
+    pub fn make_index_page<'a>(&self, page: &SitePage, output_path: PathBuf, sources: &'a SourceMap)
+    -> ctree::Root<'a>
+    {
+        let children = self.children(parent_dir(&page.path));
+        let from = parent_dir(&page.path);
+        let mut text = page.title.clone();
+        let mut ranges: Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> = Vec::new();
+        for child in &children {
+            text.push('\n');
+            let title_begin = text.len();
+            text.push_str(&child.title);
+            let title_range = title_begin..text.len();
+            text.push('\n');
+            let url_begin = text.len();
+            text.push_str(&relative_url_path(from, &child.path));
+            ranges.push((title_range, url_begin..text.len()));
+        }
+        let (file, source) = sources.add(output_path, text);
+        let title = SourceSpan::from_range(source, file, 0..page.title.len());
+
+        let elements: Vec<ListElement<'a>> = ranges.into_iter().map(|(title_range, url_range)| {
+            let link = HyperlinkText {
+                href: SourceSpan::from_range(source, file, url_range),
+                title: None,
+                child_root: plain_text(SourceSpan::from_range(source, file, title_range))
+            };
+            let content = InlineRoot { children: vec![AnyInline::Hyperlink(link)] };
+            let paragraph = BlockChild::Paragraph(Paragraph { content });
+            return ListElement { content: Block { children: vec![paragraph], indent: false },
+                task: None };
+        }).collect();
+        let section = Section {
+            heading: Heading { hlevel: 1, content: plain_text(title) },
+            children: vec![BlockChild::List(List { elements })],
+            id: ctree::slugify(&page.title)
+        };
+        return ctree::Root {
+            block: Block { children: vec![BlockChild::Section(section)], indent: false },
+            metadata: Metadata { title: Some(title), ..Metadata::default() }
+        };
+    }
+}
+
+fn plain_text<'a>(span: SourceSpan<'a>) -> InlineRoot<'a> {
+    return InlineRoot { children: vec![AnyInline::Plain(PlainText { span })] };
+}
+
+/// Returns the directory containing the page at `path`, which is relative to the output directory.
+fn parent_dir(path: &Path) -> &Path {
+    return path.parent().unwrap_or(Path::new(""));
+}
@@ -0,0 +1,120 @@
+//! This module implements the source map, a registry of every source file read during a build.
+//! Each file is identified by a [`FileId`] which is stored in every [`crate::scan::SourceSpan`],
+//! so that a single issue may quote multiple files, each beneath its own `path:line:col` header.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Identifies a file registered in a [`SourceMap`].
+///
+/// The default identifier, [`FileId::ANONYMOUS`], denotes source text which was never
+/// registered, for instance a document compiled from memory. Identifiers are ordered by
+/// registration.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct FileId(usize);
+
+impl FileId {
+    pub const ANONYMOUS: FileId = FileId(0);
+}
+
+/// The name printed in place of a path when quoting source text which was never registered.
+pub const ANONYMOUS_NAME: &str = "<anonymous>";
+
+/// A registry of source files. The text of each registered file is owned by the map and lives
+/// as long as it does, so files may be added while spans into previously added files are live.
+#[derive(Default)]
+pub struct SourceMap {
+    texts: typed_arena::Arena<String>,
+    paths: RefCell<Vec<PathBuf>>
+}
+
+impl SourceMap {
+    pub fn new() -> Self { Self::default() }
+
+    /// Registers the file at `path` and takes ownership of its `text`.
+    pub fn add(&self, path: PathBuf, text: String) -> (FileId, &str) {
+        let file = self.add_path(path);
+        let text = self.texts.alloc(text);
+        return (file, text.as_str());
+    }
+
+    /// Registers the file at `path` without taking ownership of its text. This is used for files
+    /// which outlive the map, for instance a template shared by every document in a directory.
+    pub fn add_path(&self, path: PathBuf) -> FileId {
+        let mut paths = self.paths.borrow_mut();
+        paths.push(path);
+        return FileId(paths.len());
+    }
+
+    /// Returns the path of the given file, or `None` if the file is anonymous.
+    pub fn path(&self, file: FileId) -> Option<PathBuf> {
+        if file == FileId::ANONYMOUS { return None; }
+        return self.paths.borrow().get(file.0 - 1).cloned();
+    }
+
+    /// Returns the name identifying the given file in diagnostics.
+    pub fn name(&self, file: FileId) -> String {
+        match self.path(file) {
+            Some(path) => path.to_string_lossy().into_owned(),
+            None => String::from(ANONYMOUS_NAME)
+        }
+    }
+
+    /// Returns the identifier of the first file registered at `path`, if any.
+    pub fn find(&self, path: &Path) -> Option<FileId> {
+        let paths = self.paths.borrow();
+        let index = paths.iter().position(|registered| registered == path)?;
+        return Some(FileId(index + 1));
+    }
+}
+
+/// A file registered in a [`SourceMap`], along with its text.
+type Registration<'s> = (FileId, &'s str);
+
+/// Registers files in a [`SourceMap`] at most once. A build reads some text again and again,
+/// for instance a document referenced by every page, or the directives of the project
+/// configuration, which act on every document. Each is registered the first time it is read,
+/// and the registration is reused thereafter.
+pub struct SourceCache<'s> {
+    sources: &'s SourceMap,
+    /// The files read from disk, by canonical path.
+    files: RefCell<HashMap<PathBuf, Registration<'s>>>,
+    /// The text which was not read from disk, by the path it was registered under.
+    texts: RefCell<HashMap<(PathBuf, String), Registration<'s>>>
+}
+
+impl<'s> SourceCache<'s> {
+    pub fn new(sources: &'s SourceMap) -> Self {
+        return SourceCache { sources, files: RefCell::default(), texts: RefCell::default() };
+    }
+
+    pub fn map(&self) -> &'s SourceMap { self.sources }
+
+    /// Reads and registers the file at `path`, unless it was read before.
+    pub fn read(&self, path: &Path) -> std::io::Result<(FileId, &'s str)> {
+        let key = path.canonicalize()?;
+        if let Some(registered) = self.files.borrow().get(&key) { return Ok(*registered); }
+        let text = std::fs::read_to_string(path)?;
+        return Ok(self.update(path, text));
+    }
+
+    /// Registers `text` as the content of the file at `path`, which has changed since it was
+    /// read, so that it is not read again.
+    pub fn update(&self, path: &Path, text: String) -> (FileId, &'s str) {
+        let registered = self.sources.add(path.to_path_buf(), text);
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.files.borrow_mut().insert(key, registered);
+        return registered;
+    }
+
+    /// Registers `text`, which was not read from disk, under `path`, unless the same text was
+    /// registered under that path before.
+    pub fn add(&self, path: &Path, text: &str) -> (FileId, &'s str) {
+        let key = (path.to_path_buf(), text.to_string());
+        if let Some(registered) = self.texts.borrow().get(&key) { return *registered; }
+        let registered = self.sources.add(path.to_path_buf(), text.to_string());
+        self.texts.borrow_mut().insert(key, registered);
+        return registered;
+    }
+}
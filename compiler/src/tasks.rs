@@ -0,0 +1,80 @@
+//! This module implements the *tasks report*. The report lists every unchecked task list
+//! element in a source file, or in every source file within a directory, along with the
+//! location of each task in the source text.
+
+use std::path::Path;
+use crate::ctree::{self, make_ctree, Environment};
+use crate::ltree::make_ltree;
+use crate::mtree::make_mtree;
+use crate::source_map::{FileId, SourceCache, SourceMap};
+use crate::report::Issue;
+use crate::rewrite::rewrite_subtrees;
+
+pub struct OpenTask {
+    /// The file containing the task. This differs from the file being reported on when the
+    /// task was included from another file.
+    pub file: FileId,
+    pub line_no: usize,
+    /// The plain text of the first paragraph in the task list element.
+    pub summary: String
+}
+
+/// Returns every unchecked task within the given *CTree* ordered by position in the source text.
+pub fn find_open_tasks<'a>(root: &mut ctree::Root<'a>) -> Vec<OpenTask> {
+    let mut tasks: Vec<OpenTask> = Vec::new();
+    rewrite_subtrees(&mut root.block, &mut |node| {
+        let ctree::BlockChild::List(list) = node else { return; };
+        for element in &list.elements {
+            let Some(task) = element.task else { continue; };
+            if task.checked { continue; }
+            let mut summary = String::new();
+            if let Some(ctree::BlockChild::Paragraph(p)) = element.content.children.first() {
+                p.content.write_plain_text(&mut summary);
+            }
+            let summary = summary.trim().to_string();
+            tasks.push(OpenTask { file: task.marker.file, line_no: task.marker.begin.line_pos + 1,
+                summary });
+        }
+    });
+    tasks.sort_by_key(|task| (task.file, task.line_no));
+    return tasks;
+}
+
+/// Prints the open tasks in every md2 source file within `src_dir_path` (recursively).
+pub fn report_tasks_dir(src_dir_path: &Path) -> std::io::Result<()> {
+    let mut entries: Vec<std::fs::DirEntry> = Vec::new();
+    for entry_result in std::fs::read_dir(src_dir_path)? { entries.push(entry_result?); }
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        if entry.file_type()?.is_dir() {
+            report_tasks_dir(&entry.path())?;
+            continue;
+        }
+        if entry.file_type()?.is_file() {
+            if let Some(extension) = entry.path().extension() {
+                if extension == "md2" { report_tasks_file(&entry.path())?; }
+            }
+        }
+    }
+    return Ok(());
+}
+
+/// Prints the open tasks in the given md2 source file, one per line, in the form 
+/// `path:line: summary`. 
+pub fn report_tasks_file(input_file: &Path) -> std::io::Result<()> {
+    let sources = SourceMap::new();
+    let (file, source_text) = sources.add(input_file.to_path_buf(), 
+        std::fs::read_to_string(input_file)?);
+    let ltree = make_ltree(source_text, file);
+    let mtree = make_mtree(&ltree);
+    // Syntax issues are not the concern of this report. They are shown during compilation.
+    let mut issues: Vec<Issue> = Vec::new();
+    let cwd = input_file.parent().unwrap().to_path_buf();
+    let mut ctree = make_ctree(mtree, &mut issues, cwd, &SourceCache::new(&sources), 
+        &Environment::default());
+    for task in find_open_tasks(&mut ctree) {
+        println!("{}:{}: {}", sources.name(task.file), task.line_no, task.summary);
+    }
+    return Ok(());
+}
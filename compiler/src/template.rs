@@ -0,0 +1,290 @@
+//! This module implements page templates. A template is an HTML file containing placeholders
+//! which are substituted with the content of a document during code-generation.
+//!
+//! - `{{title}}` is replaced with the title of the document.
+//! - `{{body}}` is replaced with the content of the document.
+//! - `{{toc}}` is replaced with a table of contents linking to every section in the document.
+//! - `{{meta}}` is replaced with the `<meta>` elements describing the document.
+//! - `{{meta.<name>}}` is replaced with the value of the given metadata, for instance
+//!   `{{meta.author}}`, or nothing if the document does not declare it.
+//! - `{{lang}}` is replaced with the language of the document.
+//! - `{{root}}` is replaced with the relative path from the document to the root of the
+//!   output directory. For instance `{{root}}/style.css`.
+//! - `{{date}}` is replaced with the date the document was compiled.
+//! - `{{head}}` is replaced with the contents of the head file given by the project
+//!   configuration, if any.
+//! - `{{prev}}` and `{{next}}` are replaced with links to the pages before and after the document
+//!   in reading order, and `{{breadcrumbs}}` with the trail of index pages leading to it. The
+//!   parts of these links are available separately too, for instance `{{prev.url}}` and
+//!   `{{prev.title}}`. These placeholders are replaced with nothing unless a whole directory
+//!   is compiled.
+//!
+//! A template must contain exactly one `{{body}}` placeholder.
+
+use std::path::Path;
+use crate::codegen_html::{codegen_block, codegen_breadcrumbs, codegen_metadata, codegen_nav_link, 
+    codegen_toc, write_escaped};
+use crate::ctree;
+use crate::report::{Issue, AnnotatedSourceSection, Severity};
+use crate::scan::{ForwardCursor, SourceLocation, SourceSpan};
+use crate::site::{NavLink, Navigation};
+use crate::source_map::{FileId, SourceMap};
+
+pub struct Template {
+    pub source: String,
+    pub file: FileId,
+    segments: Vec<Segment>,
+    issues: Vec<AnyTemplateIssue>
+}
+
+enum Segment {
+    Literal(std::ops::Range<usize>),
+    Placeholder(Placeholder)
+}
+
+enum Placeholder {
+    Title,
+    Body,
+    Toc,
+    Meta,
+    MetaValue(String),
+    Lang,
+    Root,
+    Date,
+    Head,
+    Prev,
+    PrevUrl,
+    PrevTitle,
+    Next,
+    NextUrl,
+    NextTitle,
+    Breadcrumbs
+}
+
+/// Variables which are not derived from the document itself, but from its place in the build.
+pub struct PageContext {
+    /// The relative path from the output file to the root of the output directory.
+    pub root_path: String,
+    /// The date of compilation formatted as `YYYY-MM-DD`.
+    pub build_date: String,
+    /// HTML given by the project configuration, to be inserted into the `<head>` of the page.
+    pub head: String,
+    /// The position of the page within the site, if a whole directory is being compiled.
+    pub navigation: Navigation
+}
+
+impl Template {
+    /// Reads and parses the template at `path`, registering it in `sources` so that its
+    /// issues are quoted beneath its path.
+    pub fn load(path: &Path, sources: &SourceMap) -> std::io::Result<Self> {
+        let source = std::fs::read_to_string(path)?;
+        return Ok(Self::new(source, sources.add_path(path.to_path_buf())));
+    }
+
+    pub fn new(source: String, file: FileId) -> Self {
+        let (segments, issues) = parse_template(&source, file);
+        return Template { source, file, segments, issues };
+    }
+
+    /// Returns the problems found in the template text. The quotes contained within these
+    /// issues refer to the template text, not the document being compiled.
+    pub fn issues<'a>(&'a self) -> Vec<Issue<'a>> {
+        self.issues.iter().map(|issue| issue.to_issue(&self.source, self.file)).collect()
+    }
+
+    /// Substitutes the placeholders in this template with the content of the document
+    /// and writes the result to `out`.
+    pub fn render<'a, W>(&self, root: &ctree::Root<'a>, page: &PageContext, out: &mut W,
+        issues: &mut Vec<Issue<'a>>) -> std::io::Result<()> where W: std::io::Write
+    {
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(range) => out.write_all(&self.source.as_bytes()[range.clone()])?,
+                Segment::Placeholder(Placeholder::Title) => {
+                    if let Some(title) = root.title() { write_escaped(&title, out)?; }
+                },
+                Segment::Placeholder(Placeholder::Body) => codegen_block(&root.block, out, issues)?,
+                Segment::Placeholder(Placeholder::Toc) => codegen_toc(root, out)?,
+                Segment::Placeholder(Placeholder::Meta) => codegen_metadata(root, out)?,
+                Segment::Placeholder(Placeholder::MetaValue(name)) => {
+                    if let Some(value) = root.metadata.get(name) {
+                        write_escaped(value.as_ref(), out)?;
+                    }
+                },
+                Segment::Placeholder(Placeholder::Lang) => {
+                    if let Some(lang) = root.metadata.lang { write_escaped(lang.as_ref(), out)?; }
+                },
+                Segment::Placeholder(Placeholder::Root) => write_escaped(&page.root_path, out)?,
+                Segment::Placeholder(Placeholder::Date) => write_escaped(&page.build_date, out)?,
+                Segment::Placeholder(Placeholder::Head) => out.write_all(page.head.as_bytes())?,
+                Segment::Placeholder(Placeholder::Prev) => {
+                    codegen_nav_link(page.navigation.prev.as_ref(), "prev", out)?;
+                },
+                Segment::Placeholder(Placeholder::PrevUrl) => {
+                    write_nav_link_part(page.navigation.prev.as_ref(), |link| &link.url, out)?;
+                },
+                Segment::Placeholder(Placeholder::PrevTitle) => {
+                    write_nav_link_part(page.navigation.prev.as_ref(), |link| &link.title, out)?;
+                },
+                Segment::Placeholder(Placeholder::Next) => {
+                    codegen_nav_link(page.navigation.next.as_ref(), "next", out)?;
+                },
+                Segment::Placeholder(Placeholder::NextUrl) => {
+                    write_nav_link_part(page.navigation.next.as_ref(), |link| &link.url, out)?;
+                },
+                Segment::Placeholder(Placeholder::NextTitle) => {
+                    write_nav_link_part(page.navigation.next.as_ref(), |link| &link.title, out)?;
+                },
+                Segment::Placeholder(Placeholder::Breadcrumbs) => {
+                    codegen_breadcrumbs(&page.navigation, out)?;
+                },
+            }
+        }
+        return Ok(());
+    }
+}
+
+fn write_nav_link_part<W>(link: Option<&NavLink>, part: impl Fn(&NavLink) -> &String, out: &mut W)
+-> std::io::Result<()> where W: std::io::Write
+{
+    if let Some(link) = link { write_escaped(part(link), out)?; }
+    return Ok(());
+}
+
+fn parse_template(source: &str, file: FileId) -> (Vec<Segment>, Vec<AnyTemplateIssue>) {
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut issues: Vec<AnyTemplateIssue> = Vec::new();
+    let mut cursor = ForwardCursor::new(source, file);
+    let mut literal_begin: usize = 0;
+    let mut body_count: usize = 0;
+
+    loop {
+        if cursor.is_end() { break; }
+        if !cursor.at_symbol("{{") {
+            cursor.pop_grapheme();
+            continue;
+        }
+        let begin = cursor.pos();
+        segments.push(Segment::Literal(literal_begin..begin.byte_pos));
+        cursor.match_symbol("{{");
+        let name_begin = cursor.pos().byte_pos;
+        while !cursor.is_end() && !cursor.at_symbol("}}") { cursor.pop_grapheme(); }
+        let name_end = cursor.pos().byte_pos;
+        if cursor.match_symbol("}}").is_none() {
+            issues.push(AnyTemplateIssue::UnclosedPlaceholder(begin));
+            literal_begin = name_end;
+            break;
+        }
+        literal_begin = cursor.pos().byte_pos;
+        let name = source[name_begin..name_end].trim();
+        let placeholder = match name {
+            "title" => Placeholder::Title,
+            "body" => Placeholder::Body,
+            "toc" => Placeholder::Toc,
+            "meta" => Placeholder::Meta,
+            "lang" => Placeholder::Lang,
+            "root" => Placeholder::Root,
+            "date" => Placeholder::Date,
+            "head" => Placeholder::Head,
+            "prev" => Placeholder::Prev,
+            "prev.url" => Placeholder::PrevUrl,
+            "prev.title" => Placeholder::PrevTitle,
+            "next" => Placeholder::Next,
+            "next.url" => Placeholder::NextUrl,
+            "next.title" => Placeholder::NextTitle,
+            "breadcrumbs" => Placeholder::Breadcrumbs,
+            _ => match name.strip_prefix("meta.") {
+                Some(meta_name) => Placeholder::MetaValue(meta_name.to_string()),
+                None => {
+                    issues.push(AnyTemplateIssue::UnknownPlaceholder(begin, cursor.pos()));
+                    continue;
+                }
+            }
+        };
+        if matches!(placeholder, Placeholder::Body) {
+            body_count += 1;
+            if body_count > 1 {
+                issues.push(AnyTemplateIssue::DuplicateBody(begin, cursor.pos()));
+            }
+        }
+        segments.push(Segment::Placeholder(placeholder));
+    }
+    segments.push(Segment::Literal(literal_begin..source.len()));
+
+    if body_count == 0 { issues.push(AnyTemplateIssue::MissingBody); }
+    return (segments, issues);
+}
+
+enum AnyTemplateIssue {
+    UnclosedPlaceholder(SourceLocation),
+    UnknownPlaceholder(SourceLocation, SourceLocation),
+    DuplicateBody(SourceLocation, SourceLocation),
+    MissingBody
+}
+
+impl AnyTemplateIssue {
+    fn to_issue<'a>(&self, source: &'a str, file: FileId) -> Issue<'a> {
+        match self {
+            AnyTemplateIssue::UnclosedPlaceholder(begin) => {
+                let end = SourceLocation { byte_pos: begin.byte_pos + 2, line_pos: begin.line_pos,
+                    colu_pos: begin.colu_pos + 2 };
+                let span = SourceSpan { source, file, begin: *begin, end };
+                let mut quote = AnnotatedSourceSection::from_span(&span);
+                quote.highlight(span.begin.byte_pos, span.end.byte_pos);
+                Issue {
+                    quote,
+                    title: "Template placeholder is never closed".into(),
+                    subtext: "There are no closing braces for the placeholder opened here. \
+                              The remainder of the template will be omitted.".into(),
+                    severity: Severity::Error,
+                    fix: None,
+                    elaborations: Vec::new()
+                }
+            },
+            AnyTemplateIssue::UnknownPlaceholder(begin, end) => {
+                let span = SourceSpan { source, file, begin: *begin, end: *end };
+                let mut quote = AnnotatedSourceSection::from_span(&span);
+                quote.highlight(span.begin.byte_pos, span.end.byte_pos);
+                Issue {
+                    quote,
+                    title: "Unknown template placeholder".into(),
+                    subtext: "The known placeholders are title, body, toc, meta, meta.<name>, \
+                              lang, root, date, head, prev, next, prev.url, prev.title, \
+                              next.url, next.title, and breadcrumbs.\n\
+                              This placeholder will be omitted.".into(),
+                    severity: Severity::Error,
+                    fix: None,
+                    elaborations: Vec::new()
+                }
+            },
+            AnyTemplateIssue::DuplicateBody(begin, end) => {
+                let span = SourceSpan { source, file, begin: *begin, end: *end };
+                let mut quote = AnnotatedSourceSection::from_span(&span);
+                quote.highlight(span.begin.byte_pos, span.end.byte_pos);
+                Issue {
+                    quote,
+                    title: "Template contains more than one body placeholder".into(),
+                    subtext: "The content of the document will be repeated in the finished document.".into(),
+                    severity: Severity::Warning,
+                    fix: None,
+                    elaborations: Vec::new()
+                }
+            },
+            AnyTemplateIssue::MissingBody => {
+                let span = SourceSpan { source, file, begin: SourceLocation::default(),
+                    end: SourceLocation::default() };
+                let mut quote = AnnotatedSourceSection::from_span(&span);
+                quote.limit = Some(0);
+                Issue {
+                    quote,
+                    title: "Template has no body placeholder".into(),
+                    subtext: "The content of the document will not appear in the finished document. \
+                              Place {{body}} where it belongs.".into(),
+                    severity: Severity::Error,
+                    fix: None,
+                    elaborations: Vec::new()
+                }
+            },
+        }
+    }
+}
@@ -112,7 +112,7 @@ fn parse_root<'a, 'b>(ctx: &mut ParseContext<'a, 'b>, stop_at: Option<&str>) ->
     macro_rules! push_pt { () => {
         if let Some(begin) = pt_begin {
             children.push(ast::AnyInline::Plain(ast::PlainText { span: SourceSpan { 
-                begin, end: ctx.line_cursor.pos(), source: ctx.line_cursor.source } }));
+                begin, end: ctx.line_cursor.pos(), source: ctx.line_cursor.source, file: ctx.line_cursor.file } }));
             pt_begin = None;
         }
     }}
@@ -178,7 +178,7 @@ fn parse_trailing_qualifier<'a, 'b>(ctx: &mut ParseContext<'a, 'b>)
     macro_rules! push_tag { () => {
         if let Some(begin) = current_tag_begin {
             if begin.byte_pos < ctx.line_cursor.pos().byte_pos {
-                let span = SourceSpan { source: ctx.line_cursor.source, begin,
+                let span = SourceSpan { source: ctx.line_cursor.source, file: ctx.line_cursor.file, begin,
                     end: ctx.line_cursor.pos() };
                 let tag = match is_split {
                     true => ast::Tag::Split(ast::SplitTag { span }),
@@ -248,7 +248,7 @@ fn parse_inline_verbatim<'a, 'b>(ctx: &mut ParseContext<'a, 'b>) -> ast::InlineV
     macro_rules! push_span { () => {
         if span_begin.byte_pos < ctx.line_cursor.pos().byte_pos {
             inner_spans.push(SourceSpan { begin: span_begin, end: ctx.line_cursor.pos(), 
-                source: ctx.line_cursor.source });
+                source: ctx.line_cursor.source, file: ctx.line_cursor.file });
             span_begin = ctx.line_cursor.pos();
         }
     }}
@@ -267,7 +267,7 @@ fn parse_inline_verbatim<'a, 'b>(ctx: &mut ParseContext<'a, 'b>) -> ast::InlineV
         }
         ctx.line_cursor.pop_grapheme().unwrap();
     }
-    let span = SourceSpan { source: ctx.line_cursor.source, begin: total_begin, end:
+    let span = SourceSpan { source: ctx.line_cursor.source, file: ctx.line_cursor.file, begin: total_begin, end:
         ctx.line_cursor.pos() };
     return ast::InlineVerbatim { inner_spans, trailing_qualifier, close, open, span }
 }
@@ -306,7 +306,7 @@ fn spell_delim(delim: ast::DelimiterKind) -> &'static str {
     }
 }
 
-use crate::report::{Issue, AnnotatedSourceSection, Severity, BarrierStyle};
+use crate::report::{Issue, AnnotatedSourceSection, Severity, BarrierStyle, Fix};
 use crate::scanner;
 
 pub enum AnyTTreeIssue<'a, 'b> {
@@ -402,6 +402,17 @@ pub fn verify_trailing_qualifier<'a, 'b>(node_opt: &'a Option<ast::TrailingQuali
     }
 }
 
+impl<'a, 'b> AnyTTreeIssue<'a, 'b> {
+    /// Returns the name by which the severity of this issue is configured, or `None` if this
+    /// issue is an error, since the severity of errors cannot be configured.
+    pub fn lint_name(&self) -> Option<&'static str> {
+        match self {
+            AnyTTreeIssue::LongVerbatimCloseWarning(_) => Some("long-verbatim-close"),
+            _ => None
+        }
+    }
+}
+
 impl<'a, 'b> From<AnyTTreeIssue<'a, 'b>> for Issue<'b> {
     fn from(value: AnyTTreeIssue<'a, 'b>) -> Self {
         match value {
@@ -422,9 +433,10 @@ impl<'a, 'b> From<SplitTagError<'a, 'b>> for Issue<'b> {
             "split tag begins here");
         Issue {
             quote,
-            title: "Trailing qualifier tag is split",
-            subtext: "A tag cannot be split over a linebreak or contain spaces.",
+            title: "Trailing qualifier tag is split".into(),
+            subtext: "A tag cannot be split over a linebreak or contain spaces.".into(),
             severity: Severity::Error,
+            fix: None,
             elaborations: Vec::new()
         }
     }
@@ -434,11 +446,15 @@ impl<'a, 'b> From<UnclosedDelimiterError<'a, 'b>> for Issue<'b> {
     fn from(value: UnclosedDelimiterError<'a, 'b>) -> Self {
         let mut quote = AnnotatedSourceSection::from_span(&value.node.open);
         quote.highlight(value.node.open.begin.byte_pos, value.node.open.end.byte_pos);
+        let delim = spell_delim(value.node.delim_kind);
+        let fix = close_fix(&value.node.open, &value.node.child_root, delim, 
+            format!("add closing `{}`", delim));
         Issue {
             quote,
-            title: "Delimited text span is never closed",
-            subtext: "There is no closing delimiter for the span opened here...",
+            title: "Delimited text span is never closed".into(),
+            subtext: "There is no closing delimiter for the span opened here...".into(),
             severity: Severity::Error,
+            fix: Some(fix),
             elaborations: Vec::new()
         }
     }
@@ -448,11 +464,14 @@ impl<'a, 'b> From<UnclosedBracketError<'a, 'b>> for Issue<'b> {
     fn from(value: UnclosedBracketError<'a, 'b>) -> Self {
         let mut quote = AnnotatedSourceSection::from_span(&value.node.open);
         quote.highlight(value.node.open.begin.byte_pos, value.node.open.end.byte_pos);
+        let fix = close_fix(&value.node.open, &value.node.child_root, "]",
+            String::from("add closing `]`"));
         Issue {
             quote,
-            title: "Bracketed text span is never closed",
-            subtext: "There is no closing bracket for the span opened here...",
+            title: "Bracketed text span is never closed".into(),
+            subtext: "There is no closing bracket for the span opened here...".into(),
             severity: Severity::Error,
+            fix: Some(fix),
             elaborations: Vec::new()
         }
     }
@@ -465,9 +484,11 @@ impl<'a, 'b> From<LongVerbatimCloseWarning<'a, 'b>> for Issue<'b> {
         quote.highlight(value.close.begin.byte_pos, value.close.end.byte_pos);
         Issue { 
             quote,
-            title: "Too many closing backticks",
-            subtext: "The number of closing backticks should equal the number of opening backticks.",
+            title: format!("Too many closing backticks, expected {} but found {}",
+                value.node.open.as_ref().len(), value.close.as_ref().len()).into(),
+            subtext: "The number of closing backticks should equal the number of opening backticks.".into(),
             severity: Severity::Warning,
+            fix: Some(crate::ltree::shorten_close_fix(value.node.open, value.close)),
             elaborations: Vec::new()
         }
     }
@@ -481,10 +502,52 @@ impl<'a, 'b> From<UnclosedTrailingQualifierError<'a, 'b>> for Issue<'b> {
         quote.highlight(value.node.open.begin.byte_pos, value.node.open.end.byte_pos);
         Issue {
             quote,
-            title: "Trailing qualifier is never closed",
-            subtext: "There is no closing bracket for the qualifier opened here.",
+            title: "Trailing qualifier is never closed".into(),
+            subtext: "There is no closing bracket for the qualifier opened here.".into(),
             severity: Severity::Error,
+            fix: None,
             elaborations: Vec::new()
         }
     }
 }
+
+/// Constructs a fix which closes the span opened by `open` by inserting `close` after the last
+/// node in `child_root`. The span likely extends further than the author intended, so the fix
+/// is not safe.
+fn close_fix<'b>(open: &SourceSpan<'b>, child_root: &ast::Root<'b>, close: &str, 
+    description: String) -> Fix 
+{
+    let insert_at = root_end(child_root).unwrap_or(open.end.byte_pos);
+    return Fix::from_edits(open.source, description, 
+        vec![(insert_at..insert_at, close.to_string())], false);
+}
+
+/// Returns the byte position immediately following the last node in `root`, or `None`
+/// if `root` contains only implicit spaces.
+fn root_end<'b>(root: &ast::Root<'b>) -> Option<usize> {
+    for child in root.children.iter().rev() {
+        let end = match child {
+            ast::AnyInline::Plain(plain) => plain.span.end.byte_pos,
+            ast::AnyInline::InlineVerbatim(verbatim) => verbatim.span.end.byte_pos,
+            ast::AnyInline::Delimited(delimited) => 
+                spanned_end(&delimited.open, &delimited.close, &delimited.child_root, 
+                    &delimited.trailing_qualifier),
+            ast::AnyInline::Bracketed(bracketed) => 
+                spanned_end(&bracketed.open, &bracketed.close, &bracketed.child_root, 
+                    &bracketed.trailing_qualifier),
+            ast::AnyInline::ImplicitSpace(_) => continue,
+        };
+        return Some(end);
+    }
+    return None;
+}
+
+fn spanned_end<'b>(open: &SourceSpan<'b>, close: &Option<SourceSpan<'b>>, child_root: &ast::Root<'b>,
+    trailing_qualifier: &Option<ast::TrailingQualifier<'b>>) -> usize
+{
+    if let Some(ast::TrailingQualifier { close: Some(qualifier_close), .. }) = trailing_qualifier {
+        return qualifier_close.end.byte_pos;
+    }
+    if let Some(close) = close { return close.end.byte_pos; }
+    return root_end(child_root).unwrap_or(open.end.byte_pos);
+}
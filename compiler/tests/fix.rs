@@ -0,0 +1,33 @@
+//! Checks that `--fix` and `merryc fmt` agree on documents whose only problems are stylistic.
+
+use merry_compiler::compile::fix_source;
+use merry_compiler::fmt::format_source;
+
+fn assert_fix_agrees_with_fmt(source: &str) {
+    let (fixed, _) = fix_source(source);
+    assert_eq!(fixed, format_source(source), "--fix and fmt disagree on {:?}", source);
+}
+
+#[test]
+fn fixes_at_the_same_offset_are_applied_in_separate_passes() {
+    assert_fix_agrees_with_fmt("-- a\n  -- b\n");
+    assert_fix_agrees_with_fmt("a\n  b\n     c\n");
+}
+
+#[test]
+fn excessive_vertical_space_is_fixed() {
+    assert_fix_agrees_with_fmt("# H\n\n\n\ntext\n");
+    assert_fix_agrees_with_fmt("a\n\n\n   b\n");
+    assert_fix_agrees_with_fmt("a\n\n  b\n\n\n  c\n");
+}
+
+#[test]
+fn indentation_is_fixed() {
+    assert_fix_agrees_with_fmt("a\n     b\n");
+    assert_fix_agrees_with_fmt("a\n   ```\n  x\n   ```\n");
+}
+
+#[test]
+fn blank_lines_ending_the_document_are_kept() {
+    assert_fix_agrees_with_fmt("a\n   b\n\n\n\n   c\n");
+}
@@ -0,0 +1,53 @@
+//! Checks the invariants of `merryc fmt`: formatting is idempotent, and the content of every
+//! line, including the content of backtick blocks, is preserved byte-for-byte.
+
+use merry_compiler::fmt::format_source;
+
+const SOURCES: &[&str] = &[
+    "# H\n\n\n\ntext\n",
+    "a\n     b\n",
+    "a\n\n  b\n\n\n  c\n",
+    "-- a\n  -- b\n",
+    ":: term\n  definition\n\n\n:: other\n     definition\n",
+    "a\n     ```\n     x  \n     \t y\n\n\n      z\n     ````{lang:rust}\n",
+    ":::\n  a\n\n\n  b\n:::::{aside}\n",
+    "```\nunclosed\n\n\n  x\n",
+];
+
+#[test]
+fn formatting_is_idempotent() {
+    for source in SOURCES {
+        let formatted = format_source(source);
+        assert_eq!(format_source(&formatted), formatted, "fmt is not idempotent on {:?}", source);
+    }
+}
+
+#[test]
+fn backtick_block_content_is_preserved() {
+    let source = "a\n     ```\n     x  \n     \t y\n\n\n      z\n     ```{lang:rust}\n";
+    let formatted = format_source(source);
+    let content: Vec<&str> = formatted.lines()
+        .skip_while(|line| !line.trim_start().starts_with("```"))
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with("```"))
+        .map(|line| line.strip_prefix("   ").unwrap_or(line))
+        .collect();
+    assert_eq!(content, ["x  ", "\t y", "", "", " z"]);
+}
+
+#[test]
+fn line_content_is_preserved() {
+    for source in SOURCES {
+        let formatted = format_source(source);
+        let original: Vec<&str> = source.lines().map(str::trim).filter(|line| line.len() > 0)
+            .collect();
+        let preserved: Vec<&str> = formatted.lines().map(str::trim).filter(|line| line.len() > 0)
+            .collect();
+        assert_eq!(preserved.len(), original.len(), "fmt changed the lines of {:?}", source);
+        for (preserved, original) in preserved.iter().zip(&original) {
+            // Closing declarators and fences are lengthened to match the opening ones.
+            if original.starts_with("```") || original.starts_with(":::") { continue; }
+            assert_eq!(preserved, original, "fmt changed a line of {:?}", source);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Drives `merryc lsp` over stdin and stdout, the way an editor would.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{ChildStdin, ChildStdout, Command, Stdio};
+use serde_json::{json, Value};
+
+fn send(stdin: &mut ChildStdin, message: Value) {
+    let body = message.to_string();
+    write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    stdin.flush().unwrap();
+}
+
+fn receive(stdout: &mut BufReader<ChildStdout>) -> Value {
+    let mut content_length: usize = 0;
+    loop {
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.len() == 0 { break; }
+        if let Some(value) = line.strip_prefix("Content-Length: ") {
+            content_length = value.parse().unwrap();
+        }
+    }
+    let mut body = vec![0; content_length];
+    stdout.read_exact(&mut body).unwrap();
+    return serde_json::from_slice(&body).unwrap();
+}
+
+/// Receives messages until the response to the request with the given `id`, skipping the
+/// notifications sent meanwhile, like published diagnostics.
+fn receive_response(stdout: &mut BufReader<ChildStdout>, id: i64) -> Value {
+    loop {
+        let message = receive(stdout);
+        if message["id"] == json!(id) { return message; }
+    }
+}
+
+#[test]
+fn hover_on_a_tagged_span_names_its_target() {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_merryc"))
+        .arg("lsp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = server.stdin.take().unwrap();
+    let mut stdout = BufReader::new(server.stdout.take().unwrap());
+    let uri = "file:///tmp/hover.md2";
+    let text = "See the [usage guide]{usage} first.\n\n| ref usage guide.md2#usage\n";
+
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize",
+        "params": { "capabilities": {} } }));
+    let response = receive_response(&mut stdout, 1);
+    assert_eq!(response["result"]["capabilities"]["hoverProvider"], json!(true));
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }));
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen",
+        "params": { "textDocument": { "uri": uri, "languageId": "md2", "version": 1,
+        "text": text } } }));
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover",
+        "params": { "textDocument": { "uri": uri }, "position": { "line": 0, "character": 12 } } }));
+    let response = receive_response(&mut stdout, 2);
+    assert_eq!(response["result"]["contents"]["value"], json!("Refers to `guide.md2#usage`"));
+
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }));
+    let response = receive_response(&mut stdout, 3);
+    assert_eq!(response["result"], Value::Null);
+    send(&mut stdin, json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert!(server.wait().unwrap().success());
+}
@@ -0,0 +1,22 @@
+//! Checks that text exported as Markdown cannot be mistaken for block syntax.
+
+use merry_compiler::compile::{compile_str, Options, OutputFormat};
+
+fn export(source: &str) -> String {
+    let options = Options { format: OutputFormat::Markdown, ..Options::default() };
+    let (output, _) = compile_str(source, &options);
+    return output;
+}
+
+#[test]
+fn block_syntax_at_paragraph_start_is_escaped() {
+    // A line beginning with `#` is a heading in Merry too, so it is wrapped in a tagged span.
+    let source = "1. x\n\n12) x\n\n- x\n\n[# x]{t}\n\n> x\n";
+    assert_eq!(export(source), "1\\. x\n\n12\\) x\n\n\\- x\n\n\\# x\n\n\\> x\n");
+}
+
+#[test]
+fn block_syntax_at_verbatim_line_start_is_escaped() {
+    let source = "```\n1. x\n12) x\n- x\n# x\n> x\n```\n";
+    assert_eq!(export(source), "1\\. x\\\n12\\) x\\\n\\- x\\\n\\# x\\\n\\> x\n");
+}